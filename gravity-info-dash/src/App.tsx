@@ -9,7 +9,7 @@ import {
   CardSubtitle,
   Table,
 } from "reactstrap";
-import { Attestation, BatchFees, BatchTransaction, ChainTotalSupplyNumbers, Erc20Metadata, EthInfo, GravityInfo, DepositWithMetadata, TransactionBatch, VolumeInfo } from './types';
+import { Attestation, BatchFees, BatchTransaction, Cached, ChainTotalSupplyNumbers, Erc20Metadata, EthInfo, GravityInfo, DepositWithMetadata, TransactionBatch, VolumeInfo } from './types';
 // 5 seconds
 const UPDATE_TIME = 5000;
 
@@ -17,6 +17,14 @@ const BACKEND_PORT = 9000;
 export const SERVER_URL =
   "https://" + window.location.hostname + ":" + BACKEND_PORT + "/";
 
+// cached endpoints wrap their data in a freshness envelope, errors are bare strings
+function unwrapCached<T>(json: Cached<T> | string): T | string {
+  if (typeof (json) === "string") {
+    return json
+  }
+  return json.data
+}
+
 function App() {
   document.title = "Gravity Bridge Info"
   const [gravityBridgeInfo, setGravityBridgeInfo] = useState<GravityInfo | null>(null);
@@ -37,7 +45,7 @@ function App() {
 
     const result = await fetch(request_url, requestOptions);
     const json = await result.json();
-    setGravityBridgeInfo(json)
+    setGravityBridgeInfo(unwrapCached(json) as GravityInfo)
   }
   async function getEthInfo() {
     let request_url = SERVER_URL + "eth_bridge_info";
@@ -50,7 +58,7 @@ function App() {
     };
 
     const result = await fetch(request_url, requestOptions);
-    const json: EthInfo = await result.json();
+    const json = unwrapCached<EthInfo>(await result.json()) as EthInfo;
     // reverse so these show up in reverse cronological order
     json.batch_events.reverse()
     json.deposit_events.reverse()
@@ -70,7 +78,7 @@ function App() {
 
     const result = await fetch(request_url, requestOptions);
     const json = await result.json();
-    setSupplyInfo(json)
+    setSupplyInfo(unwrapCached(json) as ChainTotalSupplyNumbers)
   }
   async function getErc20Metadata() {
     let request_url = SERVER_URL + "erc20_metadata";
@@ -84,7 +92,7 @@ function App() {
 
     const result = await fetch(request_url, requestOptions);
     const json = await result.json();
-    setErc20Metadata(json)
+    setErc20Metadata(unwrapCached(json) as Array<Erc20Metadata>)
   }
   async function getVolumeInfo() {
     let request_url = SERVER_URL + "bridge_volume";
@@ -98,7 +106,7 @@ function App() {
 
     const result = await fetch(request_url, requestOptions);
     const json = await result.json();
-    setVolumeInfo(json)
+    setVolumeInfo(unwrapCached(json) as VolumeInfo)
   }
 
 
@@ -146,4 +146,11 @@ export type VolumeInfo = {
     weekly_volume: number,
     weekly_inflow: number,
     weekly_outflow: number,
-}
\ No newline at end of file
+}
+export type Cached<T> = {
+    data: T,
+    updated_at: number,
+    gravity_height: number | null,
+    eth_height: number | null,
+    next_refresh: number | null
+}
@@ -2,9 +2,7 @@ use actix_rt::System;
 use actix_web::{web, HttpResponse, Responder};
 use clarity::utils::bytes_to_hex_str;
 use clarity::{Address as EthAddress, Uint256};
-use cosmos_gravity::query::{
-    get_gravity_params, get_latest_transaction_batches, get_transaction_batch_signatures,
-};
+use cosmos_gravity::query::get_gravity_params;
 use ethereum_gravity::message_signatures::encode_tx_batch_confirm_hashed;
 use ethereum_gravity::submit_batch::encode_batch_payload;
 use gravity_proto::gravity::v1::query_client::QueryClient as GravityQueryClient;
@@ -14,11 +12,15 @@ use relayer::find_latest_valset::find_latest_valset;
 use rocksdb::DB;
 use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use web30::client::Web3;
 
-use crate::gravity_info::{ETH_NODE_RPC, GRAVITY_NODE_GRPC, REQUEST_TIMEOUT};
+use crate::config::{get_config, redact_url};
+use crate::gravity_info::REQUEST_TIMEOUT;
+use crate::metrics::{record_collector_run, VALSET_COLLECTOR};
 use crate::transactions::database::{load_last_valset, save_last_valset};
+use crate::upstream::failover::{rank_endpoints, FailoverEthereum, FailoverGravity};
+use crate::upstream::{EthereumSource, GravitySource};
 
 #[derive(Debug)]
 pub enum BatchRelayError {
@@ -31,7 +33,13 @@ pub async fn generate_batch_tx_responder(
     db: web::Data<Arc<DB>>,
 ) -> impl Responder {
     let db = db.get_ref().clone();
-    let res = generate_raw_batch_tx(batch_nonce, db).await;
+    let config = get_config();
+    let web3 = FailoverEthereum::from_config(&config.ethereum, REQUEST_TIMEOUT);
+    let gravity = match FailoverGravity::from_config(&config.gravity, REQUEST_TIMEOUT) {
+        Ok(v) => v,
+        Err(e) => return HttpResponse::InternalServerError().json(format!("{:?}", e)),
+    };
+    let res = generate_raw_batch_tx(batch_nonce, db, &gravity, &web3).await;
     match res {
         Ok(payload) => HttpResponse::Ok().json(bytes_to_hex_str(&payload)),
         Err(BatchRelayError::ServerError(e)) => HttpResponse::InternalServerError().json(e),
@@ -42,18 +50,10 @@ pub async fn generate_batch_tx_responder(
 pub async fn generate_raw_batch_tx(
     batch_nonce: u64,
     db: Arc<DB>,
+    gravity: &dyn GravitySource,
+    web3: &dyn EthereumSource,
 ) -> Result<Vec<u8>, BatchRelayError> {
-    let web3 = Web3::new(ETH_NODE_RPC, REQUEST_TIMEOUT);
-    let mut grpc = loop {
-        match GravityQueryClient::connect(GRAVITY_NODE_GRPC).await {
-            Ok(client) => break client,
-            Err(e) => {
-                error!("Failed to connect to the GRPC server: {:?}", e);
-                tokio::time::sleep(Duration::from_secs(3)).await;
-            }
-        }
-    };
-    let params = match get_gravity_params(&mut grpc).await {
+    let params = match gravity.params().await {
         Ok(p) => p,
         Err(_) => {
             return Err(BatchRelayError::ServerError(
@@ -63,7 +63,7 @@ pub async fn generate_raw_batch_tx(
     };
 
     // find the target batch and check that it's not timed out
-    let latest_eth_height = match web3.eth_block_number().await {
+    let latest_eth_height = match web3.block_number().await {
         Ok(bn) => bn,
         Err(_) => {
             return Err(BatchRelayError::ServerError(
@@ -71,7 +71,7 @@ pub async fn generate_raw_batch_tx(
             ))
         }
     };
-    let latest_batches = match get_latest_transaction_batches(&mut grpc).await {
+    let latest_batches = match gravity.latest_transaction_batches().await {
         Ok(v) => v,
         Err(_) => {
             return Err(BatchRelayError::ServerError(
@@ -101,13 +101,10 @@ pub async fn generate_raw_batch_tx(
         }
     };
 
-    let sigs = get_transaction_batch_signatures(
-        &mut grpc,
-        target_batch.nonce,
-        target_batch.token_contract,
-    )
-    .await
-    .expect("Failed to get sigs for batch!");
+    let sigs = gravity
+        .transaction_batch_signatures(target_batch.nonce, target_batch.token_contract)
+        .await
+        .expect("Failed to get sigs for batch!");
     if sigs.is_empty() {
         return Err(BatchRelayError::ServerError(
             "Failed to get sigs for batch".to_string(),
@@ -151,21 +148,49 @@ pub fn valset_update_thread(db: Arc<DB>) {
         runner.block_on(async move {
             const SLEEP_TIME: Duration = Duration::from_secs(3600);
             const ERROR_SLEEP_TIME: Duration = Duration::from_secs(60);
-            let web3 = Web3::new(ETH_NODE_RPC, REQUEST_TIMEOUT);
+            let config = get_config();
             loop {
-                let mut grpc = loop {
-                    match GravityQueryClient::connect(GRAVITY_NODE_GRPC).await {
-                        Ok(client) => break client,
-                        Err(e) => {
-                            error!("Failed to connect to the GRPC server: {:?}", e);
-                            tokio::time::sleep(ERROR_SLEEP_TIME).await;
+                let start = Instant::now();
+                // find_latest_valset needs concrete clients, so connect directly to the endpoints
+                // the failover sources used by the other threads currently rank best
+                let web3 = match rank_endpoints(&config.ethereum.rpc, config.ethereum.max_block_lag)
+                    .first()
+                {
+                    Some(url) => Web3::new(url, REQUEST_TIMEOUT),
+                    None => {
+                        error!("No Ethereum RPC endpoints configured!");
+                        record_collector_run(VALSET_COLLECTOR, start, false);
+                        thread::sleep(ERROR_SLEEP_TIME);
+                        continue;
+                    }
+                };
+                let mut grpc = None;
+                for url in rank_endpoints(&config.gravity.grpc, config.gravity.max_block_lag) {
+                    match GravityQueryClient::connect(url.clone()).await {
+                        Ok(client) => {
+                            grpc = Some(client);
+                            break;
                         }
+                        Err(e) => error!(
+                            "Failed to connect to the GRPC server {}: {:?}",
+                            redact_url(&url),
+                            e
+                        ),
+                    }
+                }
+                let mut grpc = match grpc {
+                    Some(client) => client,
+                    None => {
+                        record_collector_run(VALSET_COLLECTOR, start, false);
+                        tokio::time::sleep(ERROR_SLEEP_TIME).await;
+                        continue;
                     }
                 };
                 let params = match get_gravity_params(&mut grpc).await {
                     Ok(p) => p,
                     Err(_) => {
                         error!("Failed to get gravity params!");
+                        record_collector_run(VALSET_COLLECTOR, start, false);
                         thread::sleep(ERROR_SLEEP_TIME);
                         continue;
                     }
@@ -175,6 +200,7 @@ pub fn valset_update_thread(db: Arc<DB>) {
                         Ok(a) => a,
                         Err(_) => {
                             error!("Failed to parse Gravity Address?");
+                            record_collector_run(VALSET_COLLECTOR, start, false);
                             thread::sleep(ERROR_SLEEP_TIME);
                             continue;
                         }
@@ -184,12 +210,14 @@ pub fn valset_update_thread(db: Arc<DB>) {
                         Ok(v) => v,
                         Err(_) => {
                             error!("Failed to get valset for batch");
+                            record_collector_run(VALSET_COLLECTOR, start, false);
                             thread::sleep(ERROR_SLEEP_TIME);
                             continue;
                         }
                     };
                 save_last_valset(&db, &valset);
                 info!("Saved valset {} to db", valset.nonce);
+                record_collector_run(VALSET_COLLECTOR, start, true);
                 thread::sleep(SLEEP_TIME);
             }
         });
@@ -199,13 +227,42 @@ pub fn valset_update_thread(db: Arc<DB>) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::upstream::fake::{FakeEthereum, FakeGravity, TestDb, ETH_LATEST_BLOCK};
+    use gravity_utils::types::Erc20Token;
+    use web30::amm::USDC_CONTRACT_ADDRESS;
+
+    #[actix_web::test]
+    async fn test_batch_relay_offline() {
+        let db = TestDb::open("batch_relay_offline");
+        let mut gravity = FakeGravity::fixture();
+        let web3 = FakeEthereum::fixture();
+
+        let res = generate_raw_batch_tx(5, db.shared(), &gravity, &web3).await;
+        assert!(matches!(res, Err(BatchRelayError::BadRequest(_))));
+
+        gravity.batches.push(TransactionBatch {
+            nonce: 5,
+            batch_timeout: ETH_LATEST_BLOCK - 1,
+            transactions: Vec::new(),
+            total_fee: Erc20Token {
+                amount: 0u8.into(),
+                token_contract_address: *USDC_CONTRACT_ADDRESS,
+            },
+            token_contract: *USDC_CONTRACT_ADDRESS,
+        });
+        let res = generate_raw_batch_tx(5, db.shared(), &gravity, &web3).await;
+        assert!(matches!(res, Err(BatchRelayError::BadRequest(e)) if e == "Batch has timed out!"));
+    }
 
     #[ignore]
     #[actix_web::test]
     async fn test_batch_relay_query() {
         let start = std::time::Instant::now();
-        let db = Arc::new(DB::open_default("test_db").unwrap());
-        let res = generate_raw_batch_tx(38628, db).await;
+        let db = TestDb::open("batch_relay_query");
+        let config = get_config();
+        let web3 = FailoverEthereum::from_config(&config.ethereum, REQUEST_TIMEOUT);
+        let gravity = FailoverGravity::from_config(&config.gravity, REQUEST_TIMEOUT).unwrap();
+        let res = generate_raw_batch_tx(38628, db.shared(), &gravity, &web3).await;
         println!(
             "Got batch response {:?} in {}s",
             res.unwrap(),
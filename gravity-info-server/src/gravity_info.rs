@@ -2,37 +2,36 @@
 //! and using this info to update global lazy static data in memory, this decouples requests
 //! for info from the actual info gathering and makes queries dramatically more scalable.
 
+use crate::config::get_config;
+use crate::health::{record_update, unix_now, Store};
+use crate::metrics::{record_collector_run, GRAVITY_INFO_COLLECTOR};
+use crate::transactions::handlers::decode_claim;
+use crate::upstream::failover::{FailoverEthereum, FailoverGravity};
+use crate::upstream::{gravity_height, EthereumSource, GravitySource};
 use actix_web::rt::System;
 use clarity::{Address as EthAddress, Uint256};
-use cosmos_gravity::query::{
-    get_attestations, get_gravity_params, get_latest_transaction_batches, get_pending_batch_fees,
-};
-use deep_space::{Address, Coin, Contact};
+use deep_space::{Address, Coin};
 use futures::future::{join, join5, join_all};
 use futures::join;
-use gravity_proto::gravity::v1::query_client::QueryClient as GravityQueryClient;
 use gravity_proto::gravity::v1::{
-    Attestation, BatchFees, Params as GravityParams, QueryDenomToErc20Request,
+    Attestation, BatchFees, Params as GravityParams, PendingIbcAutoForward,
 };
 use gravity_utils::error::GravityError;
 use gravity_utils::types::{event_signatures::*, *};
 use gravity_utils::types::{SendToCosmosEvent, TransactionBatch};
 use log::{error, info, trace};
+use rocksdb::DB;
 use serde::Serialize;
 use std::sync::{Arc, RwLock};
 use std::thread;
-use std::time::Duration;
-use tonic::transport::channel::Channel;
+use std::time::{Duration, Instant};
 use web30::amm::USDC_CONTRACT_ADDRESS;
-use web30::client::Web3;
+use web30::types::Log;
 
 const LOOP_TIME: Duration = Duration::from_secs(60);
 pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
-pub const GRAVITY_NODE_GRPC: &str = "https://gravitychain.io:9090";
-//pub const GRAVITY_NODE_GRPC: &str = "http://chainripper-2.althea.net:9090";
-pub const GRAVITY_PREFIX: &str = "gravity";
-pub const ETH_NODE_RPC: &str = "https://eth.althea.net";
-pub const FINALITY_DELAY: u128 = 100;
+/// Number of recent Ethereum blocks whose events are kept in the Ethereum info
+pub const ETH_INFO_BLOCKS: u64 = 7_200;
 /// number of seconds per eth block
 pub const ETH_BLOCK_TIME: u128 = 12;
 
@@ -43,6 +42,8 @@ pub struct GravityInfo {
     pub pending_tx: Vec<InternalBatchFees>,
     pub pending_batches: Vec<TransactionBatch>,
     pub attestations: Vec<InteralAttestation>,
+    /// Observed deposits waiting to be forwarded over IBC to another chain
+    pub pending_ibc_auto_forwards: Vec<InternalIbcAutoForward>,
     pub params: InternalGravityParams,
 }
 
@@ -55,6 +56,8 @@ pub struct EthInfo {
     pub erc20_deploys: Vec<Erc20DeployedEvent>,
     pub logic_calls: Vec<LogicCallExecutedEvent>,
     pub latest_eth_block: Uint256,
+    /// Events up to this block can no longer be rolled back by a reorg
+    pub latest_finalized_eth_block: Uint256,
 }
 
 lazy_static! {
@@ -97,72 +100,103 @@ pub fn blockchain_info_thread() {
     thread::spawn(move || loop {
         let runner = System::new();
         runner.block_on(async move {
-            let web30 = Web3::new(ETH_NODE_RPC, REQUEST_TIMEOUT);
-            let contact = Contact::new(GRAVITY_NODE_GRPC, REQUEST_TIMEOUT, GRAVITY_PREFIX).unwrap();
-
-            let mut grpc_client = loop {
-                match GravityQueryClient::connect(GRAVITY_NODE_GRPC).await {
-                    Ok(client) => break client,
-                    Err(e) => {
-                        error!("Failed to connect to the GRPC server: {:?}", e);
-                        tokio::time::sleep(Duration::from_secs(3)).await;
-                    }
-                }
-            };
-
-            let gravity_contract_address = match query_gravity_info(&mut grpc_client).await {
-                Ok(v) => {
-                    let bridge_eth_address = v.params.bridge_ethereum_address;
-                    set_gravity_info(v);
-                    info!("Successfully updated Gravity info");
-                    bridge_eth_address
-                }
+            let start = Instant::now();
+            let config = get_config();
+            let web30 = FailoverEthereum::from_config(&config.ethereum, REQUEST_TIMEOUT);
+            let gravity = match FailoverGravity::from_config(&config.gravity, REQUEST_TIMEOUT) {
+                Ok(v) => v,
                 Err(e) => {
-                    error!("Failed to update Gravity Info with {:?}", e);
+                    error!("Invalid Gravity GRPC endpoint {:?}", e);
+                    record_collector_run(GRAVITY_INFO_COLLECTOR, start, false);
                     return;
                 }
             };
-            let eth_info = query_eth_info(&web30, gravity_contract_address);
-            let erc20_metadata = get_all_erc20_metadata(&contact, &web30, &mut grpc_client);
-            let (eth_info, erc20_metadata) = join!(eth_info, erc20_metadata);
-            let (eth_info, erc20_metadata) = match (eth_info, erc20_metadata) {
-                (Ok(a), Ok(b)) => (a, b),
-                (_, Err(e)) => {
-                    error!("Failed to get erc20 metadata {:?}", e);
-                    return;
-                }
-                (Err(e), _) => {
-                    error!("Failed to get eth_info {:?}", e);
-                    return;
-                }
-            };
-
-            set_eth_info(eth_info);
-            set_erc20_metadata(erc20_metadata);
-            info!("Successfully updated Gravity and ETH info");
+            let success = update_blockchain_info(&gravity, &web30).await;
+            record_collector_run(GRAVITY_INFO_COLLECTOR, start, success);
         });
         thread::sleep(LOOP_TIME);
     });
 }
 
+/// Spawns a thread that hands `track` the latest Gravity and Ethereum info and the current unix
+/// time on every refresh of the info, for trackers that record what the info only shows while
+/// it is recent
+pub fn info_tracker_thread(
+    db: Arc<DB>,
+    track: fn(&DB, Option<&GravityInfo>, Option<&EthInfo>, i64),
+) {
+    thread::spawn(move || loop {
+        track(
+            &db,
+            get_gravity_info().as_ref(),
+            get_eth_info().as_ref(),
+            unix_now() as i64,
+        );
+        thread::sleep(LOOP_TIME);
+    });
+}
+
+/// A single iteration of the info loop, queries Gravity first since the Ethereum queries
+/// need the bridge contract address, then updates the in memory stores. Returns false if
+/// any of the stores could not be updated
+async fn update_blockchain_info(gravity: &dyn GravitySource, web30: &dyn EthereumSource) -> bool {
+    let gravity_height = gravity_height(gravity).await;
+    let gravity_contract_address = match query_gravity_info(gravity).await {
+        Ok(v) => {
+            let bridge_eth_address = v.params.bridge_ethereum_address;
+            set_gravity_info(v);
+            record_update(Store::GravityInfo, gravity_height, None, Some(LOOP_TIME));
+            info!("Successfully updated Gravity info");
+            bridge_eth_address
+        }
+        Err(e) => {
+            error!("Failed to update Gravity Info with {:?}", e);
+            return false;
+        }
+    };
+    let eth_info = query_eth_info(web30, gravity_contract_address);
+    let erc20_metadata = get_all_erc20_metadata(gravity, web30);
+    let (eth_info, erc20_metadata) = join!(eth_info, erc20_metadata);
+    let (eth_info, erc20_metadata) = match (eth_info, erc20_metadata) {
+        (Ok(a), Ok(b)) => (a, b),
+        (_, Err(e)) => {
+            error!("Failed to get erc20 metadata {:?}", e);
+            return false;
+        }
+        (Err(e), _) => {
+            error!("Failed to get eth_info {:?}", e);
+            return false;
+        }
+    };
+
+    let eth_height = eth_info.latest_eth_block.to_string().parse().ok();
+    set_eth_info(eth_info);
+    record_update(Store::EthInfo, None, eth_height, Some(LOOP_TIME));
+    set_erc20_metadata(erc20_metadata);
+    record_update(
+        Store::Erc20Metadata,
+        gravity_height,
+        eth_height,
+        Some(LOOP_TIME),
+    );
+    info!("Successfully updated Gravity and ETH info");
+    true
+}
+
 /// gets information about all tokens that have been bridged
 async fn get_all_erc20_metadata(
-    contact: &Contact,
-    web30: &Web3,
-    grpc_client: &mut GravityQueryClient<Channel>,
+    gravity: &dyn GravitySource,
+    web30: &dyn EthereumSource,
 ) -> Result<Vec<Erc20Metadata>, GravityError> {
-    let all_tokens_on_gravity = contact.query_total_supply().await?;
+    let all_tokens_on_gravity = gravity.total_supply().await?;
     let mut futs = Vec::new();
     for token in all_tokens_on_gravity {
         let erc20: EthAddress = if token.denom.starts_with("gravity") {
             token.denom.trim_start_matches("gravity").parse().unwrap()
         } else {
-            match grpc_client
-                .denom_to_erc20(QueryDenomToErc20Request { denom: token.denom })
-                .await
-            {
-                Ok(v) => v.into_inner().erc20.parse().unwrap(),
-                Err(_) => continue,
+            match gravity.denom_to_erc20(token.denom).await? {
+                Some(v) => v,
+                None => continue,
             }
         };
         futs.push(get_metadata(web30, erc20));
@@ -176,12 +210,12 @@ async fn get_all_erc20_metadata(
     Ok(metadata)
 }
 
-async fn get_metadata(web30: &Web3, erc20: EthAddress) -> Result<Erc20Metadata, GravityError> {
-    let query_sender: EthAddress = "0x388C818CA8B9251b393131C08a736A67ccB19297"
-        .parse()
-        .unwrap();
-    let symbol = web30.get_erc20_symbol(erc20, query_sender);
-    let decimals = web30.get_erc20_decimals(erc20, query_sender);
+async fn get_metadata(
+    web30: &dyn EthereumSource,
+    erc20: EthAddress,
+) -> Result<Erc20Metadata, GravityError> {
+    let symbol = web30.erc20_symbol(erc20);
+    let decimals = web30.erc20_decimals(erc20);
     let (symbol, decimals) = join(symbol, decimals).await;
     let (symbol, decimals) = (symbol?, decimals?);
 
@@ -199,25 +233,8 @@ async fn get_metadata(web30: &Web3, erc20: EthAddress) -> Result<Erc20Metadata,
     // one of whatever this token is
     let one: Uint256 = 10u128.pow(downcast_decimals).into();
 
-    let pricev3 = web30.get_uniswap_v3_price_with_retries(
-        query_sender,
-        erc20,
-        *USDC_CONTRACT_ADDRESS,
-        one,
-        None,
-        None,
-    );
-    let pricev2 =
-        web30.get_uniswap_v2_price(query_sender, erc20, *USDC_CONTRACT_ADDRESS, one, None);
-
-    let (pricev3, pricev2) = join(pricev3, pricev2).await;
-
     // the value of one unit of whatever this is in usdc
-    let exchange_rate = match (pricev3, pricev2) {
-        (Ok(r), _) => Some(r),
-        (_, Ok(r)) => Some(r),
-        (Err(_), Err(_)) => None,
-    };
+    let exchange_rate = web30.usdc_price(erc20, one).await.ok();
     Ok(Erc20Metadata {
         address: erc20,
         symbol,
@@ -235,19 +252,31 @@ pub struct Erc20Metadata {
     pub exchange_rate: Option<Uint256>,
 }
 
-async fn query_gravity_info(
-    grpc_client: &mut GravityQueryClient<Channel>,
-) -> Result<GravityInfo, GravityError> {
-    // can't be easily parallelized becuase of the grpc client :(
-    let pending_tx = get_pending_batch_fees(grpc_client).await?.batch_fees;
-    let pending_batches = get_latest_transaction_batches(grpc_client).await?;
-    let attestations = get_attestations(grpc_client, None).await?;
-    let params = get_gravity_params(grpc_client).await?;
+async fn query_gravity_info(gravity: &dyn GravitySource) -> Result<GravityInfo, GravityError> {
+    let (pending_tx, pending_batches, attestations, pending_ibc_auto_forwards, params) = join5(
+        gravity.pending_batch_fees(),
+        gravity.latest_transaction_batches(),
+        gravity.attestations(),
+        gravity.pending_ibc_auto_forwards(),
+        gravity.params(),
+    )
+    .await;
+    let (pending_tx, pending_batches, attestations, pending_ibc_auto_forwards, params) = (
+        pending_tx?,
+        pending_batches?,
+        attestations?,
+        pending_ibc_auto_forwards?,
+        params?,
+    );
 
     Ok(GravityInfo {
         pending_tx: pending_tx.into_iter().map(|b| b.into()).collect(),
         pending_batches,
         attestations: attestations.into_iter().map(|a| a.into()).collect(),
+        pending_ibc_auto_forwards: pending_ibc_auto_forwards
+            .into_iter()
+            .map(|f| f.into())
+            .collect(),
         params: params.into(),
     })
 }
@@ -276,6 +305,8 @@ pub struct InteralAttestation {
     pub height: u64,
     pub observed: bool,
     pub votes: u64,
+    /// Nonce of the Ethereum event attested to, None if the claim type is unknown
+    pub event_nonce: Option<u64>,
 }
 
 impl From<Attestation> for InteralAttestation {
@@ -284,6 +315,33 @@ impl From<Attestation> for InteralAttestation {
             height: a.height,
             observed: a.observed,
             votes: a.votes.len() as u64,
+            event_nonce: a
+                .claim
+                .and_then(decode_claim)
+                .map(|(_, event_nonce, _, _)| event_nonce),
+        }
+    }
+}
+
+/// A serializable version of a pending IBC auto forward
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct InternalIbcAutoForward {
+    pub event_nonce: u64,
+    pub foreign_receiver: String,
+    pub ibc_channel: String,
+    pub amount: String,
+    pub denom: String,
+}
+
+impl From<PendingIbcAutoForward> for InternalIbcAutoForward {
+    fn from(f: PendingIbcAutoForward) -> Self {
+        let token = f.token.unwrap_or_default();
+        InternalIbcAutoForward {
+            event_nonce: f.event_nonce,
+            foreign_receiver: f.foreign_receiver,
+            ibc_channel: f.ibc_channel,
+            amount: token.amount,
+            denom: token.denom,
         }
     }
 }
@@ -297,30 +355,27 @@ pub struct DepositWithMetadata {
     pub amount: Uint256,
     pub event_nonce: u64,
     pub block_height: Uint256,
-    // if this deposit is not yet executed on GB this will be false
+    /// Hash of the Ethereum transaction, None if the node did not report it
+    pub tx_hash: Option<String>,
+    /// True once the deposit is in a finalized block, orchestrators only claim finalized deposits
     pub confirmed: bool,
     pub blocks_until_confirmed: Uint256,
     pub seconds_until_confirmed: Uint256,
 }
 
 impl DepositWithMetadata {
-    /// Converts an event
+    /// Converts an event, the deposit is confirmed once it is in a finalized block
     pub fn convert(
         input: SendToCosmosEvent,
-        current_eth_height: Uint256,
+        tx_hash: Option<String>,
         current_final_height: Uint256,
     ) -> Option<Self> {
-        let finished = if input.block_height < current_final_height {
-            true
-        } else {
-            current_eth_height - input.block_height > FINALITY_DELAY.into()
-        };
-        // height at which Gravity will see this tx
-        let confirm_height = input.block_height + FINALITY_DELAY.into();
+        let finished = input.block_height <= current_final_height;
+        // the finalized block advances one block per block
         let blocks_until_confirmed: Uint256 = if finished {
             0u8.into()
         } else {
-            confirm_height - current_eth_height
+            input.block_height - current_final_height
         };
 
         if let Some(destination) = input.validated_destination {
@@ -331,6 +386,7 @@ impl DepositWithMetadata {
                 amount: input.amount,
                 event_nonce: input.event_nonce,
                 block_height: input.block_height,
+                tx_hash,
                 confirmed: finished,
                 blocks_until_confirmed,
                 seconds_until_confirmed: blocks_until_confirmed * ETH_BLOCK_TIME.into(),
@@ -385,43 +441,57 @@ impl From<GravityParams> for InternalGravityParams {
     }
 }
 
+/// The hash of the transaction that emitted `log`, 0x prefixed hex
+pub fn log_tx_hash(log: &Log) -> Option<String> {
+    log.transaction_hash
+        .as_ref()
+        .map(|h| format!("0x{}", hex::encode(&h[..])))
+}
+
+/// The hash of the block `log` was emitted in, None if the node did not report it
+pub fn log_block_hash(log: &Log) -> Option<String> {
+    log.block_hash
+        .as_ref()
+        .map(|h| format!("0x{}", hex::encode(&h[..])))
+}
+
 async fn query_eth_info(
-    web3: &Web3,
+    web3: &dyn EthereumSource,
     gravity_contract_address: EthAddress,
 ) -> Result<EthInfo, GravityError> {
-    let latest_block = web3.eth_block_number().await?;
-    let latest_finalized_block = web3.eth_get_finalized_block().await?.number;
-    let starting_block = latest_block - 7_200u16.into();
+    let latest_block = web3.block_number().await?;
+    let latest_finalized_block = web3.finalized_block_number().await?;
+    let starting_block = latest_block - ETH_INFO_BLOCKS.into();
 
-    let deposits = web3.check_for_events(
+    let deposits = web3.get_logs(
         starting_block,
-        Some(latest_block),
-        vec![gravity_contract_address],
-        vec![SENT_TO_COSMOS_EVENT_SIG],
+        latest_block,
+        gravity_contract_address,
+        SENT_TO_COSMOS_EVENT_SIG,
     );
-    let batches = web3.check_for_events(
+    let batches = web3.get_logs(
         starting_block,
-        Some(latest_block),
-        vec![gravity_contract_address],
-        vec![TRANSACTION_BATCH_EXECUTED_EVENT_SIG],
+        latest_block,
+        gravity_contract_address,
+        TRANSACTION_BATCH_EXECUTED_EVENT_SIG,
     );
-    let valsets = web3.check_for_events(
+    let valsets = web3.get_logs(
         starting_block,
-        Some(latest_block),
-        vec![gravity_contract_address],
-        vec![VALSET_UPDATED_EVENT_SIG],
+        latest_block,
+        gravity_contract_address,
+        VALSET_UPDATED_EVENT_SIG,
     );
-    let erc20_deployed = web3.check_for_events(
+    let erc20_deployed = web3.get_logs(
         starting_block,
-        Some(latest_block),
-        vec![gravity_contract_address],
-        vec![ERC20_DEPLOYED_EVENT_SIG],
+        latest_block,
+        gravity_contract_address,
+        ERC20_DEPLOYED_EVENT_SIG,
     );
-    let logic_call_executed = web3.check_for_events(
+    let logic_call_executed = web3.get_logs(
         starting_block,
-        Some(latest_block),
-        vec![gravity_contract_address],
-        vec![LOGIC_CALL_EVENT_SIG],
+        latest_block,
+        gravity_contract_address,
+        LOGIC_CALL_EVENT_SIG,
     );
     let (deposits, batches, valsets, erc20_deployed, logic_call_executed) = join5(
         deposits,
@@ -444,7 +514,8 @@ async fn query_eth_info(
     trace!("parsed valsets {:?}", valsets);
     let withdraws = TransactionBatchExecutedEvent::from_logs(&batches)?;
     trace!("parsed batches {:?}", batches);
-    let deposits = SendToCosmosEvent::from_logs(&deposits)?;
+    let deposit_logs = deposits;
+    let deposits = SendToCosmosEvent::from_logs(&deposit_logs)?;
     trace!("parsed deposits {:?}", deposits);
     let erc20_deploys = Erc20DeployedEvent::from_logs(&erc20_deployed)?;
     trace!("parsed erc20 deploys {:?}", erc20_deploys);
@@ -452,8 +523,10 @@ async fn query_eth_info(
     trace!("logic call executions {:?}", logic_calls);
 
     let mut deposit_events = Vec::new();
-    for d in deposits {
-        let d = DepositWithMetadata::convert(d, latest_block, latest_finalized_block);
+    // every log is parsed into one event, in order
+    for (d, log) in deposits.into_iter().zip(deposit_logs.iter()) {
+        let tx_hash = log_tx_hash(log);
+        let d = DepositWithMetadata::convert(d, tx_hash, latest_finalized_block);
         if let Some(d) = d {
             deposit_events.push(d);
         }
@@ -466,6 +539,7 @@ async fn query_eth_info(
         erc20_deploys,
         logic_calls,
         latest_eth_block: latest_block,
+        latest_finalized_eth_block: latest_finalized_block,
     })
 }
 
@@ -474,24 +548,89 @@ mod tests {
     use rustls::crypto::CryptoProvider;
 
     use super::*;
+    use crate::upstream::fake::{
+        FakeEthereum, FakeGravity, BRIDGE_ADDRESS, CLAIM_EVENT_NONCE, ETH_LATEST_BLOCK,
+        WETH_ADDRESS,
+    };
+
+    #[actix_web::test]
+    async fn test_gravity_info_offline() {
+        let info = query_gravity_info(&FakeGravity::fixture()).await.unwrap();
+        assert_eq!(
+            info.params.bridge_ethereum_address,
+            BRIDGE_ADDRESS.parse().unwrap()
+        );
+        assert_eq!(info.pending_tx.len(), 1);
+        assert_eq!(info.pending_tx[0].token, *USDC_CONTRACT_ADDRESS);
+        assert_eq!(info.attestations[0].votes, 3);
+        assert_eq!(info.attestations[0].event_nonce, Some(CLAIM_EVENT_NONCE));
+    }
 
+    #[actix_web::test]
+    async fn test_eth_info_offline() {
+        let info = query_eth_info(&FakeEthereum::fixture(), BRIDGE_ADDRESS.parse().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(info.latest_eth_block, ETH_LATEST_BLOCK.into());
+        assert_eq!(info.deposit_events.len(), 2);
+        // the first deposit is in a finalized block, the second is 42 blocks past the last one
+        assert!(info.deposit_events[0].confirmed);
+        assert!(!info.deposit_events[1].confirmed);
+        assert_eq!(info.deposit_events[1].blocks_until_confirmed, 42u8.into());
+        assert!(info.deposit_events[0]
+            .tx_hash
+            .as_ref()
+            .is_some_and(|h| h.starts_with("0x")));
+        assert_eq!(info.batch_events.len(), 1);
+        assert_eq!(info.batch_events[0].batch_nonce, 6312);
+    }
+
+    #[actix_web::test]
+    async fn test_erc20_metadata_offline() {
+        let mut gravity = FakeGravity::fixture();
+        // a bridged cosmos token that maps to WETH
+        gravity
+            .total_supply
+            .push(crate::upstream::fake::coin("ibc/weth", 1));
+        gravity
+            .denom_to_erc20
+            .insert("ibc/weth".to_string(), WETH_ADDRESS.parse().unwrap());
+        let res = get_all_erc20_metadata(&gravity, &FakeEthereum::fixture())
+            .await
+            .unwrap();
+        // ugraviton has no erc20 and is skipped
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].symbol, "USDC");
+        assert_eq!(res[0].exchange_rate, Some(1_000_000u32.into()));
+        assert_eq!(res[1].symbol, "WETH");
+        assert_eq!(res[1].exchange_rate, Some(1_300_000_000u64.into()));
+    }
+
+    #[actix_web::test]
+    async fn test_update_blockchain_info_offline() {
+        assert!(update_blockchain_info(&FakeGravity::fixture(), &FakeEthereum::fixture()).await);
+        assert!(get_gravity_info().is_some());
+        assert!(get_eth_info().is_some());
+        assert_eq!(get_erc20_metadata().unwrap().len(), 1);
+    }
+
+    #[ignore]
     #[actix_web::test]
     async fn test_gravity_info() {
         if CryptoProvider::get_default().is_none() {
             CryptoProvider::install_default(rustls::crypto::aws_lc_rs::default_provider()).unwrap();
         }
-        let mut grpc_client = GravityQueryClient::connect(GRAVITY_NODE_GRPC)
-            .await
-            .unwrap();
-        let _info = query_gravity_info(&mut grpc_client).await.unwrap();
+        let gravity = FailoverGravity::from_config(&get_config().gravity, REQUEST_TIMEOUT).unwrap();
+        let _info = query_gravity_info(&gravity).await.unwrap();
     }
 
+    #[ignore]
     #[actix_web::test]
     async fn test_eth_info() {
         if CryptoProvider::get_default().is_none() {
             CryptoProvider::install_default(rustls::crypto::aws_lc_rs::default_provider()).unwrap();
         }
-        let web3 = Web3::new("https://eth.althea.net", Duration::from_secs(60));
+        let web3 = FailoverEthereum::from_config(&get_config().ethereum, Duration::from_secs(60));
         let res = query_eth_info(
             &web3,
             "0xa4108aA1Ec4967F8b52220a4f7e94A8201F2D906"
@@ -502,17 +641,16 @@ mod tests {
         println!("{:?}", res);
     }
 
+    #[ignore]
     #[actix_web::test]
     async fn test_erc20_metadata() {
         if CryptoProvider::get_default().is_none() {
             CryptoProvider::install_default(rustls::crypto::aws_lc_rs::default_provider()).unwrap();
         }
-        let web3 = Web3::new("https://eth.althea.net", Duration::from_secs(60));
-        let contact = Contact::new(GRAVITY_NODE_GRPC, REQUEST_TIMEOUT, GRAVITY_PREFIX).unwrap();
-        let mut grpc_client = GravityQueryClient::connect(GRAVITY_NODE_GRPC)
-            .await
-            .unwrap();
-        let res = get_all_erc20_metadata(&contact, &web3, &mut grpc_client).await;
+        let config = get_config();
+        let web3 = FailoverEthereum::from_config(&config.ethereum, Duration::from_secs(60));
+        let gravity = FailoverGravity::from_config(&config.gravity, REQUEST_TIMEOUT).unwrap();
+        let res = get_all_erc20_metadata(&gravity, &web3).await;
         println!("{:?}", res);
     }
 }
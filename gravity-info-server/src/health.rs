@@ -0,0 +1,358 @@
+//! Tracks when each cached store was last updated and from which block heights, this backs the `/health`
+//! and `/ready` endpoints used by load balancers to drop instances serving stale data. `/health` fails if
+//! any store is older than its configured maximum age, `/ready` additionally fails until every store has
+//! been populated at least once. The same information is attached to every API response served from a
+//! store, in an `Envelope` and as `Last-Modified` and `ETag` headers.
+
+use crate::config::{get_config, HealthConfig};
+use crate::transactions::database::{load_last_download_block_updated, load_last_valset_updated};
+use actix_web::http::header::{self, EntityTag, Header, HttpDate, IfModifiedSince, IfNoneMatch};
+use actix_web::{get, HttpRequest, HttpResponse, Responder};
+use rocksdb::DB;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Store {
+    GravityInfo,
+    EthInfo,
+    Erc20Metadata,
+    Volume,
+    TotalSupply,
+    LastValset,
+    IndexerTip,
+}
+
+impl Store {
+    pub const ALL: [Store; 7] = [
+        Store::GravityInfo,
+        Store::EthInfo,
+        Store::Erc20Metadata,
+        Store::Volume,
+        Store::TotalSupply,
+        Store::LastValset,
+        Store::IndexerTip,
+    ];
+
+    fn max_age(&self, config: &HealthConfig) -> u64 {
+        match self {
+            Store::GravityInfo => config.gravity_info_max_age,
+            Store::EthInfo => config.eth_info_max_age,
+            Store::Erc20Metadata => config.erc20_metadata_max_age,
+            Store::Volume => config.volume_max_age,
+            Store::TotalSupply => config.total_supply_max_age,
+            Store::LastValset => config.last_valset_max_age,
+            Store::IndexerTip => config.indexer_tip_max_age,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Freshness {
+    /// Unix time in seconds
+    pub updated_at: u64,
+    /// Gravity block height the data was read at, if it was read from Gravity
+    pub gravity_height: Option<u64>,
+    /// Ethereum block height the data was read at, if it was read from Ethereum
+    pub eth_height: Option<u64>,
+    /// Unix time in seconds of the collector's next scheduled refresh, if it runs on a schedule
+    pub next_refresh: Option<u64>,
+}
+
+lazy_static! {
+    static ref FRESHNESS: Arc<RwLock<HashMap<Store, Freshness>>> =
+        Arc::new(RwLock::new(HashMap::new()));
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Marks `store` as updated now with data from the given heights, `refresh_interval` is how long
+/// the collector sleeps before updating it again
+pub fn record_update(
+    store: Store,
+    gravity_height: Option<u64>,
+    eth_height: Option<u64>,
+    refresh_interval: Option<Duration>,
+) {
+    let now = unix_now();
+    set_freshness(
+        store,
+        Freshness {
+            updated_at: now,
+            gravity_height,
+            eth_height,
+            next_refresh: refresh_interval.map(|i| now + i.as_secs()),
+        },
+    )
+}
+
+pub fn set_freshness(store: Store, freshness: Freshness) {
+    FRESHNESS.write().unwrap().insert(store, freshness);
+}
+
+pub fn get_freshness(store: Store) -> Option<Freshness> {
+    FRESHNESS.read().unwrap().get(&store).cloned()
+}
+
+/// The stores kept in RocksDB survive a restart, so their last update time is persisted
+/// alongside them and restored here on startup
+pub fn restore_from_db(db: &DB) {
+    if let Some(updated_at) = load_last_valset_updated(db) {
+        set_freshness(
+            Store::LastValset,
+            Freshness {
+                updated_at,
+                gravity_height: None,
+                eth_height: None,
+                next_refresh: None,
+            },
+        );
+    }
+    if let Some((height, updated_at)) = load_last_download_block_updated(db) {
+        set_freshness(
+            Store::IndexerTip,
+            Freshness {
+                updated_at,
+                gravity_height: Some(height),
+                eth_height: None,
+                next_refresh: None,
+            },
+        );
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StoreStatus {
+    pub store: Store,
+    pub updated_at: Option<u64>,
+    pub age_seconds: Option<u64>,
+    pub gravity_height: Option<u64>,
+    pub eth_height: Option<u64>,
+    pub max_age_seconds: u64,
+    pub stale: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    /// False if any populated store is older than its maximum age
+    pub healthy: bool,
+    /// True if healthy and every store has been populated
+    pub ready: bool,
+    pub stores: Vec<StoreStatus>,
+}
+
+pub fn health_report(config: &HealthConfig, now: u64) -> HealthReport {
+    let mut stores = Vec::new();
+    for store in Store::ALL {
+        let max_age = store.max_age(config);
+        let status = match get_freshness(store) {
+            Some(f) => {
+                let age = now.saturating_sub(f.updated_at);
+                StoreStatus {
+                    store,
+                    updated_at: Some(f.updated_at),
+                    age_seconds: Some(age),
+                    gravity_height: f.gravity_height,
+                    eth_height: f.eth_height,
+                    max_age_seconds: max_age,
+                    stale: age > max_age,
+                }
+            }
+            None => StoreStatus {
+                store,
+                updated_at: None,
+                age_seconds: None,
+                gravity_height: None,
+                eth_height: None,
+                max_age_seconds: max_age,
+                stale: false,
+            },
+        };
+        stores.push(status);
+    }
+    let healthy = stores.iter().all(|s| !s.stale);
+    let ready = healthy && stores.iter().all(|s| s.updated_at.is_some());
+    HealthReport {
+        healthy,
+        ready,
+        stores,
+    }
+}
+
+/// A cached store as returned by the API
+#[derive(Debug, Clone, Serialize)]
+pub struct Envelope<T> {
+    pub data: T,
+    /// Unix time in seconds the data was computed at
+    pub updated_at: u64,
+    pub gravity_height: Option<u64>,
+    pub eth_height: Option<u64>,
+    pub next_refresh: Option<u64>,
+}
+
+/// Responds with `data` read from `store` wrapped in an `Envelope`, or with 304 Not Modified
+/// if the client already has this version
+pub fn cached_response<T: Serialize>(req: &HttpRequest, store: Store, data: T) -> HttpResponse {
+    respond(req, store, get_freshness(store), data, true)
+}
+
+/// Like `cached_response` but returns `data` as is, for endpoints whose bare value is relied on by
+/// third parties, freshness is only reported in the headers
+pub fn cached_bare_response<T: Serialize>(
+    req: &HttpRequest,
+    store: Store,
+    data: T,
+) -> HttpResponse {
+    respond(req, store, get_freshness(store), data, false)
+}
+
+fn respond<T: Serialize>(
+    req: &HttpRequest,
+    store: Store,
+    freshness: Option<Freshness>,
+    data: T,
+    envelope: bool,
+) -> HttpResponse {
+    // the data is set just before its freshness is recorded, so briefly there may be none
+    let freshness = freshness.unwrap_or(Freshness {
+        updated_at: unix_now(),
+        gravity_height: None,
+        eth_height: None,
+        next_refresh: None,
+    });
+    let modified = UNIX_EPOCH + Duration::from_secs(freshness.updated_at);
+    let etag = EntityTag::new_strong(format!("{:?}-{}", store, freshness.updated_at));
+
+    // If-None-Match takes precedence over If-Modified-Since when both are present
+    let not_modified = match IfNoneMatch::parse(req) {
+        Ok(IfNoneMatch::Any) => true,
+        Ok(IfNoneMatch::Items(tags)) if !tags.is_empty() => tags.iter().any(|t| t.weak_eq(&etag)),
+        _ => match IfModifiedSince::parse(req) {
+            Ok(IfModifiedSince(since)) => modified <= SystemTime::from(since),
+            Err(_) => false,
+        },
+    };
+
+    let mut builder = if not_modified {
+        HttpResponse::NotModified()
+    } else {
+        HttpResponse::Ok()
+    };
+    builder
+        .insert_header(header::LastModified(HttpDate::from(modified)))
+        .insert_header(header::ETag(etag));
+    if not_modified {
+        builder.finish()
+    } else if envelope {
+        builder.json(Envelope {
+            data,
+            updated_at: freshness.updated_at,
+            gravity_height: freshness.gravity_height,
+            eth_height: freshness.eth_height,
+            next_refresh: freshness.next_refresh,
+        })
+    } else {
+        builder.json(data)
+    }
+}
+
+#[get("/health")]
+pub async fn health() -> impl Responder {
+    let report = health_report(&get_config().health, unix_now());
+    if report.healthy {
+        HttpResponse::Ok().json(report)
+    } else {
+        HttpResponse::ServiceUnavailable().json(report)
+    }
+}
+
+#[get("/ready")]
+pub async fn ready() -> impl Responder {
+    let report = health_report(&get_config().health, unix_now());
+    if report.ready {
+        HttpResponse::Ok().json(report)
+    } else {
+        HttpResponse::ServiceUnavailable().json(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_report() {
+        let config = HealthConfig::default();
+        let now = unix_now();
+        for store in Store::ALL {
+            set_freshness(
+                store,
+                Freshness {
+                    updated_at: now - 10,
+                    gravity_height: Some(1_000),
+                    eth_height: None,
+                    next_refresh: None,
+                },
+            );
+        }
+        let report = health_report(&config, now);
+        assert!(report.healthy);
+        assert!(report.ready);
+        let volume = &report.stores[3];
+        assert_eq!(volume.store, Store::Volume);
+        assert_eq!(volume.age_seconds, Some(10));
+        assert_eq!(volume.gravity_height, Some(1_000));
+
+        // a store older than its threshold makes the instance unhealthy
+        let report = health_report(&config, now + config.last_valset_max_age);
+        assert!(!report.healthy);
+        assert!(!report.ready);
+        let valset = &report.stores[5];
+        assert_eq!(valset.store, Store::LastValset);
+        assert!(valset.stale);
+        assert!(!report.stores[3].stale);
+    }
+
+    #[actix_web::test]
+    async fn test_cached_response() {
+        let freshness = Freshness {
+            updated_at: 1_700_000_000,
+            gravity_height: Some(1_000),
+            eth_height: Some(15_876_600),
+            next_refresh: Some(1_700_000_060),
+        };
+        let req = actix_web::test::TestRequest::default().to_http_request();
+        let res = respond(&req, Store::Volume, Some(freshness), vec![1, 2], true);
+        assert_eq!(res.status(), 200);
+        let etag = res.headers().get(header::ETAG).unwrap().clone();
+        assert!(res.headers().contains_key(header::LAST_MODIFIED));
+        let body = actix_web::body::to_bytes(res.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["data"], serde_json::json!([1, 2]));
+        assert_eq!(body["updated_at"], 1_700_000_000);
+        assert_eq!(body["eth_height"], 15_876_600);
+
+        // the client's copy is current
+        let req = actix_web::test::TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, etag))
+            .to_http_request();
+        let res = respond(&req, Store::Volume, Some(freshness), vec![1, 2], true);
+        assert_eq!(res.status(), 304);
+
+        // the store has been updated since
+        let updated = Freshness {
+            updated_at: 1_700_000_060,
+            ..freshness
+        };
+        let res = respond(&req, Store::Volume, Some(updated), vec![1, 2], true);
+        assert_eq!(res.status(), 200);
+    }
+}
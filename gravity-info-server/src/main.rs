@@ -2,38 +2,35 @@
 extern crate lazy_static;
 
 pub mod batch_relaying;
+pub mod config;
 pub mod gravity_info;
+pub mod health;
+pub mod metrics;
+pub mod oracle;
 pub mod total_suppy;
 pub mod transactions;
+pub mod upstream;
 pub mod volume;
 
-const DEVELOPMENT: bool = cfg!(feature = "development");
-const SSL: bool = !DEVELOPMENT;
-const DOMAIN: &str = if cfg!(test) || DEVELOPMENT {
-    "localhost"
-} else {
-    "info.gravitychain.io"
-};
-/// The backend RPC port for the info server fucntions implemented in this repo
-const INFO_SERVER_PORT: u16 = 9000;
-/// Provides the eip-712 metamask rpc for Gravity Bridge
-const METAMASK_RPC_PORT: u16 = 8545;
-
 use crate::batch_relaying::generate_batch_tx_responder;
+use crate::config::{set_config, Args, Config};
 use crate::gravity_info::get_erc20_metadata;
 use crate::gravity_info::get_gravity_info;
 use crate::total_suppy::get_supply_info;
 use crate::volume::get_volume_info;
 use actix_cors::Cors;
+use actix_web::middleware::from_fn;
 use actix_web::web::Data;
-use actix_web::{get, web, App, HttpResponse, HttpServer, Responder};
+use actix_web::{get, web, App, HttpRequest, HttpResponse, HttpServer, Responder};
 use batch_relaying::valset_update_thread;
+use clap::Parser;
 use env_logger::Env;
 use futures::future::join;
 use gravity_info::{blockchain_info_thread, get_eth_info};
+use health::{cached_bare_response, cached_response, Store};
 use jsonrpc_server::server::request_dispatcher;
 use log::{error, info};
-use rocksdb::Options;
+use oracle::oracle_lag_thread;
 use rocksdb::DB;
 use rustls::crypto::CryptoProvider;
 use rustls::pki_types::pem::PemObject;
@@ -42,7 +39,11 @@ use rustls::ServerConfig;
 use std::sync::Arc;
 use tonic::transport::CertificateDer;
 use total_suppy::chain_total_supply_thread;
-use transactions::database::transaction_info_thread;
+use transactions::database::{open_database, transaction_info_thread};
+use transactions::deposits::deposit_tracker_thread;
+use transactions::endpoints::{ClaimQuery, ConfirmQuery, TotalsQuery, TxQuery};
+use transactions::eth_events::eth_event_sync_thread;
+use transactions::withdrawals::withdrawal_tracker_thread;
 use volume::bridge_volume_thread;
 
 /// This is a helper api endpoint which generates an unsigned tx for a transaction batch sent from a given address
@@ -54,10 +55,10 @@ async fn generate_batch_tx(data: web::Path<(u64,)>, db: Data<Arc<DB>>) -> impl R
 }
 
 #[get("/total_supply")]
-async fn get_total_supply() -> impl Responder {
+async fn get_total_supply(req: HttpRequest) -> impl Responder {
     // if we have already computed supply info return it, if not return an error
     match get_supply_info() {
-        Some(v) => HttpResponse::Ok().json(v.total_supply),
+        Some(v) => cached_bare_response(&req, Store::TotalSupply, v.total_supply),
         None => HttpResponse::InternalServerError()
             .json("Info not yet generated, please query in 5 minutes"),
     }
@@ -67,12 +68,12 @@ async fn get_total_supply() -> impl Responder {
 /// return an error.
 pub const SUPPLY_CHECKPOINT: u128 = 500000000000000;
 #[get("/total_liquid_supply")]
-async fn get_total_liquid_supply() -> impl Responder {
+async fn get_total_liquid_supply(req: HttpRequest) -> impl Responder {
     // if we have already computed supply info return it, if not return an error
     match get_supply_info() {
         Some(v) => {
             if v.total_liquid_supply > SUPPLY_CHECKPOINT.into() {
-                HttpResponse::Ok().json(v.total_liquid_supply)
+                cached_bare_response(&req, Store::TotalSupply, v.total_liquid_supply)
             } else {
                 error!("Invalid supply data, got total liquid supply of {:#?}", v);
                 HttpResponse::InternalServerError()
@@ -85,12 +86,12 @@ async fn get_total_liquid_supply() -> impl Responder {
 }
 
 #[get("/supply_info")]
-async fn get_all_supply_info() -> impl Responder {
+async fn get_all_supply_info(req: HttpRequest) -> impl Responder {
     // if we have already computed supply info return it, if not return an error
     match get_supply_info() {
         Some(v) => {
             if v.total_liquid_supply > SUPPLY_CHECKPOINT.into() {
-                HttpResponse::Ok().json(v)
+                cached_response(&req, Store::TotalSupply, v)
             } else {
                 error!("Invalid supply data, got total liquid supply of {:#?}", v);
                 HttpResponse::InternalServerError()
@@ -103,58 +104,211 @@ async fn get_all_supply_info() -> impl Responder {
 }
 
 #[get("/eth_bridge_info")]
-async fn get_eth_bridge_info() -> impl Responder {
+async fn get_eth_bridge_info(req: HttpRequest) -> impl Responder {
     // if we have already computed supply info return it, if not return an error
     match get_eth_info() {
-        Some(v) => HttpResponse::Ok().json(v),
+        Some(v) => cached_response(&req, Store::EthInfo, v),
         None => HttpResponse::InternalServerError()
             .json("Info not yet generated, please query in 5 minutes"),
     }
 }
 
 #[get("/gravity_bridge_info")]
-async fn get_gravity_bridge_info() -> impl Responder {
+async fn get_gravity_bridge_info(req: HttpRequest) -> impl Responder {
     // if we have already computed supply info return it, if not return an error
     match get_gravity_info() {
-        Some(v) => HttpResponse::Ok().json(v),
+        Some(v) => cached_response(&req, Store::GravityInfo, v),
         None => HttpResponse::InternalServerError()
             .json("Info not yet generated, please query in 5 minutes"),
     }
 }
 
 #[get("/erc20_metadata")]
-async fn erc20_metadata() -> impl Responder {
+async fn erc20_metadata(req: HttpRequest) -> impl Responder {
     // if we have already computed supply info return it, if not return an error
     match get_erc20_metadata() {
-        Some(v) => HttpResponse::Ok().json(v),
+        Some(v) => cached_response(&req, Store::Erc20Metadata, v),
         None => HttpResponse::InternalServerError()
             .json("Info not yet generated, please query in 5 minutes"),
     }
 }
 
 #[get("/bridge_volume")]
-async fn get_bridge_volume() -> impl Responder {
+async fn get_bridge_volume(req: HttpRequest) -> impl Responder {
     // if we have already computed volume info return it, if not return an error
     match get_volume_info() {
-        Some(v) => HttpResponse::Ok().json(v),
+        Some(v) => cached_response(&req, Store::Volume, v),
         None => HttpResponse::InternalServerError()
             .json("Info not yet generated, please query in 20 minutes"),
     }
 }
 
 #[get("/transactions/send_to_eth")]
-async fn get_all_msg_send_to_eth_transactions(db: web::Data<Arc<DB>>) -> impl Responder {
-    transactions::endpoints::get_all_msg_send_to_eth_transactions(db).await
+async fn get_all_msg_send_to_eth_transactions(
+    db: web::Data<Arc<DB>>,
+    query: web::Query<TxQuery>,
+) -> impl Responder {
+    transactions::endpoints::get_all_msg_send_to_eth_transactions(db, query).await
 }
 
 #[get("/transactions/ibc_transfer")]
-async fn get_all_msg_ibc_transfer_transactions(db: web::Data<Arc<DB>>) -> impl Responder {
-    transactions::endpoints::get_all_msg_ibc_transfer_transactions(db).await
+async fn get_all_msg_ibc_transfer_transactions(
+    db: web::Data<Arc<DB>>,
+    query: web::Query<TxQuery>,
+) -> impl Responder {
+    transactions::endpoints::get_all_msg_ibc_transfer_transactions(db, query).await
+}
+
+#[get("/transactions/cancel_send_to_eth")]
+async fn get_all_msg_cancel_send_to_eth_transactions(
+    db: web::Data<Arc<DB>>,
+    query: web::Query<TxQuery>,
+) -> impl Responder {
+    transactions::endpoints::get_all_msg_cancel_send_to_eth_transactions(db, query).await
+}
+
+#[get("/transactions/by_address/{address}")]
+async fn get_transactions_by_address(
+    address: web::Path<(String,)>,
+    db: web::Data<Arc<DB>>,
+    query: web::Query<TxQuery>,
+) -> impl Responder {
+    transactions::endpoints::get_transactions_by_address(db, &address.into_inner().0, query).await
+}
+
+#[get("/transactions/by_denom/{denom:.*}")]
+async fn get_transactions_by_denom(
+    denom: web::Path<(String,)>,
+    db: web::Data<Arc<DB>>,
+    query: web::Query<TxQuery>,
+) -> impl Responder {
+    transactions::endpoints::get_transactions_by_denom(db, &denom.into_inner().0, query).await
+}
+
+#[get("/transactions/by_channel/{channel}")]
+async fn get_transactions_by_channel(
+    channel: web::Path<(String,)>,
+    db: web::Data<Arc<DB>>,
+    query: web::Query<TxQuery>,
+) -> impl Responder {
+    transactions::endpoints::get_transactions_by_channel(db, &channel.into_inner().0, query).await
+}
+
+#[get("/transactions/tx/{tx_hash}")]
+async fn get_transaction_by_hash(
+    tx_hash: web::Path<(String,)>,
+    db: web::Data<Arc<DB>>,
+) -> impl Responder {
+    transactions::endpoints::get_transaction_by_hash(db, &tx_hash.into_inner().0).await
+}
+
+#[get("/transactions/status")]
+async fn get_transaction_indexer_status(db: web::Data<Arc<DB>>) -> impl Responder {
+    transactions::endpoints::get_transaction_indexer_status(db).await
+}
+
+#[get("/withdrawal/{tx_hash}")]
+async fn get_withdrawal(tx_hash: web::Path<(String,)>, db: web::Data<Arc<DB>>) -> impl Responder {
+    transactions::endpoints::get_withdrawal(db, &tx_hash.into_inner().0).await
+}
+
+#[get("/withdrawal/by_sender/{address}")]
+async fn get_withdrawals_by_sender(
+    address: web::Path<(String,)>,
+    db: web::Data<Arc<DB>>,
+    query: web::Query<TxQuery>,
+) -> impl Responder {
+    transactions::endpoints::get_withdrawals_by_sender(db, &address.into_inner().0, query).await
+}
+
+#[get("/deposit/{tx_hash}")]
+async fn get_deposit(tx_hash: web::Path<(String,)>, db: web::Data<Arc<DB>>) -> impl Responder {
+    transactions::endpoints::get_deposit(db, &tx_hash.into_inner().0).await
+}
+
+#[get("/deposit/by_nonce/{event_nonce}")]
+async fn get_deposit_by_nonce(
+    event_nonce: web::Path<(u64,)>,
+    db: web::Data<Arc<DB>>,
+) -> impl Responder {
+    transactions::endpoints::get_deposit_by_nonce(db, event_nonce.into_inner().0).await
+}
+
+#[get("/deposit/by_sender/{address}")]
+async fn get_deposits_by_sender(
+    address: web::Path<(String,)>,
+    db: web::Data<Arc<DB>>,
+    query: web::Query<ClaimQuery>,
+) -> impl Responder {
+    transactions::endpoints::get_deposits_by_sender(db, &address.into_inner().0, query).await
+}
+
+#[get("/deposit/by_destination/{address}")]
+async fn get_deposits_by_destination(
+    address: web::Path<(String,)>,
+    db: web::Data<Arc<DB>>,
+    query: web::Query<ClaimQuery>,
+) -> impl Responder {
+    transactions::endpoints::get_deposits_by_destination(db, &address.into_inner().0, query).await
+}
+
+#[get("/eth_events/status")]
+async fn get_eth_event_status(db: web::Data<Arc<DB>>) -> impl Responder {
+    transactions::endpoints::get_eth_event_status(db).await
+}
+
+#[get("/eth_events/unconfirmed")]
+async fn get_unconfirmed_eth_events(db: web::Data<Arc<DB>>) -> impl Responder {
+    transactions::endpoints::get_unconfirmed_eth_events(db).await
+}
+
+#[get("/eth_events/{event_type}")]
+async fn get_eth_events(
+    event_type: web::Path<(String,)>,
+    db: web::Data<Arc<DB>>,
+    query: web::Query<ClaimQuery>,
+) -> impl Responder {
+    transactions::endpoints::get_eth_events(db, &event_type.into_inner().0, query).await
+}
+
+#[get("/claims/event/{event_nonce}")]
+async fn get_event_claims(
+    event_nonce: web::Path<(u64,)>,
+    db: web::Data<Arc<DB>>,
+) -> impl Responder {
+    transactions::endpoints::get_event_claims(db, event_nonce.into_inner().0).await
+}
+
+#[get("/claims/orchestrator/{orchestrator}")]
+async fn get_orchestrator_claims(
+    orchestrator: web::Path<(String,)>,
+    db: web::Data<Arc<DB>>,
+    query: web::Query<ClaimQuery>,
+) -> impl Responder {
+    transactions::endpoints::get_orchestrator_claims(db, &orchestrator.into_inner().0, query).await
+}
+
+#[get("/confirms/signing")]
+async fn get_signing_report(db: web::Data<Arc<DB>>) -> impl Responder {
+    transactions::endpoints::get_signing_report(db).await
+}
+
+#[get("/confirms/orchestrator/{orchestrator}")]
+async fn get_orchestrator_confirms(
+    orchestrator: web::Path<(String,)>,
+    db: web::Data<Arc<DB>>,
+    query: web::Query<ConfirmQuery>,
+) -> impl Responder {
+    transactions::endpoints::get_orchestrator_confirms(db, &orchestrator.into_inner().0, query)
+        .await
 }
 
 #[get("/transactions/send_to_eth/time")]
-async fn get_send_to_eth_transaction_totals(db: web::Data<Arc<DB>>) -> impl Responder {
-    transactions::endpoints::get_send_to_eth_transaction_totals(db).await
+async fn get_send_to_eth_transaction_totals(
+    db: web::Data<Arc<DB>>,
+    query: web::Query<TotalsQuery>,
+) -> impl Responder {
+    transactions::endpoints::get_send_to_eth_transaction_totals(db, query).await
 }
 
 #[actix_web::main]
@@ -164,13 +318,32 @@ async fn main() -> std::io::Result<()> {
     }
     CryptoProvider::install_default(rustls::crypto::aws_lc_rs::default_provider()).unwrap();
     env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+    let args = Args::parse();
+    let config = match Config::load(&args) {
+        Ok(c) => c,
+        Err(e) => panic!("Failed to load config {:?}", e),
+    };
+    info!("Starting with config {:?}", config.redacted());
+    set_config(config.clone());
     // starts a background thread for downloading transactions
-    let mut db_options = Options::default();
-    db_options.create_if_missing(true);
-    let db = Arc::new(DB::open(&db_options, "transactions").expect("Failed to open database"));
+    let db = Arc::new(open_database(&config.database.path).expect("Failed to open database"));
+    if let Some(command) = args.command {
+        return transactions::admin::run(&db, command)
+            .await
+            .map_err(|e| std::io::Error::other(e.to_string()));
+    }
+    health::restore_from_db(&db);
     valset_update_thread(db.clone());
     let api_db = web::Data::new(db.clone());
     transaction_info_thread(db.clone());
+    // starts a background thread recording the batches and batch executions of withdrawals
+    withdrawal_tracker_thread(db.clone());
+    // starts a background thread recording when each deposit reaches each stage
+    deposit_tracker_thread(db.clone());
+    // starts a background thread storing every event of the Gravity contract on Ethereum
+    eth_event_sync_thread(db.clone());
+    // starts a background thread comparing Ethereum event nonces with observed attestations
+    oracle_lag_thread(db.clone());
     // starts background thread for gathering into
     blockchain_info_thread();
     // starts a background thread for generating the total supply numbers
@@ -186,6 +359,11 @@ async fn main() -> std::io::Result<()> {
                     .allow_any_header()
                     .allow_any_method(),
             )
+            .wrap(from_fn(metrics::http_metrics))
+            .service(metrics::metrics)
+            .service(health::health)
+            .service(health::ready)
+            .service(oracle::oracle_lag)
             .service(get_total_supply)
             .service(get_total_liquid_supply)
             .service(get_all_supply_info)
@@ -196,7 +374,26 @@ async fn main() -> std::io::Result<()> {
             .app_data(api_db.clone())
             .service(get_all_msg_send_to_eth_transactions)
             .service(get_all_msg_ibc_transfer_transactions)
+            .service(get_all_msg_cancel_send_to_eth_transactions)
             .service(get_send_to_eth_transaction_totals)
+            .service(get_transactions_by_address)
+            .service(get_transactions_by_denom)
+            .service(get_transactions_by_channel)
+            .service(get_transaction_by_hash)
+            .service(get_transaction_indexer_status)
+            .service(get_withdrawals_by_sender)
+            .service(get_withdrawal)
+            .service(get_deposit_by_nonce)
+            .service(get_deposits_by_sender)
+            .service(get_deposits_by_destination)
+            .service(get_deposit)
+            .service(get_eth_event_status)
+            .service(get_unconfirmed_eth_events)
+            .service(get_eth_events)
+            .service(get_event_claims)
+            .service(get_orchestrator_claims)
+            .service(get_signing_report)
+            .service(get_orchestrator_confirms)
             .service(generate_batch_tx)
     });
 
@@ -211,9 +408,10 @@ async fn main() -> std::io::Result<()> {
             .service(request_dispatcher)
     });
 
-    let (info_server, metamask_server) = if SSL {
-        let cert_file = format!("/etc/letsencrypt/live/{}/fullchain.pem", DOMAIN);
-        let key_file = format!("/etc/letsencrypt/live/{}/privkey.pem", DOMAIN);
+    let server = config.server;
+    let (info_server, metamask_server) = if server.ssl {
+        let cert_file = server.cert_path();
+        let key_file = server.key_path();
         let cert_chain = CertificateDer::pem_file_iter(cert_file)
             .unwrap()
             .map(|cert| cert.unwrap())
@@ -225,14 +423,18 @@ async fn main() -> std::io::Result<()> {
             .unwrap();
 
         info!("Binding to SSL");
-        let a = info_server
-            .bind_rustls_0_23(format!("{}:{}", DOMAIN, INFO_SERVER_PORT), config.clone())?;
-        let b = metamask_server
-            .bind_rustls_0_23(format!("{}:{}", DOMAIN, METAMASK_RPC_PORT), config)?;
+        let a = info_server.bind_rustls_0_23(
+            format!("{}:{}", server.domain, server.info_port),
+            config.clone(),
+        )?;
+        let b = metamask_server.bind_rustls_0_23(
+            format!("{}:{}", server.domain, server.metamask_port),
+            config,
+        )?;
         (a, b)
     } else {
-        let a = info_server.bind(format!("{}:{}", DOMAIN, INFO_SERVER_PORT))?;
-        let b = metamask_server.bind(format!("{}:{}", DOMAIN, METAMASK_RPC_PORT))?;
+        let a = info_server.bind(format!("{}:{}", server.domain, server.info_port))?;
+        let b = metamask_server.bind(format!("{}:{}", server.domain, server.metamask_port))?;
         (a, b)
     };
 
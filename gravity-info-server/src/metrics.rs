@@ -0,0 +1,250 @@
+//! Prometheus metrics for the background collectors, the transaction indexer, upstream node requests
+//! and the HTTP API. Everything is registered in the default registry and served by `/metrics`.
+
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{get, Error, HttpResponse, Responder};
+use prometheus::{
+    register_histogram_vec, register_int_counter, register_int_counter_vec, register_int_gauge,
+    register_int_gauge_vec, Encoder, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, TextEncoder,
+};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Collector names used as the `collector` label
+pub const GRAVITY_INFO_COLLECTOR: &str = "gravity_info";
+pub const VOLUME_COLLECTOR: &str = "volume";
+pub const SUPPLY_COLLECTOR: &str = "supply";
+pub const VALSET_COLLECTOR: &str = "valset";
+pub const INDEXER_COLLECTOR: &str = "indexer";
+pub const ETH_EVENTS_COLLECTOR: &str = "eth_events";
+pub const ORACLE_COLLECTOR: &str = "oracle";
+
+lazy_static! {
+    static ref COLLECTOR_LAST_SUCCESS: IntGaugeVec = register_int_gauge_vec!(
+        "gravity_info_collector_last_success_timestamp_seconds",
+        "Unix time of the last successful run of each collector",
+        &["collector"]
+    )
+    .unwrap();
+    static ref COLLECTOR_DURATION: HistogramVec = register_histogram_vec!(
+        "gravity_info_collector_duration_seconds",
+        "Duration of each collector run",
+        &["collector", "outcome"],
+        vec![0.5, 1.0, 5.0, 15.0, 60.0, 300.0, 900.0, 3600.0, 14400.0]
+    )
+    .unwrap();
+    static ref COLLECTOR_ERRORS: IntCounterVec = register_int_counter_vec!(
+        "gravity_info_collector_errors_total",
+        "Number of failed collector runs",
+        &["collector"]
+    )
+    .unwrap();
+    static ref INDEXER_BLOCKS: IntCounter = register_int_counter!(
+        "gravity_info_indexer_blocks_total",
+        "Number of Gravity blocks scanned by the transaction indexer"
+    )
+    .unwrap();
+    static ref INDEXER_TXS: IntCounter = register_int_counter!(
+        "gravity_info_indexer_txs_total",
+        "Number of indexed transactions"
+    )
+    .unwrap();
+    static ref INDEXER_MSGS: IntCounterVec = register_int_counter_vec!(
+        "gravity_info_indexer_msgs_total",
+        "Number of indexed messages by type",
+        &["msg_type"]
+    )
+    .unwrap();
+    static ref INDEXER_HEIGHT: IntGauge = register_int_gauge!(
+        "gravity_info_indexer_height",
+        "Last Gravity block height fully downloaded by the transaction indexer"
+    )
+    .unwrap();
+    static ref INDEXER_GAPS: IntGauge = register_int_gauge!(
+        "gravity_info_indexer_gaps",
+        "Number of block ranges the transaction indexer failed to download and will retry"
+    )
+    .unwrap();
+    static ref CHAIN_HEIGHT: IntGauge = register_int_gauge!(
+        "gravity_info_chain_height",
+        "Latest Gravity block height seen by the transaction indexer"
+    )
+    .unwrap();
+    static ref ETH_REORGS: IntCounter = register_int_counter!(
+        "gravity_info_eth_reorgs_total",
+        "Number of Ethereum reorgs that rolled back stored unconfirmed events"
+    )
+    .unwrap();
+    static ref ETH_REORGED_EVENTS: IntCounter = register_int_counter!(
+        "gravity_info_eth_reorged_events_total",
+        "Number of stored unconfirmed Ethereum events rolled back by reorgs"
+    )
+    .unwrap();
+    static ref ORACLE_NONCE_GAP: IntGauge = register_int_gauge!(
+        "gravity_info_oracle_nonce_gap",
+        "Events in finalized Ethereum blocks not yet observed on Gravity"
+    )
+    .unwrap();
+    static ref ORACLE_SECONDS_SINCE_OBSERVED: IntGauge = register_int_gauge!(
+        "gravity_info_oracle_seconds_since_observed",
+        "Seconds since an Ethereum event was last observed on Gravity"
+    )
+    .unwrap();
+    static ref ORACLE_ORCHESTRATOR_LAG: IntGaugeVec = register_int_gauge_vec!(
+        "gravity_info_oracle_orchestrator_lag",
+        "Events emitted on Ethereum since the last claim of each active orchestrator",
+        &["orchestrator"]
+    )
+    .unwrap();
+    static ref UPSTREAM_LATENCY: HistogramVec = register_histogram_vec!(
+        "gravity_info_upstream_request_duration_seconds",
+        "Latency of requests to Gravity and Ethereum nodes",
+        &["chain", "endpoint", "outcome"],
+        vec![0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 15.0, 60.0]
+    )
+    .unwrap();
+    static ref HTTP_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "gravity_info_http_requests_total",
+        "HTTP requests by route and status",
+        &["route", "status"]
+    )
+    .unwrap();
+    static ref HTTP_LATENCY: HistogramVec = register_histogram_vec!(
+        "gravity_info_http_request_duration_seconds",
+        "HTTP request latency by route and status",
+        &["route", "status"],
+        vec![0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 30.0]
+    )
+    .unwrap();
+}
+
+fn outcome(success: bool) -> &'static str {
+    if success {
+        "success"
+    } else {
+        "error"
+    }
+}
+
+/// Records a collector run that started at `start`
+pub fn record_collector_run(collector: &str, start: Instant, success: bool) {
+    COLLECTOR_DURATION
+        .with_label_values(&[collector, outcome(success)])
+        .observe(start.elapsed().as_secs_f64());
+    if success {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        COLLECTOR_LAST_SUCCESS
+            .with_label_values(&[collector])
+            .set(now.as_secs() as i64);
+    } else {
+        COLLECTOR_ERRORS.with_label_values(&[collector]).inc();
+    }
+}
+
+pub fn record_indexed_blocks(blocks: u64, txs: u64) {
+    INDEXER_BLOCKS.inc_by(blocks);
+    INDEXER_TXS.inc_by(txs);
+}
+
+pub fn record_indexed_msgs(msg_type: &str, count: u64) {
+    INDEXER_MSGS.with_label_values(&[msg_type]).inc_by(count);
+}
+
+pub fn set_indexer_height(height: u64) {
+    INDEXER_HEIGHT.set(height as i64);
+}
+
+pub fn set_indexer_gaps(gaps: u64) {
+    INDEXER_GAPS.set(gaps as i64);
+}
+
+pub fn set_chain_height(height: u64) {
+    CHAIN_HEIGHT.set(height as i64);
+}
+
+pub fn record_eth_reorg(rolled_back_events: u64) {
+    ETH_REORGS.inc();
+    ETH_REORGED_EVENTS.inc_by(rolled_back_events);
+}
+
+pub fn set_oracle_lag(nonce_gap: u64, seconds_since_observed: u64) {
+    ORACLE_NONCE_GAP.set(nonce_gap as i64);
+    ORACLE_SECONDS_SINCE_OBSERVED.set(seconds_since_observed as i64);
+}
+
+/// Replaces the lag of every orchestrator, so orchestrators no longer active are dropped
+pub fn set_orchestrator_lags(lags: &[(String, u64)]) {
+    ORACLE_ORCHESTRATOR_LAG.reset();
+    for (orchestrator, lag) in lags {
+        ORACLE_ORCHESTRATOR_LAG
+            .with_label_values(&[orchestrator])
+            .set(*lag as i64);
+    }
+}
+
+pub fn record_upstream_request(chain: &str, endpoint: &str, latency: Duration, success: bool) {
+    UPSTREAM_LATENCY
+        .with_label_values(&[chain, endpoint, outcome(success)])
+        .observe(latency.as_secs_f64());
+}
+
+/// Middleware recording the status and latency of every request, labeled by the matched route
+/// pattern rather than the raw path to keep the label set bounded
+pub async fn http_metrics(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let start = Instant::now();
+    let route = req
+        .match_pattern()
+        .unwrap_or_else(|| "unmatched".to_string());
+    let res = next.call(req).await;
+    let status = match &res {
+        Ok(r) => r.status().as_u16().to_string(),
+        Err(e) => e.as_response_error().status_code().as_u16().to_string(),
+    };
+    HTTP_REQUESTS.with_label_values(&[&route, &status]).inc();
+    HTTP_LATENCY
+        .with_label_values(&[&route, &status])
+        .observe(start.elapsed().as_secs_f64());
+    res
+}
+
+#[get("/metrics")]
+pub async fn metrics() -> impl Responder {
+    let mut buffer = Vec::new();
+    let encoder = TextEncoder::new();
+    match encoder.encode(&prometheus::gather(), &mut buffer) {
+        Ok(_) => HttpResponse::Ok()
+            .content_type(encoder.format_type())
+            .body(buffer),
+        Err(e) => HttpResponse::InternalServerError().json(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::middleware::from_fn;
+    use actix_web::{test, App};
+
+    #[actix_web::test]
+    async fn test_metrics_endpoint() {
+        record_collector_run(VOLUME_COLLECTOR, Instant::now(), false);
+        record_indexed_msgs("msgSendToEth", 2);
+        let app = test::init_service(App::new().wrap(from_fn(http_metrics)).service(metrics)).await;
+        // the first request records itself, the second one reports it
+        test::call_service(&app, test::TestRequest::get().uri("/metrics").to_request()).await;
+        let res =
+            test::call_service(&app, test::TestRequest::get().uri("/metrics").to_request()).await;
+        assert!(res.status().is_success());
+        let body = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+        assert!(body.contains("gravity_info_collector_errors_total{collector=\"volume\"}"));
+        assert!(body.contains("gravity_info_indexer_msgs_total{msg_type=\"msgSendToEth\"}"));
+        assert!(
+            body.contains("gravity_info_http_requests_total{route=\"/metrics\",status=\"200\"}")
+        );
+    }
+}
@@ -0,0 +1,311 @@
+//! Watches how far the Gravity oracle is behind Ethereum. Every event the Gravity contract emits
+//! gets a nonce, the gap between the latest stored event of a finalized block and the latest
+//! observed attestation is the number of events orchestrators still have to bring over. Each
+//! active orchestrator's last claimed nonce shows which validators are holding the oracle back.
+//! `/oracle_lag` fails once any of the configured thresholds is exceeded so it can be alerted on.
+
+use crate::config::{get_config, OracleConfig};
+use crate::gravity_info::{get_gravity_info, GravityInfo, REQUEST_TIMEOUT};
+use crate::health::unix_now;
+use crate::metrics::{
+    record_collector_run, set_oracle_lag, set_orchestrator_lags, ORACLE_COLLECTOR,
+};
+use crate::transactions::database::{
+    load_last_claims, load_last_observed_nonce, load_latest_eth_event_nonce,
+    record_last_observed_nonce,
+};
+use crate::upstream::failover::FailoverGravity;
+use crate::upstream::GravitySource;
+use actix_web::rt::System;
+use actix_web::{get, HttpResponse, Responder};
+use futures::future::join_all;
+use log::{error, info, warn};
+use rocksdb::DB;
+use serde::Serialize;
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const LOOP_TIME: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OracleAlert {
+    /// More than `max_nonce_gap` events are waiting to be observed
+    NonceGap,
+    /// Events are waiting and none has been observed for `max_observe_delay` seconds
+    ObservationStalled,
+    /// Some orchestrator's last claim is more than `max_orchestrator_lag` events behind
+    OrchestratorsLagging,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrchestratorLag {
+    pub orchestrator: String,
+    /// Nonce of the last event the orchestrator claimed, None if Gravity could not be queried
+    pub last_event_nonce: Option<u64>,
+    /// Events emitted on Ethereum since that claim
+    pub lag: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OracleLag {
+    /// Nonce of the latest event in a finalized Ethereum block, None until events are synced
+    pub latest_eth_event_nonce: Option<u64>,
+    /// Nonce of the latest observed attestation, None until one has been seen
+    pub last_observed_nonce: Option<u64>,
+    pub nonce_gap: u64,
+    /// Unix time in seconds the last observed nonce was first seen
+    pub last_observed_at: Option<i64>,
+    pub seconds_since_observed: Option<u64>,
+    /// Active orchestrators, the furthest behind first and those that could not be queried last
+    pub orchestrators: Vec<OrchestratorLag>,
+    pub alerts: Vec<OracleAlert>,
+    /// Unix time in seconds
+    pub updated_at: i64,
+}
+
+lazy_static! {
+    static ref ORACLE_LAG: Arc<RwLock<Option<OracleLag>>> = Arc::new(RwLock::new(None));
+}
+
+pub fn get_oracle_lag() -> Option<OracleLag> {
+    ORACLE_LAG.read().unwrap().clone()
+}
+
+fn set_oracle_lag_snapshot(lag: OracleLag) {
+    let mut lock = ORACLE_LAG.write().unwrap();
+    *lock = Some(lag)
+}
+
+pub fn oracle_lag_thread(db: Arc<DB>) {
+    info!("Starting oracle lag monitor");
+
+    thread::spawn(move || loop {
+        let runner = System::new();
+        runner.block_on(async {
+            let start = Instant::now();
+            let config = get_config();
+            let gravity = match FailoverGravity::from_config(&config.gravity, REQUEST_TIMEOUT) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("Failed to connect to Gravity {:?}", e);
+                    record_collector_run(ORACLE_COLLECTOR, start, false);
+                    return;
+                }
+            };
+            let info = get_gravity_info();
+            let lag = compute_oracle_lag(
+                &db,
+                &gravity,
+                info.as_ref(),
+                &config.oracle,
+                unix_now() as i64,
+            )
+            .await;
+            if !lag.alerts.is_empty() {
+                warn!("Oracle is lagging {:?}", lag.alerts);
+            }
+            set_oracle_lag(lag.nonce_gap, lag.seconds_since_observed.unwrap_or(0));
+            let lags: Vec<(String, u64)> = lag
+                .orchestrators
+                .iter()
+                .filter_map(|o| o.lag.map(|l| (o.orchestrator.clone(), l)))
+                .collect();
+            // the report is still published, but the run failed if any orchestrator is unknown
+            let complete = lags.len() == lag.orchestrators.len();
+            set_orchestrator_lags(&lags);
+            set_oracle_lag_snapshot(lag);
+            record_collector_run(ORACLE_COLLECTOR, start, complete);
+        });
+        thread::sleep(LOOP_TIME);
+    });
+}
+
+/// Compares the latest stored Ethereum event with the latest observed attestation in `info` and
+/// the last claim of every orchestrator that submitted one within `active_window`. An
+/// orchestrator whose last claim can't be queried is reported as unknown.
+pub async fn compute_oracle_lag(
+    db: &DB,
+    gravity: &dyn GravitySource,
+    info: Option<&GravityInfo>,
+    config: &OracleConfig,
+    now: i64,
+) -> OracleLag {
+    if let Some(info) = info {
+        record_last_observed_nonce(db, info, now);
+    }
+    let last_observed = load_last_observed_nonce(db);
+    let last_observed_nonce = last_observed.map(|(nonce, _)| nonce);
+    let last_observed_at = last_observed.map(|(_, timestamp)| timestamp);
+    let latest = load_latest_eth_event_nonce(db);
+    let nonce_gap = latest
+        .unwrap_or(0)
+        .saturating_sub(last_observed_nonce.unwrap_or(0));
+    let seconds_since_observed = last_observed_at.map(|t| (now - t).max(0) as u64);
+
+    let active: Vec<String> = load_last_claims(db)
+        .into_iter()
+        .filter(|c| now - c.timestamp <= config.active_window as i64)
+        .map(|c| c.orchestrator)
+        .collect();
+    let nonces = join_all(
+        active
+            .iter()
+            .map(|o| gravity.last_event_nonce_by_addr(o.clone())),
+    )
+    .await;
+    let mut orchestrators = Vec::new();
+    for (orchestrator, nonce) in active.into_iter().zip(nonces) {
+        let last_event_nonce = match nonce {
+            Ok(n) => Some(n),
+            Err(e) => {
+                error!(
+                    "Failed to get the last event nonce of {} {:?}",
+                    orchestrator, e
+                );
+                None
+            }
+        };
+        orchestrators.push(OrchestratorLag {
+            orchestrator,
+            last_event_nonce,
+            lag: last_event_nonce.map(|n| latest.unwrap_or(0).saturating_sub(n)),
+        });
+    }
+    orchestrators.sort_by(|a, b| b.lag.cmp(&a.lag));
+
+    let mut alerts = Vec::new();
+    if nonce_gap > config.max_nonce_gap {
+        alerts.push(OracleAlert::NonceGap);
+    }
+    if nonce_gap > 0 && seconds_since_observed.is_some_and(|s| s > config.max_observe_delay) {
+        alerts.push(OracleAlert::ObservationStalled);
+    }
+    if orchestrators
+        .iter()
+        .any(|o| o.lag.is_some_and(|l| l > config.max_orchestrator_lag))
+    {
+        alerts.push(OracleAlert::OrchestratorsLagging);
+    }
+
+    OracleLag {
+        latest_eth_event_nonce: latest,
+        last_observed_nonce,
+        nonce_gap,
+        last_observed_at,
+        seconds_since_observed,
+        orchestrators,
+        alerts,
+        updated_at: now,
+    }
+}
+
+#[get("/oracle_lag")]
+pub async fn oracle_lag() -> impl Responder {
+    match get_oracle_lag() {
+        Some(lag) if lag.alerts.is_empty() => HttpResponse::Ok().json(lag),
+        Some(lag) => HttpResponse::ServiceUnavailable().json(lag),
+        None => HttpResponse::InternalServerError()
+            .json("Info not yet generated, please query in 1 minute"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transactions::database::{
+        save_claim, save_eth_event, ClaimDetails, CustomClaim, EthEvent,
+    };
+    use crate::upstream::fake::{self, observed, FakeGravity, TestDb, WETH_ADDRESS};
+
+    const ALICE: &str = "gravity1alice";
+    const BOB: &str = "gravity1bob";
+    const CAROL: &str = "gravity1carol";
+
+    fn details(batch_nonce: u64) -> ClaimDetails {
+        ClaimDetails::BatchSendToEth {
+            batch_nonce,
+            token_contract: WETH_ADDRESS.to_string(),
+        }
+    }
+
+    fn claim(event_nonce: u64, orchestrator: &str, timestamp: i64) -> CustomClaim {
+        fake::claim(
+            event_nonce,
+            orchestrator,
+            timestamp,
+            15_000_000 + event_nonce,
+            details(event_nonce),
+        )
+    }
+
+    fn observed_up_to(event_nonce: u64) -> GravityInfo {
+        GravityInfo {
+            attestations: vec![observed(event_nonce)],
+            ..Default::default()
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_oracle_lag() {
+        let db = TestDb::open("oracle_lag");
+        let config = OracleConfig::default();
+        let started = 1_700_000_000;
+        for nonce in 1..=30 {
+            save_eth_event(
+                &db,
+                &EthEvent {
+                    event_nonce: nonce,
+                    block_height: 15_000_000 + nonce,
+                    tx_hash: None,
+                    block_hash: None,
+                    details: details(nonce),
+                },
+            );
+        }
+        // carol stopped claiming long ago and is no longer checked
+        save_claim(&db, &claim(28, ALICE, started - 60));
+        save_claim(&db, &claim(29, ALICE, started - 30));
+        save_claim(&db, &claim(5, BOB, started - 600));
+        save_claim(&db, &claim(1, CAROL, started - 30 * 24 * 3600));
+        let mut gravity = FakeGravity::default();
+        gravity.last_event_nonces.insert(ALICE.to_string(), 29);
+        gravity.last_event_nonces.insert(BOB.to_string(), 5);
+
+        let lag =
+            compute_oracle_lag(&db, &gravity, Some(&observed_up_to(25)), &config, started).await;
+        assert_eq!(lag.latest_eth_event_nonce, Some(30));
+        assert_eq!(lag.last_observed_nonce, Some(25));
+        assert_eq!(lag.nonce_gap, 5);
+        assert_eq!(lag.seconds_since_observed, Some(0));
+        let orchestrators: Vec<_> = lag
+            .orchestrators
+            .iter()
+            .map(|o| (o.orchestrator.as_str(), o.last_event_nonce, o.lag))
+            .collect();
+        assert_eq!(
+            orchestrators,
+            vec![(BOB, Some(5), Some(25)), (ALICE, Some(29), Some(1))]
+        );
+        assert_eq!(lag.alerts, vec![OracleAlert::OrchestratorsLagging]);
+
+        // bob can't be queried and nothing is observed for an hour, the rest is still reported
+        gravity.last_event_nonces.remove(BOB);
+        let later = started + 3_600;
+        let lag =
+            compute_oracle_lag(&db, &gravity, Some(&observed_up_to(25)), &config, later).await;
+        assert_eq!(lag.last_observed_at, Some(started));
+        assert_eq!(lag.seconds_since_observed, Some(3_600));
+        assert_eq!(lag.orchestrators[0].lag, Some(1));
+        assert_eq!(lag.orchestrators[1].orchestrator, BOB);
+        assert_eq!(lag.orchestrators[1].lag, None);
+        assert_eq!(lag.alerts, vec![OracleAlert::ObservationStalled]);
+
+        // once everything is observed nothing is waiting
+        let lag =
+            compute_oracle_lag(&db, &gravity, Some(&observed_up_to(30)), &config, later).await;
+        assert_eq!(lag.nonce_gap, 0);
+        assert!(lag.alerts.is_empty());
+    }
+}
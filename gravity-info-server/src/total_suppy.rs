@@ -4,27 +4,24 @@
 //! to effectively query all the data in a reasonable amount of time and compute the result locally
 //! This code provides a generic way to compute the total liquid supply for a cosmos chain across all vesting types
 
-use crate::gravity_info::{GRAVITY_NODE_GRPC, GRAVITY_PREFIX, REQUEST_TIMEOUT};
+use crate::config::get_config;
+use crate::gravity_info::REQUEST_TIMEOUT;
+use crate::health::{record_update, Store};
+use crate::metrics::{record_collector_run, SUPPLY_COLLECTOR};
+use crate::upstream::failover::FailoverGravity;
+use crate::upstream::{gravity_height, GravitySource};
 use actix_web::rt::System;
-use cosmos_sdk_proto_althea::cosmos::bank::v1beta1::query_client::QueryClient as BankQueryClient;
-use cosmos_sdk_proto_althea::cosmos::bank::v1beta1::QueryBalanceRequest;
-use cosmos_sdk_proto_althea::cosmos::distribution::v1beta1::query_client::QueryClient as DistQueryClient;
-use cosmos_sdk_proto_althea::cosmos::distribution::v1beta1::QueryDelegationTotalRewardsRequest;
-use cosmos_sdk_proto_althea::cosmos::staking::v1beta1::query_client::QueryClient as StakingQueryClient;
-use cosmos_sdk_proto_althea::cosmos::staking::v1beta1::QueryDelegatorDelegationsRequest;
 use cosmos_sdk_proto_althea::cosmos::vesting::v1beta1::BaseVestingAccount;
 use deep_space::client::types::AccountType;
-use deep_space::client::PAGE;
-use deep_space::error::CosmosGrpcError;
-use deep_space::{Coin, Contact};
+use deep_space::Coin;
 use futures::future::{join3, join_all};
+use gravity_utils::error::GravityError;
 use log::{error, info, trace};
 use num256::Uint256;
 use serde::Serialize;
 use std::sync::{Arc, RwLock};
 use std::thread;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
-use tonic::transport::channel::Channel;
 
 // update once a day
 const LOOP_TIME: Duration = Duration::from_secs(86400);
@@ -73,14 +70,30 @@ pub fn chain_total_supply_thread() {
     thread::spawn(move || loop {
         let runner = System::new();
         runner.block_on(async move {
-            let contact = Contact::new(GRAVITY_NODE_GRPC, REQUEST_TIMEOUT, GRAVITY_PREFIX).unwrap();
-            match compute_liquid_supply(&contact, GRAVITY_DENOM.to_string()).await {
+            let start = Instant::now();
+            let gravity = match FailoverGravity::from_config(&get_config().gravity, REQUEST_TIMEOUT)
+            {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("Invalid Gravity GRPC endpoint {:?}", e);
+                    record_collector_run(SUPPLY_COLLECTOR, start, false);
+                    thread::sleep(Duration::from_secs(5));
+                    return;
+                }
+            };
+            let height = gravity_height(&gravity).await;
+            match compute_liquid_supply(&gravity, GRAVITY_DENOM.to_string()).await {
                 Ok(v) => {
                     info!("Successfully updated supply info!");
                     set_supply_info(v);
+                    record_update(Store::TotalSupply, height, None, Some(LOOP_TIME));
+                    record_collector_run(SUPPLY_COLLECTOR, start, true);
                     thread::sleep(LOOP_TIME);
                 }
-                Err(e) => error!("Failed to update supply info with {:?}", e),
+                Err(e) => {
+                    error!("Failed to update supply info with {:?}", e);
+                    record_collector_run(SUPPLY_COLLECTOR, start, false);
+                }
             }
         });
     });
@@ -98,11 +111,11 @@ pub fn chain_total_supply_thread() {
 ///
 /// Returns liquid supply (not including community pool, including staked but liquid tokens)
 async fn compute_liquid_supply(
-    contact: &Contact,
+    gravity: &dyn GravitySource,
     denom: String,
-) -> Result<ChainTotalSupplyNumbers, CosmosGrpcError> {
+) -> Result<ChainTotalSupplyNumbers, GravityError> {
     // lets do the easy totals first, grand total and communiy pool
-    let totals = contact.query_total_supply().await?;
+    let totals = gravity.total_supply().await?;
     let mut total_supply = None;
     for i in totals {
         if i.denom == denom {
@@ -112,7 +125,7 @@ async fn compute_liquid_supply(
     let total_supply = total_supply.unwrap();
 
     let mut community_pool = None;
-    let pool_totals = contact.query_community_pool().await?;
+    let pool_totals = gravity.community_pool().await?;
     for i in pool_totals {
         if i.denom == denom {
             community_pool = Some(i.amount);
@@ -123,9 +136,9 @@ async fn compute_liquid_supply(
     let start = Instant::now();
     info!("Starting get all accounts");
     // start by getting every account on chain and every balance for every account
-    let accounts = contact.get_all_accounts().await?;
+    let accounts = gravity.all_accounts().await?;
     info!("Got all accounts after {}ms", start.elapsed().as_millis());
-    let users = get_balances_for_accounts(accounts, denom.clone()).await?;
+    let users = get_balances_for_accounts(gravity, accounts, denom.clone()).await?;
     info!(
         "Got all balances/vesting after {}s",
         start.elapsed().as_secs()
@@ -334,10 +347,11 @@ async fn compute_liquid_supply(
 
 /// Dispatching utility function for building an array of joinable futures containing sets of batch requests
 async fn get_balances_for_accounts(
+    gravity: &dyn GravitySource,
     input: Vec<AccountType>,
     denom: String,
-) -> Result<Vec<UserInfo>, CosmosGrpcError> {
-    // handed tuned parameter for the ideal number of queryes per BankQueryClient
+) -> Result<Vec<UserInfo>, GravityError> {
+    // handed tuned parameter for the ideal number of queries per batch
     const BATCH_SIZE: usize = 500;
     info!(
         "Querying {} accounts in {} batches of {}",
@@ -347,14 +361,19 @@ async fn get_balances_for_accounts(
     );
     let mut index = 0;
     let mut futs = Vec::new();
-    while index + BATCH_SIZE < input.len() - 1 {
+    while index + BATCH_SIZE < input.len() {
         futs.push(batch_query_user_information(
+            gravity,
             &input[index..index + BATCH_SIZE],
             denom.clone(),
         ));
         index += BATCH_SIZE;
     }
-    futs.push(batch_query_user_information(&input[index..], denom.clone()));
+    futs.push(batch_query_user_information(
+        gravity,
+        &input[index..],
+        denom.clone(),
+    ));
 
     let executed_futures = join_all(futs).await;
     let mut balances = Vec::new();
@@ -364,28 +383,17 @@ async fn get_balances_for_accounts(
     Ok(balances)
 }
 
-/// Utility function for batching balance requests so that they occupy a single bankqueryclient which represents a connection
-/// to the rpc server, opening connections is overhead intensive so we want to do a few thousand requests per client to really
-/// make it worth our while
+/// Utility function for batching balance requests so that each batch is executed sequentially, this bounds
+/// the number of requests in flight against the node to the number of batches
 async fn batch_query_user_information(
+    gravity: &dyn GravitySource,
     input: &[AccountType],
     denom: String,
-) -> Result<Vec<UserInfo>, CosmosGrpcError> {
+) -> Result<Vec<UserInfo>, GravityError> {
     trace!("Starting batch of {}", input.len());
-    let mut bankrpc = BankQueryClient::connect(GRAVITY_NODE_GRPC).await?;
-    let mut distrpc = DistQueryClient::connect(GRAVITY_NODE_GRPC).await?;
-    let mut stakingrpc = StakingQueryClient::connect(GRAVITY_NODE_GRPC).await?;
-
     let mut ret = Vec::new();
     for account in input {
-        let res = merge_user_information(
-            account.clone(),
-            denom.clone(),
-            &mut bankrpc,
-            &mut distrpc,
-            &mut stakingrpc,
-        )
-        .await?;
+        let res = merge_user_information(gravity, account.clone(), denom.clone()).await?;
         ret.push(res);
     }
     trace!("Finished batch of {}", input.len());
@@ -395,46 +403,28 @@ async fn batch_query_user_information(
 /// utility function for keeping the Account and Balance info
 /// in the same scope rather than zipping them on return
 async fn merge_user_information(
+    gravity: &dyn GravitySource,
     account: AccountType,
     denom: String,
-    bankrpc: &mut BankQueryClient<Channel>,
-    distrpc: &mut DistQueryClient<Channel>,
-    stakingrpc: &mut StakingQueryClient<Channel>,
-) -> Result<UserInfo, CosmosGrpcError> {
+) -> Result<UserInfo, GravityError> {
     // required because dec coins are multiplied by 1*10^18
     const ONE_ETH: u128 = 10u128.pow(18);
 
     let address = account.get_base_account().address;
-    let balance_fut = bankrpc.balance(QueryBalanceRequest {
-        address: address.to_string(),
-        denom: denom.clone(),
-    });
-    let delegation_rewards_fut =
-        distrpc.delegation_total_rewards(QueryDelegationTotalRewardsRequest {
-            delegator_address: address.to_string(),
-        });
-    let total_delegated_fut = stakingrpc.delegator_delegations(QueryDelegatorDelegationsRequest {
-        delegator_addr: address.to_string(),
-        pagination: PAGE,
-    });
+    let balance_fut = gravity.balance(address.to_string(), denom.clone());
+    let delegation_rewards_fut = gravity.delegation_rewards(address.to_string());
+    let total_delegated_fut = gravity.delegations(address.to_string());
 
     let (balance, delegation_rewards, total_delegated) =
         join3(balance_fut, delegation_rewards_fut, total_delegated_fut).await;
 
-    let balance = balance?.into_inner();
-    let delegation_rewards = delegation_rewards?.into_inner();
-    let delegated = total_delegated?.into_inner();
-
-    let balance = match balance.balance {
-        Some(v) => {
-            let v: Coin = v.into();
-            v.amount
-        }
+    let balance = match balance? {
+        Some(v) => v.amount,
         None => 0u8.into(),
     };
 
     let mut delegation_rewards_total: Uint256 = 0u8.into();
-    for reward in delegation_rewards.total {
+    for reward in delegation_rewards? {
         if reward.denom == denom {
             delegation_rewards_total += reward.amount.parse().unwrap();
         }
@@ -443,7 +433,7 @@ async fn merge_user_information(
     delegation_rewards_total /= ONE_ETH.into();
 
     let mut total_delegated: Uint256 = 0u8.into();
-    for delegated in delegated.delegation_responses {
+    for delegated in total_delegated? {
         if let Some(b) = delegated.balance {
             let b: Coin = b.into();
             assert_eq!(b.denom, denom);
@@ -493,11 +483,24 @@ struct UserInfo {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::upstream::fake::FakeGravity;
+
+    #[actix_web::test]
+    async fn test_liquid_supply_offline() {
+        let gravity = FakeGravity::fixture();
+        let supply = compute_liquid_supply(&gravity, GRAVITY_DENOM.to_string())
+            .await
+            .unwrap();
+        assert_eq!(supply.total_supply, 2_489_386_289_699_730u128.into());
+        assert_eq!(supply.community_pool, 938_460_578_037_767u128.into());
+        assert_eq!(supply.total_liquid_supply, 0u8.into());
+    }
 
     #[actix_web::test]
+    #[ignore]
     async fn test_vesting_query() {
-        let contact = Contact::new(GRAVITY_NODE_GRPC, REQUEST_TIMEOUT, GRAVITY_PREFIX).unwrap();
-        let supply = compute_liquid_supply(&contact, GRAVITY_DENOM.to_string())
+        let gravity = FailoverGravity::from_config(&get_config().gravity, REQUEST_TIMEOUT).unwrap();
+        let supply = compute_liquid_supply(&gravity, GRAVITY_DENOM.to_string())
             .await
             .unwrap();
         info!("Got a liquid supply of {:?}", supply);
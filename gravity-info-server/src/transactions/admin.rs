@@ -0,0 +1,602 @@
+//! Maintenance commands for the transaction database, run as `gravity-info-server <command>`
+//! instead of starting the server. They open the database at the configured path, so stop the
+//! server first, RocksDB only allows one process to open it.
+
+use crate::config::get_config;
+use crate::gravity_info::REQUEST_TIMEOUT;
+use crate::transactions::database::{
+    cf, check_meta_entry, delete_msg, eth_event_cf, event_nonce_key, find_by_outgoing_tx_id,
+    get_msg, load_claim, load_confirm, load_deposit, load_event_claims, load_gaps,
+    load_indexer_start, load_last_download_block, load_last_download_block_updated,
+    load_last_valset, load_last_valset_updated, load_msg_cancel_send_to_eth, load_msg_send_to_eth,
+    load_schema_version, msg_cf, search, unlink_cancellation, BatchedSend, ClaimDetails,
+    CustomClaim, CustomConfirm, Deposit, EthEvent, ExecutedBatch, Gap, SendToEthRollup, TxStatus,
+    BATCHED_SENDS, CLAIMS, COLUMN_FAMILIES, CONFIRMS, CONFIRM_ORCHESTRATOR_INDEX, DEPOSITS,
+    DEPOSIT_DESTINATION_INDEX, DEPOSIT_SENDER_INDEX, DEPOSIT_TX_HASH_INDEX,
+    ETH_EVENT_COLUMN_FAMILIES, ETH_UNCONFIRMED_EVENTS, EXECUTED_BATCHES, INDEXED_RANGES,
+    INDEXER_GAPS, META, MSG_COLUMN_FAMILIES, MSG_INDEXES, MSG_KEY_PARTS, ORCHESTRATOR_INDEX,
+    SCHEMA_VERSION_KEY, SEND_TO_ETH_ROLLUPS, TX_STATUS,
+};
+use crate::transactions::handlers::handler_by_name;
+use crate::transactions::records::Record;
+use crate::upstream::failover::FailoverGravity;
+use crate::upstream::GravitySource;
+use clap::Subcommand;
+use rocksdb::{DBIterator, Direction, IteratorMode, DB, DEFAULT_COLUMN_FAMILY_NAME};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Count the entries of every key type and column family
+    Stats,
+    /// Delete everything indexed from Gravity blocks `start` to `end` and download them again
+    Reindex { start: u64, end: u64 },
+    /// Delete everything indexed from Gravity blocks `start` to `end`, the indexer does not
+    /// download them again, use `reindex` for that
+    DeleteRange { start: u64, end: u64 },
+    /// Write every entry of every column family to a JSONL file, `-` for stdout
+    Dump { path: PathBuf },
+    /// Load the entries of a JSONL dump, existing entries with the same key are overwritten
+    Restore { path: PathBuf },
+    /// Check that every key parses and every value deserializes
+    Verify,
+    /// Print the stored last valset and indexer progress
+    State,
+}
+
+pub async fn run(db: &DB, command: Command) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Stats => print_json(&stats(db)),
+        Command::Reindex { start, end } => {
+            let gravity = FailoverGravity::from_config(&get_config().gravity, REQUEST_TIMEOUT)
+                .map_err(|e| format!("Invalid Gravity GRPC endpoint {:?}", e))?;
+            let deleted = reindex(db, &gravity, start, end).await?;
+            println!(
+                "Deleted {} entries and downloaded blocks {} to {} again",
+                deleted, start, end
+            );
+            Ok(())
+        }
+        Command::DeleteRange { start, end } => {
+            let deleted = delete_range(db, start, end);
+            println!(
+                "Deleted {} entries from blocks {} to {}",
+                deleted, start, end
+            );
+            Ok(())
+        }
+        Command::Dump { path } => {
+            let written = if path.as_os_str() == "-" {
+                dump(db, &mut io::stdout().lock())?
+            } else {
+                let mut file = BufWriter::new(File::create(&path)?);
+                let written = dump(db, &mut file)?;
+                file.flush()?;
+                written
+            };
+            eprintln!("Dumped {} entries", written);
+            Ok(())
+        }
+        Command::Restore { path } => {
+            let restored = restore(db, BufReader::new(File::open(&path)?))?;
+            println!("Restored {} entries", restored);
+            Ok(())
+        }
+        Command::Verify => {
+            let problems = verify(db);
+            for problem in problems.iter() {
+                println!("{}", problem);
+            }
+            if problems.is_empty() {
+                println!("No problems found");
+                Ok(())
+            } else {
+                Err(format!("{} problems found", problems.len()).into())
+            }
+        }
+        Command::State => print_json(&state(db)),
+    }
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<(), Box<dyn Error>> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+fn entries<'a>(db: &'a DB, name: &str, mode: IteratorMode) -> DBIterator<'a> {
+    if name == DEFAULT_COLUMN_FAMILY_NAME {
+        db.iterator(mode)
+    } else {
+        db.iterator_cf(cf(db, name), mode)
+    }
+}
+
+fn delete(db: &DB, name: &str, key: &[u8]) {
+    if name == DEFAULT_COLUMN_FAMILY_NAME {
+        db.delete(key).unwrap();
+    } else {
+        db.delete_cf(cf(db, name), key).unwrap();
+    }
+}
+
+/// Every column family, the schema version in the meta column family comes first so a dump
+/// starts with it
+fn all_column_families() -> impl Iterator<Item = &'static str> {
+    COLUMN_FAMILIES
+        .into_iter()
+        .chain(std::iter::once(DEFAULT_COLUMN_FAMILY_NAME))
+}
+
+/// Splits a message key `{height:012}:{msgType}:{timestamp}:{msg_index}:{hash}` into height and
+/// type
+fn parse_msg_key(key: &str) -> Option<(u64, &str)> {
+    let parts: Vec<&str> = key.split(':').collect();
+    if parts.len() != MSG_KEY_PARTS
+        || parts[2].parse::<i64>().is_err()
+        || parts[3].parse::<u32>().is_err()
+        || parts[4].is_empty()
+    {
+        return None;
+    }
+    Some((parts[0].parse().ok()?, parts[1]))
+}
+
+/// Splits a secondary index entry `{value}:{key}` into the indexed value and the key, where the
+/// key has `key_parts` parts
+fn split_index_entry(entry: &str, key_parts: usize) -> Option<(&str, &str)> {
+    let split = entry
+        .rmatch_indices(':')
+        .nth(key_parts - 1)
+        .map(|(i, _)| i)?;
+    Some((&entry[..split], &entry[split + 1..]))
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Stats {
+    pub schema_version: u64,
+    /// Messages by type
+    pub messages: BTreeMap<String, u64>,
+    /// Entries in every column family
+    pub column_families: BTreeMap<String, u64>,
+}
+
+pub fn stats(db: &DB) -> Stats {
+    let mut stats = Stats {
+        schema_version: load_schema_version(db),
+        ..Default::default()
+    };
+    for cf in MSG_COLUMN_FAMILIES {
+        for item in entries(db, cf, IteratorMode::Start) {
+            let (key, _) = item.unwrap();
+            let msg_type = parse_msg_key(&String::from_utf8_lossy(&key))
+                .map(|(_, msg_type)| msg_type.to_string())
+                .unwrap_or_else(|| "invalid".to_string());
+            *stats.messages.entry(msg_type).or_default() += 1;
+        }
+    }
+    for cf in all_column_families() {
+        let count = entries(db, cf, IteratorMode::Start).count() as u64;
+        stats.column_families.insert(cf.to_string(), count);
+    }
+    stats
+}
+
+/// Deletes the messages, claims and confirms of Gravity blocks `start..=end` along with their
+/// secondary index entries and tx statuses, returns how many were deleted
+pub fn delete_range(db: &DB, start: u64, end: u64) -> u64 {
+    let in_range = |height: u64| height >= start && height <= end;
+    let mut deleted = 0;
+
+    let from = format!("{:012}:", start);
+    let to = format!("{:012}:", end + 1);
+    let keys: Vec<String> = MSG_COLUMN_FAMILIES
+        .iter()
+        .flat_map(|cf| {
+            entries(
+                db,
+                cf,
+                IteratorMode::From(from.as_bytes(), Direction::Forward),
+            )
+            .map(|item| String::from_utf8_lossy(&item.unwrap().0).to_string())
+            .take_while(|key| key.as_str() < to.as_str())
+            .filter(|key| parse_msg_key(key).is_some())
+            .collect::<Vec<String>>()
+        })
+        .collect();
+    for cf in MSG_INDEXES {
+        for item in entries(db, cf, IteratorMode::Start) {
+            let (entry, _) = item.unwrap();
+            let entry_str = String::from_utf8_lossy(&entry);
+            let msg = split_index_entry(&entry_str, MSG_KEY_PARTS)
+                .and_then(|(_, key)| parse_msg_key(key));
+            if matches!(msg, Some((height, _)) if in_range(height)) {
+                delete(db, cf, &entry);
+            }
+        }
+    }
+    for key in keys.iter() {
+        // a send outside the range keeps no reference to a cancellation that is gone
+        if key.split(':').nth(1) == Some("msgCancelSendToEth") {
+            if let Some(cancel) = load_msg_cancel_send_to_eth(db, key) {
+                if let Some(send_key) =
+                    find_by_outgoing_tx_id(db, cancel.transaction_id, "msgSendToEth")
+                {
+                    let cancel_hash = key.rsplit(':').next();
+                    let send = load_msg_send_to_eth(db, &send_key);
+                    if send.and_then(|s| s.cancelled_by).as_deref() == cancel_hash {
+                        unlink_cancellation(db, &send_key);
+                    }
+                }
+            }
+        }
+        delete_msg(db, key);
+        delete(db, TX_STATUS, key.rsplit(':').next().unwrap().as_bytes());
+        deleted += 1;
+    }
+
+    let mut deposit_claims = Vec::new();
+    for item in entries(db, CLAIMS, IteratorMode::Start) {
+        let (key, value) = item.unwrap();
+        // the block of a corrupt claim is unknown, it is left for verify to report
+        let claim = match CustomClaim::from_bytes(&value) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!(
+                    "Skipped claim {} that could not be decoded: {}",
+                    String::from_utf8_lossy(&key),
+                    e
+                );
+                continue;
+            }
+        };
+        if in_range(claim.block_number) {
+            let index_entry = format!(
+                "{}:{}",
+                claim.orchestrator.to_lowercase(),
+                String::from_utf8_lossy(&key)
+            );
+            delete(db, ORCHESTRATOR_INDEX, index_entry.as_bytes());
+            delete(db, CLAIMS, &key);
+            deleted += 1;
+            if let ClaimDetails::SendToCosmos {
+                ethereum_sender,
+                cosmos_receiver,
+                ..
+            } = claim.details
+            {
+                deposit_claims.push((claim.event_nonce, ethereum_sender, cosmos_receiver));
+            }
+        }
+    }
+    // deposits only known from the deleted claims are no longer found by address
+    for (event_nonce, sender, destination) in deposit_claims {
+        if !deposit_known(db, event_nonce) {
+            let key = event_nonce_key(event_nonce);
+            let sender_entry = format!("{}:{}", sender.to_lowercase(), key);
+            let destination_entry = format!("{}:{}", destination.to_lowercase(), key);
+            delete(db, DEPOSIT_SENDER_INDEX, sender_entry.as_bytes());
+            delete(db, DEPOSIT_DESTINATION_INDEX, destination_entry.as_bytes());
+        }
+    }
+
+    let confirms: Vec<String> = entries(
+        db,
+        CONFIRMS,
+        IteratorMode::From(from.as_bytes(), Direction::Forward),
+    )
+    .map(|item| String::from_utf8_lossy(&item.unwrap().0).to_string())
+    .take_while(|key| key.as_str() < to.as_str())
+    .collect();
+    for key in confirms {
+        let orchestrator = key.rsplit(':').next().unwrap();
+        let index_entry = format!("{}:{}", orchestrator, key);
+        delete(db, CONFIRM_ORCHESTRATOR_INDEX, index_entry.as_bytes());
+        delete(db, CONFIRMS, key.as_bytes());
+        deleted += 1;
+    }
+    deleted
+}
+
+/// Deletes Gravity blocks `start..=end` and downloads them again, returns how many entries were
+/// deleted
+pub async fn reindex(
+    db: &DB,
+    gravity: &dyn GravitySource,
+    start: u64,
+    end: u64,
+) -> Result<u64, Box<dyn Error>> {
+    const BATCH_SIZE: u64 = 500;
+    let deleted = delete_range(db, start, end);
+    let mut pos = start;
+    while pos <= end {
+        let batch_end = end.min(pos + BATCH_SIZE - 1);
+        search(gravity, pos, batch_end, db)
+            .await
+            .map_err(|(failed_at, e)| {
+                format!(
+                    "Failed to download block {}, run reindex again from there: {:?}",
+                    failed_at, e
+                )
+            })?;
+        pos = batch_end + 1;
+    }
+    Ok(deleted)
+}
+
+/// One line of a dump, keys are stored as text
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DumpEntry {
+    pub cf: String,
+    pub key: String,
+    /// Hex encoded, most values are protobuf
+    pub value: String,
+}
+
+/// Writes every entry as a line of JSON, returns how many were written
+pub fn dump(db: &DB, out: &mut dyn Write) -> io::Result<u64> {
+    let mut written = 0;
+    for cf in all_column_families() {
+        for item in entries(db, cf, IteratorMode::Start) {
+            let (key, value) = item.unwrap();
+            let entry = DumpEntry {
+                cf: cf.to_string(),
+                key: String::from_utf8_lossy(&key).to_string(),
+                value: hex::encode(&value),
+            };
+            serde_json::to_writer(&mut *out, &entry)?;
+            out.write_all(b"\n")?;
+            written += 1;
+        }
+    }
+    Ok(written)
+}
+
+/// Loads the entries of a dump, returns how many were loaded
+pub fn restore(db: &DB, input: impl BufRead) -> Result<u64, Box<dyn Error>> {
+    let mut restored = 0;
+    for (i, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: DumpEntry = serde_json::from_str(&line)
+            .map_err(|e| format!("Invalid entry on line {}: {}", i + 1, e))?;
+        let value = hex::decode(&entry.value)
+            .map_err(|e| format!("Invalid value on line {}: {}", i + 1, e))?;
+        // the layout of the database depends on its version, a dump is only restored into a
+        // database of the same version
+        if entry.cf == META && entry.key == SCHEMA_VERSION_KEY {
+            let version = load_schema_version(db);
+            let dumped = String::from_utf8_lossy(&value);
+            if dumped != version.to_string() {
+                return Err(format!(
+                    "The dump has schema version {} but the database has version {}",
+                    dumped, version
+                )
+                .into());
+            }
+        }
+        if entry.cf == DEFAULT_COLUMN_FAMILY_NAME {
+            db.put(entry.key.as_bytes(), &value)?;
+        } else {
+            let cf = db
+                .cf_handle(&entry.cf)
+                .ok_or_else(|| format!("Unknown column family {} on line {}", entry.cf, i + 1))?;
+            db.put_cf(cf, entry.key.as_bytes(), &value)?;
+        }
+        restored += 1;
+    }
+    Ok(restored)
+}
+
+fn check_json<T: for<'de> Deserialize<'de>>(value: &[u8]) -> Result<(), String> {
+    serde_json::from_slice::<T>(value)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+fn check_record<T: Record>(value: &[u8]) -> Result<(), String> {
+    T::from_bytes(value).map(|_| ())
+}
+
+fn check_u64(value: &[u8]) -> Result<(), String> {
+    String::from_utf8_lossy(value)
+        .parse::<u64>()
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Deposits are indexed both when seen on Ethereum and from their claims
+fn deposit_known(db: &DB, event_nonce: u64) -> bool {
+    load_deposit(db, event_nonce).is_some() || !load_event_claims(db, event_nonce).is_empty()
+}
+
+fn check_entry(db: &DB, cf: &str, key: &str, value: &[u8]) -> Result<(), String> {
+    match cf {
+        DEFAULT_COLUMN_FAMILY_NAME => {
+            Err("entry left in the default column family by an older version".to_string())
+        }
+        META => check_meta_entry(key, value),
+        cf if MSG_COLUMN_FAMILIES.contains(&cf) => match parse_msg_key(key) {
+            Some((_, msg_type)) if msg_cf(msg_type) == Some(cf) => handler_by_name(msg_type)
+                .and_then(|handler| handler.to_json(value))
+                .map(|_| ())
+                .ok_or_else(|| format!("value is not a valid {}", msg_type)),
+            Some((_, msg_type)) => Err(format!("{} in the wrong column family", msg_type)),
+            None => Err("invalid message key".to_string()),
+        },
+        TX_STATUS => check_record::<TxStatus>(value),
+        CLAIMS => check_record::<CustomClaim>(value),
+        CONFIRMS => check_record::<CustomConfirm>(value),
+        INDEXED_RANGES => check_u64(key.as_bytes()).and_then(|_| check_u64(value)),
+        INDEXER_GAPS => check_json::<Gap>(value),
+        SEND_TO_ETH_ROLLUPS => check_record::<SendToEthRollup>(value),
+        BATCHED_SENDS => check_record::<BatchedSend>(value),
+        EXECUTED_BATCHES => check_record::<ExecutedBatch>(value),
+        cf if ETH_EVENT_COLUMN_FAMILIES.contains(&cf) => check_u64(key.as_bytes())
+            .and_then(|_| EthEvent::from_bytes(value))
+            .and_then(|event| match eth_event_cf(&event.details) {
+                event_cf if event_cf == cf => Ok(()),
+                _ => Err("event in the wrong column family".to_string()),
+            }),
+        ETH_UNCONFIRMED_EVENTS => {
+            check_u64(key.as_bytes()).and_then(|_| check_record::<EthEvent>(value))
+        }
+        DEPOSITS => check_record::<Deposit>(value),
+        DEPOSIT_TX_HASH_INDEX | DEPOSIT_SENDER_INDEX | DEPOSIT_DESTINATION_INDEX => {
+            match split_index_entry(key, 1).and_then(|(_, nonce)| nonce.parse().ok()) {
+                Some(nonce) if deposit_known(db, nonce) => Ok(()),
+                _ => Err("index entry for a missing deposit".to_string()),
+            }
+        }
+        ORCHESTRATOR_INDEX => match split_index_entry(key, 2) {
+            Some((_, claim_key)) if load_claim(db, claim_key).is_some() => Ok(()),
+            _ => Err("index entry for a missing claim".to_string()),
+        },
+        CONFIRM_ORCHESTRATOR_INDEX => match split_index_entry(key, 4) {
+            Some((_, confirm_key)) if load_confirm(db, confirm_key).is_some() => Ok(()),
+            _ => Err("index entry for a missing confirm".to_string()),
+        },
+        _ => match split_index_entry(key, MSG_KEY_PARTS) {
+            Some((_, msg_key)) if get_msg(db, msg_key).is_some() => Ok(()),
+            _ => Err("index entry for a missing message".to_string()),
+        },
+    }
+}
+
+/// Checks every entry of every column family, returns a description of each invalid one
+pub fn verify(db: &DB) -> Vec<String> {
+    let mut problems = Vec::new();
+    for cf in all_column_families() {
+        for item in entries(db, cf, IteratorMode::Start) {
+            let (key, value) = item.unwrap();
+            let key = match std::str::from_utf8(&key) {
+                Ok(k) => k,
+                Err(_) => {
+                    problems.push(format!("{}: key {:?} is not valid UTF-8", cf, key));
+                    continue;
+                }
+            };
+            if let Err(e) = check_entry(db, cf, key, &value) {
+                problems.push(format!("{}: {}: {}", cf, key, e));
+            }
+        }
+    }
+    problems
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct State {
+    pub last_valset: Option<serde_json::Value>,
+    pub last_valset_updated: Option<u64>,
+    pub last_download_block: Option<u64>,
+    pub last_download_block_updated: Option<u64>,
+    pub indexer_start_block: Option<u64>,
+    pub gaps: Vec<Gap>,
+}
+
+pub fn state(db: &DB) -> State {
+    State {
+        last_valset: load_last_valset(db).map(|v| serde_json::to_value(v).unwrap()),
+        last_valset_updated: load_last_valset_updated(db),
+        last_download_block: load_last_download_block(db),
+        last_download_block_updated: load_last_download_block_updated(db).map(|(_, t)| t),
+        indexer_start_block: load_indexer_start(db),
+        gaps: load_gaps(db),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transactions::database::{transactions, MSG_SEND_TO_ETH};
+    use crate::upstream::fake::{
+        FakeGravity, TestDb, GRAVITY_CANCEL_BLOCK, GRAVITY_CLAIM_BLOCK, GRAVITY_TX_BLOCK,
+        SEND_TO_ETH_TX_ID,
+    };
+    use std::collections::HashSet;
+
+    #[actix_web::test]
+    async fn test_admin_commands() {
+        let db = TestDb::open("admin_commands");
+        let gravity = FakeGravity::fixture();
+        transactions(&db, &gravity).await.unwrap();
+        assert!(verify(&db).is_empty());
+
+        let before = stats(&db);
+        assert_eq!(before.messages["msgSendToEth"], 2);
+        assert_eq!(before.messages["msgIbcTransfer"], 1);
+        assert_eq!(before.column_families[CLAIMS], 2);
+
+        // a dump restored into an empty database is identical
+        let mut out = Vec::new();
+        let written = dump(&db, &mut out).unwrap();
+        let restored = TestDb::open("admin_restored");
+        assert_eq!(restore(&restored, out.as_slice()).unwrap(), written);
+        assert_eq!(stats(&restored), before);
+        let lines: HashSet<&[u8]> = out.split(|b| *b == b'\n').collect();
+        let mut restored_out = Vec::new();
+        dump(&restored, &mut restored_out).unwrap();
+        assert_eq!(lines, restored_out.split(|b| *b == b'\n').collect());
+
+        // deleting the cancellation leaves the send uncancelled
+        let send_key = find_by_outgoing_tx_id(&db, SEND_TO_ETH_TX_ID, "msgSendToEth").unwrap();
+        assert!(load_msg_send_to_eth(&db, &send_key)
+            .unwrap()
+            .cancelled_by
+            .is_some());
+        assert_eq!(
+            delete_range(&db, GRAVITY_CANCEL_BLOCK, GRAVITY_CLAIM_BLOCK),
+            3
+        );
+        assert!(load_msg_send_to_eth(&db, &send_key)
+            .unwrap()
+            .cancelled_by
+            .is_none());
+        let after = stats(&db);
+        assert!(!after.messages.contains_key("msgCancelSendToEth"));
+        assert_eq!(after.column_families[CLAIMS], 0);
+        assert_eq!(after.column_families[ORCHESTRATOR_INDEX], 0);
+        assert!(verify(&db).is_empty());
+
+        // everything comes back once the blocks are downloaded again
+        reindex(&db, &gravity, GRAVITY_TX_BLOCK, GRAVITY_CLAIM_BLOCK)
+            .await
+            .unwrap();
+        assert_eq!(stats(&db), before);
+        assert!(load_msg_send_to_eth(&db, &send_key)
+            .unwrap()
+            .cancelled_by
+            .is_some());
+
+        // broken values and dangling index entries are reported
+        db.put_cf(
+            db.cf_handle(MSG_SEND_TO_ETH).unwrap(),
+            send_key.as_bytes(),
+            b"\xff",
+        )
+        .unwrap();
+        db.put(b"unknown_key", b"1").unwrap();
+        let problems = verify(&db);
+        assert_eq!(problems.len(), 2);
+        assert!(problems.iter().any(|p| p.contains("unknown_key")));
+        assert!(problems.iter().any(|p| p.contains(&send_key)));
+
+        // a corrupt claim does not stop a range from being deleted
+        let broken_claim = "00000000000000099999:gravity1broken";
+        db.put_cf(
+            db.cf_handle(CLAIMS).unwrap(),
+            broken_claim.as_bytes(),
+            b"\xff",
+        )
+        .unwrap();
+        delete_range(&db, GRAVITY_TX_BLOCK, GRAVITY_CLAIM_BLOCK);
+        assert!(!stats(&db).messages.contains_key("msgIbcTransfer"));
+        assert!(verify(&db).iter().any(|p| p.contains(broken_claim)));
+    }
+}
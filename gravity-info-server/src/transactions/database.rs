@@ -1,62 +1,221 @@
-use crate::gravity_info::{GRAVITY_NODE_GRPC, GRAVITY_PREFIX, REQUEST_TIMEOUT};
+use crate::config::get_config;
+use crate::gravity_info::{GravityInfo, REQUEST_TIMEOUT};
+use crate::health::{record_update, unix_now, Store};
+use crate::metrics::{
+    record_collector_run, record_indexed_blocks, record_indexed_msgs, set_chain_height,
+    set_indexer_gaps, set_indexer_height, INDEXER_COLLECTOR,
+};
+use crate::transactions::handlers::{enabled_handlers, handler_by_name, MsgContext, MsgHandler};
+use crate::transactions::records::Record;
+use crate::upstream::failover::FailoverGravity;
+use crate::upstream::{GravitySource, RawTxResult};
 use actix_rt::System;
+use chrono::DateTime;
 use cosmos_sdk_proto_althea::{
-    cosmos::tx::v1beta1::{TxBody, TxRaw},
+    cosmos::tx::v1beta1::{AuthInfo, TxBody, TxRaw},
     ibc::{applications::transfer::v1::MsgTransfer, core::client::v1::Height},
 };
-use deep_space::{client::Contact, utils::decode_any};
+use deep_space::client::ChainStatus;
+use deep_space::utils::decode_any;
 use futures::future::join_all;
-use gravity_proto::gravity::v1::MsgSendToEth;
+use gravity_proto::gravity::v1::{MsgCancelSendToEth, MsgSendToEth};
+use gravity_utils::error::GravityError;
 use gravity_utils::types::Valset;
 use lazy_static::lazy_static;
-use log::{error, info};
-use rocksdb::DB;
+use log::{error, info, warn};
+use rocksdb::{ColumnFamily, Options, WriteBatch, DB};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::path::Path;
+use std::str::FromStr;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
 use std::{
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, RwLock},
     thread,
     time::Instant,
 };
 use tokio::time::sleep;
 
 lazy_static! {
-    static ref COUNTER: Arc<RwLock<Counters>> = Arc::new(RwLock::new(Counters {
-        blocks: 0,
-        transactions: 0,
-        msgs: 0,
-        ibc_msgs: 0,
-        send_eth_msgs: 0
-    }));
+    static ref COUNTER: Arc<RwLock<Counters>> = Arc::new(RwLock::new(Counters::default()));
 }
 
+#[derive(Default)]
 pub struct Counters {
     blocks: u64,
     transactions: u64,
-    msgs: u64,
-    ibc_msgs: u64,
-    send_eth_msgs: u64,
+    /// Indexed messages by handler name
+    msgs: HashMap<&'static str, u64>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl Counters {
+    /// Lists the message counts as `{count} {name}`, most frequent first
+    fn msgs_summary(&self) -> String {
+        let mut msgs: Vec<(&&str, &u64)> = self.msgs.iter().collect();
+        msgs.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        msgs.iter()
+            .map(|(name, count)| format!("{} {}", count, name))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CustomMsgSendToEth {
-    sender: String,
-    eth_dest: String,
+    pub sender: String,
+    pub eth_dest: String,
     pub amount: Vec<CustomCoin>,
     pub bridge_fee: Vec<CustomCoin>,
     pub chain_fee: Vec<CustomCoin>,
+    /// The id the chain assigned this send in the outgoing tx pool, None if the tx result was
+    /// unavailable or the send was indexed before tx results were read
+    #[serde(default)]
+    pub tx_id: Option<u64>,
+    /// Hash of the tx that cancelled this send and returned the amount and bridge fee to the sender
+    #[serde(default)]
+    pub cancelled_by: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CustomMsgCancelSendToEth {
+    /// The outgoing tx id of the cancelled send
+    pub transaction_id: u64,
+    pub sender: String,
+}
+
+impl From<&MsgCancelSendToEth> for CustomMsgCancelSendToEth {
+    fn from(msg: &MsgCancelSendToEth) -> Self {
+        CustomMsgCancelSendToEth {
+            transaction_id: msg.transaction_id,
+            sender: msg.sender.clone(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CustomMsgTransfer {
-    source_port: String,
-    source_channel: String,
-    token: Vec<CustomCoin>,
-    sender: String,
-    receiver: String,
-    timeout_height: Option<CustomHeight>,
-    timeout_timestamp: u64,
+    pub source_port: String,
+    pub source_channel: String,
+    pub token: Vec<CustomCoin>,
+    pub sender: String,
+    pub receiver: String,
+    pub timeout_height: Option<CustomHeight>,
+    pub timeout_timestamp: u64,
+}
+
+/// A claim an orchestrator submitted attesting to an Ethereum event, along with the Gravity
+/// block and tx it was submitted in
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CustomClaim {
+    pub orchestrator: String,
+    pub event_nonce: u64,
+    pub eth_block_height: u64,
+    pub block_number: u64,
+    pub timestamp: i64,
+    pub tx_hash: String,
+    #[serde(flatten)]
+    pub details: ClaimDetails,
+}
+
+/// The fields specific to each claim type
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "claim_type", rename_all = "snake_case")]
+pub enum ClaimDetails {
+    SendToCosmos {
+        token_contract: String,
+        amount: String,
+        ethereum_sender: String,
+        cosmos_receiver: String,
+    },
+    BatchSendToEth {
+        batch_nonce: u64,
+        token_contract: String,
+    },
+    ValsetUpdated {
+        valset_nonce: u64,
+        members: Vec<CustomBridgeValidator>,
+        reward_amount: String,
+        reward_denom: String,
+        reward_token: String,
+    },
+    Erc20Deployed {
+        cosmos_denom: String,
+        token_contract: String,
+        name: String,
+        symbol: String,
+        decimals: u64,
+    },
+    LogicCallExecuted {
+        /// Hex encoded
+        invalidation_id: String,
+        invalidation_nonce: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CustomBridgeValidator {
+    pub power: u64,
+    pub ethereum_address: String,
+}
+
+/// A signature an orchestrator submitted for a batch, valset or logic call, along with the
+/// Gravity block and tx it was submitted in. The signature itself is not kept.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CustomConfirm {
+    pub orchestrator: String,
+    /// The Ethereum key the orchestrator signed with
+    pub eth_signer: String,
+    pub block_number: u64,
+    pub timestamp: i64,
+    pub tx_hash: String,
+    #[serde(flatten)]
+    pub details: ConfirmDetails,
+}
+
+/// What was signed
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "confirm_type", rename_all = "snake_case")]
+pub enum ConfirmDetails {
+    Batch {
+        nonce: u64,
+        token_contract: String,
+    },
+    Valset {
+        nonce: u64,
+    },
+    LogicCall {
+        invalidation_id: String,
+        invalidation_nonce: u64,
+    },
+}
+
+impl ConfirmDetails {
+    /// Name of the confirm's message type, as used for the other message types
+    pub fn msg_type(&self) -> &'static str {
+        match self {
+            ConfirmDetails::Batch { .. } => "msgConfirmBatch",
+            ConfirmDetails::Valset { .. } => "msgValsetConfirm",
+            ConfirmDetails::LogicCall { .. } => "msgConfirmLogicCall",
+        }
+    }
+
+    /// Identifies the signed batch, valset or logic call among those of its type
+    pub fn item_id(&self) -> String {
+        match self {
+            ConfirmDetails::Batch {
+                nonce,
+                token_contract,
+            } => format!("{}/{}", token_contract.to_lowercase(), nonce),
+            ConfirmDetails::Valset { nonce } => nonce.to_string(),
+            ConfirmDetails::LogicCall {
+                invalidation_id,
+                invalidation_nonce,
+            } => format!("{}/{}", invalidation_id, invalidation_nonce),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -80,9 +239,47 @@ pub struct CustomCoin {
     pub amount: String,
 }
 
+/// How a transaction containing indexed messages executed on chain
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TxStatus {
+    /// Zero if the tx succeeded
+    pub code: u32,
+    /// Only kept for failed txs, the log of a successful tx just repeats its events
+    pub log: String,
+    pub gas_wanted: i64,
+    pub gas_used: i64,
+    pub fee: Vec<CustomCoin>,
+}
+
+impl TxStatus {
+    fn new(result: RawTxResult, fee: Vec<CustomCoin>) -> Self {
+        let succeeded = result.code == 0;
+        TxStatus {
+            code: result.code,
+            log: if succeeded {
+                String::new()
+            } else {
+                result.raw_log
+            },
+            gas_wanted: result.gas_wanted,
+            gas_used: result.gas_used,
+            fee,
+        }
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.code == 0
+    }
+}
+
 #[derive(Serialize)]
 pub struct ApiResponse {
     pub tx_hash: String,
+    /// Only set when a response mixes message types
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub msg_type: Option<String>,
+    /// None for messages indexed before tx results were read or whose result was unavailable
+    pub status: Option<TxStatus>,
     pub data: serde_json::Value,
 }
 
@@ -118,6 +315,8 @@ impl From<&MsgSendToEth> for CustomMsgSendToEth {
                 })
                 .into_iter()
                 .collect(),
+            tx_id: None,
+            cancelled_by: None,
         }
     }
 }
@@ -150,10 +349,10 @@ const MAX_RETRIES: usize = 5;
 /// node will not have history from chain halt upgrades and could be state synced
 /// and missing history before the state sync
 /// Iterative implementation due to the limitations of async recursion in rust.
-async fn get_earliest_block(contact: &Contact, mut start: u64, mut end: u64) -> u64 {
+async fn get_earliest_block(gravity: &dyn GravitySource, mut start: u64, mut end: u64) -> u64 {
     while start <= end {
         let mid = start + (end - start) / 2;
-        let mid_block = contact.get_block(mid).await;
+        let mid_block = gravity.get_block(mid).await;
         if let Ok(Some(_)) = mid_block {
             end = mid - 1;
         } else {
@@ -164,15 +363,22 @@ async fn get_earliest_block(contact: &Contact, mut start: u64, mut end: u64) ->
     start + 1
 }
 
-// Loads sendToEth & MsgTransfer messages from grpc endpoint & downlaods to DB
-async fn search(contact: &Contact, start: u64, end: u64, db: &DB) {
+// Loads the messages of every enabled handler from grpc endpoint & downlaods to DB, on failure
+// returns the first block that was not downloaded
+pub async fn search(
+    gravity: &dyn GravitySource,
+    start: u64,
+    end: u64,
+    db: &DB,
+) -> Result<(), (u64, GravityError)> {
     let mut current_start = start;
     let retries = AtomicUsize::new(0);
+    let handlers = enabled_handlers();
 
     loop {
-        let blocks_result = contact.get_block_range(current_start, end).await;
+        let blocks_result = gravity.get_block_range(current_start, end).await;
 
-        let blocks = match blocks_result {
+        let mut blocks = match blocks_result {
             Ok(result) => {
                 retries.store(0, Ordering::Relaxed);
                 result
@@ -181,7 +387,7 @@ async fn search(contact: &Contact, start: u64, end: u64, db: &DB) {
                 let current_retries = retries.fetch_add(1, Ordering::Relaxed);
                 if current_retries >= MAX_RETRIES {
                     error!("Error getting block range: {:?}, exceeded max retries", e);
-                    break;
+                    return Err((current_start, e));
                 } else {
                     error!("Error getting block range: {:?}, retrying", e);
                     tokio::time::sleep(Duration::from_secs(1)).await;
@@ -190,36 +396,42 @@ async fn search(contact: &Contact, start: u64, end: u64, db: &DB) {
             }
         };
 
+        // every height has to come back, otherwise blocks that were never downloaded would be
+        // checkpointed as indexed. The blocks before a missing one are still indexed
+        let missing = (current_start..)
+            .zip(blocks.iter())
+            .find(|(height, block)| block.height != *height)
+            .map(|(height, _)| height)
+            .or_else(|| blocks.is_empty().then_some(current_start));
+        let missing_error = missing.map(|height| {
+            blocks.truncate((height - current_start) as usize);
+            (
+                height,
+                GravityError::InvalidBridgeStateError(format!(
+                    "Block {} missing from range {} to {}",
+                    height, current_start, end
+                )),
+            )
+        });
         if blocks.is_empty() {
-            break;
+            return Err(missing_error.unwrap());
         }
 
         // gets the last block that was successfully fetched to be referenced
         // in case of grpc error
-        let last_block_height = blocks
-            .last()
-            .unwrap()
-            .as_ref()
-            .unwrap()
-            .header
-            .as_ref()
-            .unwrap()
-            .height;
+        let last_block_height = blocks.last().unwrap().height;
 
-        // counters for transactions, messages, blocks & tx types
+        // counters for transactions, blocks & messages by type
         let mut tx_counter = 0;
-        let mut msg_counter = 0;
-        let mut ibc_transfer_counter = 0;
-        let mut send_eth_counter = 0;
+        let mut msg_counters: HashMap<&'static str, u64> = HashMap::new();
         let blocks_len = blocks.len() as u64;
 
         for block in blocks.into_iter() {
-            let block = block.unwrap();
-            // Get the block number
-            let block_number = block.header.as_ref().unwrap().height;
+            let block_number = block.height;
+            let timestamp = block.time;
 
             // tx fetching
-            for tx in block.data.unwrap().txs {
+            for tx in block.txs {
                 let raw_tx_any = prost_types::Any {
                     type_url: "/cosmos.tx.v1beta1.TxRaw".to_string(),
                     value: tx,
@@ -232,89 +444,116 @@ async fn search(contact: &Contact, start: u64, end: u64, db: &DB) {
                     value: tx_raw.body_bytes,
                 };
                 let tx_body: TxBody = decode_any(body_any).unwrap();
+                let auth_info_any = prost_types::Any {
+                    type_url: "/cosmos.tx.v1beta1.AuthInfo".to_string(),
+                    value: tx_raw.auth_info_bytes,
+                };
+                let auth_info: Option<AuthInfo> = decode_any(auth_info_any).ok();
 
-                let mut has_msg_send_to_eth = false;
-                let mut has_msg_ibc_transfer = false;
+                let msg_handlers: Vec<Option<&dyn MsgHandler>> = tx_body
+                    .messages
+                    .iter()
+                    .map(|m| {
+                        handlers
+                            .iter()
+                            .find(|h| h.type_url() == m.type_url)
+                            .copied()
+                    })
+                    .collect();
+                if msg_handlers.iter().all(Option::is_none) {
+                    continue;
+                }
 
-                // tx sorting
-                for message in tx_body.messages {
-                    if message.type_url == "/gravity.v1.MsgSendToEth" {
-                        has_msg_send_to_eth = true;
-                        msg_counter += 1;
-
-                        let msg_send_to_eth_any = prost_types::Any {
-                            type_url: "/gravity.v1.MsgSendToEth".to_string(),
-                            value: message.value,
-                        };
-                        let msg_send_to_eth: Result<MsgSendToEth, _> =
-                            decode_any(msg_send_to_eth_any);
-
-                        if let Ok(msg_send_to_eth) = msg_send_to_eth {
-                            let custom_msg_send_to_eth = CustomMsgSendToEth::from(&msg_send_to_eth);
-                            let timestamp = block
-                                .header
-                                .as_ref()
-                                .unwrap()
-                                .time
-                                .as_ref()
-                                .unwrap()
-                                .seconds;
-                            let key = format!(
-                                "{:012}:msgSendToEth:{}:{}",
-                                block_number, timestamp, tx_hash
-                            );
-                            save_msg_send_to_eth(db, &key, &custom_msg_send_to_eth);
-                        }
-                    } else if message.type_url == "/ibc.applications.transfer.v1.MsgTransfer" {
-                        has_msg_ibc_transfer = true;
-                        msg_counter += 1;
-
-                        let msg_ibc_transfer_any = prost_types::Any {
-                            type_url: "/ibc.applications.transfer.v1.MsgTransfer".to_string(),
-                            value: message.value,
-                        };
-                        let msg_ibc_transfer: Result<MsgTransfer, _> =
-                            decode_any(msg_ibc_transfer_any);
-
-                        if let Ok(msg_ibc_transfer) = msg_ibc_transfer {
-                            let custom_ibc_transfer = CustomMsgTransfer::from(&msg_ibc_transfer);
-                            let timestamp = block
-                                .header
-                                .as_ref()
-                                .unwrap()
-                                .time
-                                .as_ref()
-                                .unwrap()
-                                .seconds;
-                            let key = format!(
-                                "{:012}:msgIbcTransfer:{}:{}",
-                                block_number, timestamp, tx_hash
-                            );
-                            save_msg_ibc_transfer(db, &key, &custom_ibc_transfer);
-                        }
+                // the execution status and the outgoing tx ids linking sends and cancellations
+                // are only found in the tx result, so it is only requested for txs we index. Without
+                // it a failed tx would be saved as successful, so the block is retried as a gap
+                let tx_result = match gravity.get_tx_result(tx_hash.clone()).await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        error!("Failed to get tx result for {}: {:?}", tx_hash, e);
+                        return Err((block_number, e));
                     }
+                };
+                // saved before the messages so cancellations can check it when linking
+                if let Some(result) = tx_result.clone() {
+                    let fee = auth_info
+                        .and_then(|a| a.fee)
+                        .map(|f| {
+                            f.amount
+                                .into_iter()
+                                .map(|c| CustomCoin {
+                                    denom: c.denom,
+                                    amount: c.amount,
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    save_tx_status(db, &tx_hash, &TxStatus::new(result, fee));
                 }
 
-                if has_msg_send_to_eth {
-                    tx_counter += 1;
-                    send_eth_counter += 1;
+                // tx sorting
+                let mut indexed = false;
+                for (msg_index, (message, handler)) in
+                    tx_body.messages.into_iter().zip(msg_handlers).enumerate()
+                {
+                    let handler = match handler {
+                        Some(h) => h,
+                        None => continue,
+                    };
+                    let ctx = MsgContext {
+                        block_number,
+                        timestamp,
+                        tx_hash: &tx_hash,
+                        msg_index: msg_index as u32,
+                        tx_result: tx_result.as_ref(),
+                    };
+                    if handler.save(db, &ctx, message) {
+                        indexed = true;
+                        *msg_counters.entry(handler.name()).or_default() += 1;
+                        record_indexed_msgs(handler.name(), 1);
+                    }
                 }
-                if has_msg_ibc_transfer {
+                if indexed {
                     tx_counter += 1;
-                    ibc_transfer_counter += 1;
                 }
             }
-            current_start = (last_block_height as u64) + 1;
-            if current_start > end {
-                break;
-            }
         }
         let mut c = COUNTER.write().unwrap();
         c.blocks += blocks_len;
         c.transactions += tx_counter;
-        c.msgs += msg_counter;
-        c.ibc_msgs += ibc_transfer_counter;
-        c.send_eth_msgs += send_eth_counter;
+        for (name, count) in msg_counters {
+            *c.msgs.entry(name).or_default() += count;
+        }
+        record_indexed_blocks(blocks_len, tx_counter);
+
+        if let Some(e) = missing_error {
+            return Err(e);
+        }
+        // a short batch is continued from the block after it
+        current_start = last_block_height + 1;
+        if current_start > end {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Downloads the blocks `start..=end` and records which part of the range was indexed, anything
+/// that failed is recorded as a gap to retry on the next run. Returns true if the whole range
+/// was indexed.
+async fn index_range(gravity: &dyn GravitySource, start: u64, end: u64, db: &DB) -> bool {
+    match search(gravity, start, end, db).await {
+        Ok(()) => {
+            mark_range_indexed(db, start, end);
+            true
+        }
+        Err((failed_at, e)) => {
+            if failed_at > start {
+                mark_range_indexed(db, start, failed_at - 1);
+            }
+            record_gap(db, failed_at, end, format!("{:?}", e));
+            false
+        }
     }
 }
 
@@ -324,18 +563,37 @@ pub fn transaction_info_thread(db: Arc<DB>) {
     thread::spawn(move || loop {
         let runner = System::new();
         runner.block_on(async {
-            match transactions(&db).await {
-                Ok(_) => (),
+            let start = Instant::now();
+            let gravity = match FailoverGravity::from_config(&get_config().gravity, REQUEST_TIMEOUT)
+            {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("Invalid Gravity GRPC endpoint {:?}", e);
+                    record_collector_run(INDEXER_COLLECTOR, start, false);
+                    sleep(Duration::from_secs(3)).await;
+                    return;
+                }
+            };
+            start_backfill();
+            match transactions(&db, &gravity).await {
+                Ok(_) => record_collector_run(INDEXER_COLLECTOR, start, true),
                 Err(e) => {
                     error!("Error downloading transactions: {:?}", e);
+                    record_collector_run(INDEXER_COLLECTOR, start, false);
                     let mut retry_interval = Duration::from_secs(1);
                     loop {
                         info!("Retrying block download");
                         sleep(retry_interval).await;
-                        match transactions(&db).await {
-                            Ok(_) => break,
+                        let start = Instant::now();
+                        start_backfill();
+                        match transactions(&db, &gravity).await {
+                            Ok(_) => {
+                                record_collector_run(INDEXER_COLLECTOR, start, true);
+                                break;
+                            }
                             Err(e) => {
                                 error!("Error in transaction download retry: {:?}", e);
+                                record_collector_run(INDEXER_COLLECTOR, start, false);
                                 retry_interval =
                                     if let Some(new_interval) = retry_interval.checked_mul(2) {
                                         new_interval
@@ -347,20 +605,36 @@ pub fn transaction_info_thread(db: Arc<DB>) {
                     }
                 }
             }
-            tokio::time::sleep(Duration::from_secs(24 * 60 * 60)).await; // Sleep for 24 hours
+
+            // the backfill is done, from here on new blocks are indexed as they are produced
+            info!("Backfill complete, following the chain tip");
+            update_status(|s| {
+                s.phase = IndexerPhase::Follow;
+                s.backfill.completed_at = Some(unix_now());
+                s.follow.started_at = Some(unix_now());
+            });
+            let interval = Duration::from_secs(get_config().indexer.follow_interval);
+            loop {
+                sleep(interval).await;
+                let start = Instant::now();
+                match follow_tip(&db, &gravity).await {
+                    Ok(_) => record_collector_run(INDEXER_COLLECTOR, start, true),
+                    Err(e) => {
+                        error!("Error following the chain tip: {:?}", e);
+                        record_collector_run(INDEXER_COLLECTOR, start, false);
+                        update_status(|s| s.follow.failed_polls += 1);
+                    }
+                }
+            }
         });
     });
 }
 
-/// creates batches of transactions found and sorted using the search function
-/// then writes them to the db
-pub async fn transactions(db: &DB) -> Result<(), Box<dyn std::error::Error>> {
-    info!("Started downloading & parsing transactions");
-    let contact: Contact = Contact::new(GRAVITY_NODE_GRPC, REQUEST_TIMEOUT, GRAVITY_PREFIX)?;
-
+/// Gets the latest block of a running chain, waiting for the node to start producing blocks
+async fn get_latest_block(gravity: &dyn GravitySource) -> Result<u64, Box<dyn std::error::Error>> {
     let mut retries = 0;
     let status = loop {
-        let result = contact.get_chain_status().await;
+        let result = gravity.chain_status().await;
 
         match result {
             Ok(chain_status) => {
@@ -384,13 +658,13 @@ pub async fn transactions(db: &DB) -> Result<(), Box<dyn std::error::Error>> {
     let latest_block;
     loop {
         match current_status {
-            deep_space::client::ChainStatus::Moving { block_height } => {
+            ChainStatus::Moving { block_height } => {
                 latest_block = Some(block_height);
                 break;
             }
-            _ => match contact.get_chain_status().await {
+            _ => match gravity.chain_status().await {
                 Ok(chain_status) => {
-                    if let deep_space::client::ChainStatus::Moving { block_height } = chain_status {
+                    if let ChainStatus::Moving { block_height } = chain_status {
                         latest_block = Some(block_height);
                         break;
                     }
@@ -411,117 +685,2256 @@ pub async fn transactions(db: &DB) -> Result<(), Box<dyn std::error::Error>> {
     }
 
     let latest_block = latest_block.expect("Node is not synced or not running");
+    set_chain_height(latest_block);
+    Ok(latest_block)
+}
+
+/// Downloads the blocks `first..=latest` that are not indexed yet and returns how many were
+/// downloaded, returns an error if any of these ranges failed so it is retried
+async fn index_blocks(
+    db: &DB,
+    gravity: &dyn GravitySource,
+    first: u64,
+    latest: u64,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    // how many blocks to search per future
+    const BATCH_SIZE: u64 = 500;
+    // how many futures to execute at once
+    const EXECUTE_SIZE: usize = 10;
+    // ranges completed by an interrupted run are skipped, gaps are never marked as indexed so
+    // they are downloaded again
+    let ranges = pending_ranges(db, first, latest, BATCH_SIZE);
+    let mut indexed = 0;
+    let mut failed = 0;
+    for chunk in ranges.chunks(EXECUTE_SIZE) {
+        let results = join_all(
+            chunk
+                .iter()
+                .map(|(start, end)| index_range(gravity, *start, *end, db)),
+        )
+        .await;
+        for ((start, end), ok) in chunk.iter().zip(results) {
+            if ok {
+                indexed += end + 1 - start;
+            } else {
+                failed += 1;
+            }
+        }
+        info!(
+            "Completed batch of {} ranges up to block {}",
+            chunk.len(),
+            chunk[chunk.len() - 1].1
+        );
+    }
+
+    // every gap is reported, including those outside of this run
+    let gaps = load_gaps(db);
+    set_indexer_gaps(gaps.len() as u64);
+    for gap in gaps.iter() {
+        error!(
+            "Blocks {} to {} are not indexed after {} attempts: {}",
+            gap.start, gap.end, gap.attempts, gap.last_error
+        );
+    }
+    if failed > 0 {
+        return Err(format!("{} block ranges failed to index", failed).into());
+    }
+    Ok(indexed)
+}
+
+/// Backfill, creates batches of transactions found and sorted using the search function
+/// then writes them to the db
+pub async fn transactions(
+    db: &DB,
+    gravity: &dyn GravitySource,
+) -> Result<(), Box<dyn std::error::Error>> {
+    info!("Started downloading & parsing transactions");
+
+    let latest_block = get_latest_block(gravity).await?;
+    update_status(|s| {
+        s.chain_height = Some(latest_block);
+        s.backfill.target_height = Some(latest_block);
+    });
 
     // now we find the earliest block this node has via binary search, we could just read it from
     // the error message you get when requesting an earlier block, but this was more fun
-    let earliest_block = get_earliest_block(&contact, 0, latest_block).await;
+    let earliest_block = get_earliest_block(gravity, 0, latest_block).await;
 
-    let earliest_block = match load_last_download_block(db) {
-        Some(block) => block,
-        None => earliest_block,
+    // every block up to the tip is indexed, on the first run we start from the earliest block
+    // this node has and keep that start so later runs know where the indexed blocks begin
+    let first_block = match load_last_download_block(db) {
+        Some(block) => {
+            set_indexer_height(block);
+            block + 1
+        }
+        None => {
+            let start = load_indexer_start(db).unwrap_or(earliest_block);
+            save_indexer_start(db, start);
+            start
+        }
     };
 
     info!(
         "This node has {} blocks to download, downloading to database",
-        latest_block - earliest_block
+        (latest_block + 1).saturating_sub(first_block)
     );
     let start = Instant::now();
+    let res = index_blocks(db, gravity, first_block, latest_block)
+        .await
+        .map(|_| ());
 
-    // how many blocks to search per future
-    const BATCH_SIZE: u64 = 500;
-    // how many futures to execute at once
-    const EXECUTE_SIZE: usize = 10;
-    let mut pos = earliest_block;
-    let mut futures = Vec::new();
-    while pos < latest_block {
-        let start = pos;
-        let end = if latest_block - pos > BATCH_SIZE {
-            pos += BATCH_SIZE;
-            pos
-        } else {
-            pos = latest_block;
-            latest_block
+    let counter = COUNTER.read().unwrap();
+    info!(
+        "Successfully downloaded {} blocks and {} tx containing msgs [{}] in {} seconds",
+        counter.blocks,
+        counter.transactions,
+        counter.msgs_summary(),
+        start.elapsed().as_secs()
+    );
+    res
+}
+
+/// Indexes the blocks produced since the tip, returns how many blocks were indexed
+pub async fn follow_tip(
+    db: &DB,
+    gravity: &dyn GravitySource,
+) -> Result<u64, Box<dyn std::error::Error>> {
+    let latest_block = get_latest_block(gravity).await?;
+    let now = unix_now();
+    update_status(|s| {
+        s.chain_height = Some(latest_block);
+        s.follow.polls += 1;
+        s.follow.last_poll = Some(now);
+    });
+    let tip = load_last_download_block(db);
+    let first_block = match tip {
+        Some(block) => block + 1,
+        None => match load_indexer_start(db) {
+            Some(start) => start,
+            None => return Err("The backfill has not started".into()),
+        },
+    };
+    if first_block > latest_block {
+        return Ok(0);
+    }
+
+    // gaps are retried apart from the new blocks, a range that keeps failing is reported as a gap
+    // and does not fail every poll or hold back the new blocks
+    let new_from = load_gaps(db)
+        .iter()
+        .map(|g| g.end + 1)
+        .fold(first_block, u64::max);
+    let mut indexed = 0;
+    if new_from > first_block {
+        let retry_to = (new_from - 1).min(latest_block);
+        match index_blocks(db, gravity, first_block, retry_to).await {
+            Ok(blocks) => indexed += blocks,
+            Err(e) => warn!(
+                "Failed to retry blocks {} to {}: {:?}",
+                first_block, retry_to, e
+            ),
+        }
+    }
+    if new_from <= latest_block {
+        indexed += index_blocks(db, gravity, new_from, latest_block).await?;
+        info!("Indexed blocks {} to {}", new_from, latest_block);
+    }
+    update_status(|s| s.follow.last_indexed_at = Some(now));
+    Ok(indexed)
+}
+
+/// Which phase the indexer is in, it backfills every block up to the chain height seen at startup
+/// and then follows the chain tip
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexerPhase {
+    #[default]
+    Starting,
+    Backfill,
+    Follow,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BackfillStatus {
+    /// Unix time the current or last backfill attempt started
+    pub started_at: Option<u64>,
+    /// Unix time the backfill completed, None while it is running
+    pub completed_at: Option<u64>,
+    /// Number of backfill attempts, more than one if it had to be retried
+    pub attempts: u64,
+    /// Chain height the backfill downloads up to
+    pub target_height: Option<u64>,
+    /// Blocks left to download before the backfill reaches its target
+    pub remaining_blocks: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FollowStatus {
+    /// Unix time the indexer started following the chain tip
+    pub started_at: Option<u64>,
+    /// Unix time of the last poll for new blocks
+    pub last_poll: Option<u64>,
+    /// Unix time new blocks were last indexed
+    pub last_indexed_at: Option<u64>,
+    pub polls: u64,
+    pub failed_polls: u64,
+    /// Blocks the indexer is behind the chain
+    pub lag_blocks: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IndexerStatus {
+    pub phase: IndexerPhase,
+    /// Every block up to this height is indexed
+    pub indexed_height: Option<u64>,
+    /// Latest chain height seen by the indexer
+    pub chain_height: Option<u64>,
+    /// Block ranges that failed to download and are waiting to be retried
+    pub gaps: usize,
+    pub backfill: BackfillStatus,
+    pub follow: FollowStatus,
+}
+
+lazy_static! {
+    static ref INDEXER_STATUS: Arc<RwLock<IndexerStatus>> =
+        Arc::new(RwLock::new(IndexerStatus::default()));
+}
+
+fn update_status(f: impl FnOnce(&mut IndexerStatus)) {
+    f(&mut INDEXER_STATUS.write().unwrap())
+}
+
+fn start_backfill() {
+    update_status(|s| {
+        s.phase = IndexerPhase::Backfill;
+        s.backfill.started_at = Some(unix_now());
+        s.backfill.attempts += 1;
+    });
+}
+
+/// The status of the indexer with its progress read from the database
+pub fn get_indexer_status(db: &DB) -> IndexerStatus {
+    let mut status = INDEXER_STATUS.read().unwrap().clone();
+    status.indexed_height = load_last_download_block(db);
+    status.gaps = load_gaps(db).len();
+    let indexed = status.indexed_height.unwrap_or_default();
+    status.backfill.remaining_blocks = match status.phase {
+        IndexerPhase::Follow => Some(0),
+        _ => status
+            .backfill
+            .target_height
+            .map(|target| target.saturating_sub(indexed)),
+    };
+    status.follow.lag_blocks = match status.phase {
+        IndexerPhase::Follow => status
+            .chain_height
+            .map(|height| height.saturating_sub(indexed)),
+        _ => None,
+    };
+    status
+}
+
+// Messages, a column family per type keyed by
+// `{height:012}:{msgType}:{timestamp}:{msg_index}:{hash}` where msg_index is the position of the
+// message in its tx. The handler of each type names its column family.
+pub const MSG_KEY_PARTS: usize = 5;
+pub const MSG_SEND_TO_ETH: &str = "msg_send_to_eth";
+pub const MSG_CANCEL_SEND_TO_ETH: &str = "msg_cancel_send_to_eth";
+pub const MSG_IBC_TRANSFER: &str = "msg_ibc_transfer";
+pub const MSG_COLUMN_FAMILIES: [&str; 3] =
+    [MSG_SEND_TO_ETH, MSG_CANCEL_SEND_TO_ETH, MSG_IBC_TRANSFER];
+/// The schema version, the indexer's progress and the last valset
+pub const META: &str = "meta";
+/// Execution status of every tx with an indexed message, keyed by tx hash
+pub const TX_STATUS: &str = "tx_status";
+/// Orchestrator claims keyed by `{event_nonce:020}:{orchestrator}`, so the claims for one event
+/// sort together
+pub const CLAIMS: &str = "claims";
+/// Batch, valset and logic call confirms keyed by `{height:012}:{msg_type}:{item_id}:{orchestrator}`
+/// so they can be read by block range
+pub const CONFIRMS: &str = "confirms";
+/// Block ranges indexed past a range that is still missing, keyed by `{start:012}` with the
+/// inclusive end as value. They are folded into the tip once every block before them is indexed.
+pub const INDEXED_RANGES: &str = "indexed_ranges";
+/// Block ranges that failed to download, keyed by `{start:012}`, retried on every run
+pub const INDEXER_GAPS: &str = "indexer_gaps";
+/// Send to eth totals by UTC day and denom keyed by `{date}:{status}:{denom}`, where date is
+/// `YYYY-MM-DD` and status is `ok` or `failed` for sends from txs that failed on chain
+pub const SEND_TO_ETH_ROLLUPS: &str = "send_to_eth_rollups";
+/// The batches each send to eth was seen in, keyed by `{tx_id:020}:{token_contract}:{batch_nonce:020}`
+/// so the batches of one send sort together. Batch contents are only on chain until the batch is
+/// executed or times out, so only batches seen while the server was running are recorded.
+pub const BATCHED_SENDS: &str = "batched_sends";
+/// Batches executed on Ethereum keyed by `{token_contract}:{batch_nonce:020}`, recorded from the
+/// first orchestrator claim or Ethereum event that reports them
+pub const EXECUTED_BATCHES: &str = "executed_batches";
+/// Deposits seen on Ethereum keyed by `{event_nonce:020}`, with the time each stage was first
+/// seen. Only deposits seen while the server was running are recorded.
+pub const DEPOSITS: &str = "deposits";
+
+// Events emitted by the Gravity contract on Ethereum, a column family per type keyed by
+// `{event_nonce:020}`. Event nonces are shared by every type and increase with block height.
+pub const ETH_SEND_TO_COSMOS: &str = "eth_send_to_cosmos";
+pub const ETH_BATCH_EXECUTED: &str = "eth_batch_executed";
+pub const ETH_VALSET_UPDATED: &str = "eth_valset_updated";
+pub const ETH_ERC20_DEPLOYED: &str = "eth_erc20_deployed";
+pub const ETH_LOGIC_CALL_EXECUTED: &str = "eth_logic_call_executed";
+pub const ETH_EVENT_COLUMN_FAMILIES: [&str; 5] = [
+    ETH_SEND_TO_COSMOS,
+    ETH_BATCH_EXECUTED,
+    ETH_VALSET_UPDATED,
+    ETH_ERC20_DEPLOYED,
+    ETH_LOGIC_CALL_EXECUTED,
+];
+/// Events of every type from blocks that are not finalized yet, keyed by `{event_nonce:020}`.
+/// They are replaced on every sync, an event that changed or disappeared was rolled back by a
+/// reorg.
+pub const ETH_UNCONFIRMED_EVENTS: &str = "eth_unconfirmed_events";
+
+// Column families holding the secondary indexes, entries are `{value}:{key}` with an empty value
+// where key is the message's key, so the entries for one indexed
+// value sort by block height. Addresses are stored lowercase.
+pub const SENDER_INDEX: &str = "index_sender";
+pub const RECEIVER_INDEX: &str = "index_receiver";
+pub const DENOM_INDEX: &str = "index_denom";
+pub const CHANNEL_INDEX: &str = "index_channel";
+pub const TX_HASH_INDEX: &str = "index_tx_hash";
+/// Sends and cancellations by outgoing tx id, formatted as `{:020}`
+pub const OUTGOING_TX_ID_INDEX: &str = "index_outgoing_tx_id";
+/// The secondary indexes of messages
+pub const MSG_INDEXES: [&str; 6] = [
+    SENDER_INDEX,
+    RECEIVER_INDEX,
+    DENOM_INDEX,
+    CHANNEL_INDEX,
+    TX_HASH_INDEX,
+    OUTGOING_TX_ID_INDEX,
+];
+/// Claims by orchestrator, the keys are those of the `CLAIMS` column family so the entries for
+/// one orchestrator sort by event nonce
+pub const ORCHESTRATOR_INDEX: &str = "index_orchestrator";
+/// Confirms by orchestrator, the keys are those of the `CONFIRMS` column family so the entries
+/// for one orchestrator sort by height
+pub const CONFIRM_ORCHESTRATOR_INDEX: &str = "index_confirm_orchestrator";
+/// Deposits by Ethereum tx hash, sender and destination, the keys are event nonces formatted as
+/// `{:020}` so the entries for one value sort by nonce. Senders and destinations are also indexed
+/// from deposit claims, so deposits from before the server ran can still be found.
+pub const DEPOSIT_TX_HASH_INDEX: &str = "index_deposit_tx_hash";
+pub const DEPOSIT_SENDER_INDEX: &str = "index_deposit_sender";
+pub const DEPOSIT_DESTINATION_INDEX: &str = "index_deposit_destination";
+pub const COLUMN_FAMILIES: [&str; 30] = [
+    META,
+    MSG_SEND_TO_ETH,
+    MSG_CANCEL_SEND_TO_ETH,
+    MSG_IBC_TRANSFER,
+    TX_STATUS,
+    CLAIMS,
+    CONFIRMS,
+    INDEXED_RANGES,
+    INDEXER_GAPS,
+    SEND_TO_ETH_ROLLUPS,
+    BATCHED_SENDS,
+    EXECUTED_BATCHES,
+    DEPOSITS,
+    ETH_SEND_TO_COSMOS,
+    ETH_BATCH_EXECUTED,
+    ETH_VALSET_UPDATED,
+    ETH_ERC20_DEPLOYED,
+    ETH_LOGIC_CALL_EXECUTED,
+    ETH_UNCONFIRMED_EVENTS,
+    ORCHESTRATOR_INDEX,
+    CONFIRM_ORCHESTRATOR_INDEX,
+    DEPOSIT_TX_HASH_INDEX,
+    DEPOSIT_SENDER_INDEX,
+    DEPOSIT_DESTINATION_INDEX,
+    SENDER_INDEX,
+    RECEIVER_INDEX,
+    DENOM_INDEX,
+    CHANNEL_INDEX,
+    TX_HASH_INDEX,
+    OUTGOING_TX_ID_INDEX,
+];
+
+/// Opens the database at `path`, creating it and any missing column families and migrating it
+/// to the current schema version
+pub fn open_database<P: AsRef<Path>>(path: P) -> Result<DB, Box<dyn std::error::Error>> {
+    let mut options = Options::default();
+    options.create_if_missing(true);
+    options.create_missing_column_families(true);
+    let db = DB::open_cf(&options, path, COLUMN_FAMILIES)?;
+    migrate(&db)?;
+    Ok(db)
+}
+
+/// The handle of the column family `name`, `open_database` creates every one of them
+pub fn cf<'a>(db: &'a DB, name: &str) -> &'a ColumnFamily {
+    db.cf_handle(name)
+        .expect("Database opened without column families")
+}
+
+/// Decodes a stored record, a value this version can't read is logged and skipped rather than
+/// taking the server down
+fn decode<T: Record>(key: &str, bytes: &[u8]) -> Option<T> {
+    match T::from_bytes(bytes) {
+        Ok(v) => Some(v),
+        Err(e) => {
+            error!("Failed to decode {}: {}", key, e);
+            None
+        }
+    }
+}
+
+/// Like `decode` for the indexer state, which is small and kept as JSON
+fn decode_json<T: DeserializeOwned>(key: &str, bytes: &[u8]) -> Option<T> {
+    match serde_json::from_slice(bytes) {
+        Ok(v) => Some(v),
+        Err(e) => {
+            error!("Failed to decode {}: {}", key, e);
+            None
+        }
+    }
+}
+
+/// Column family holding the messages of type `msg_type`
+pub fn msg_cf(msg_type: &str) -> Option<&'static str> {
+    handler_by_name(msg_type)?.column_family()
+}
+
+/// The message stored under `key`, whose type is the second part of the key
+pub fn get_msg(db: &DB, key: &str) -> Option<Vec<u8>> {
+    let cf = cf(db, msg_cf(key.split(':').nth(1)?)?);
+    db.get_cf(cf, key.as_bytes()).unwrap()
+}
+
+fn put_msg(db: &DB, key: &str, value: &[u8]) {
+    let msg_type = key.split(':').nth(1).unwrap();
+    let cf = cf(
+        db,
+        msg_cf(msg_type).expect("Message type without a column family"),
+    );
+    db.put_cf(cf, key.as_bytes(), value).unwrap();
+}
+
+pub fn delete_msg(db: &DB, key: &str) {
+    if key.split(':').nth(1) == Some("msgSendToEth") {
+        if let Some(send) = load_msg_send_to_eth(db, key) {
+            update_rollups(db, key, Some(&send), None);
+        }
+    }
+    if let Some(name) = key.split(':').nth(1).and_then(msg_cf) {
+        db.delete_cf(cf(db, name), key.as_bytes()).unwrap();
+    }
+}
+
+fn get_meta(db: &DB, key: &str) -> Option<Vec<u8>> {
+    let cf = cf(db, META);
+    db.get_cf(cf, key.as_bytes()).unwrap()
+}
+
+/// A number stored as a string, a value that doesn't parse is logged and treated as missing
+fn get_meta_number<T: FromStr>(db: &DB, key: &str) -> Option<T>
+where
+    T::Err: Display,
+{
+    let bytes = get_meta(db, key)?;
+    match String::from_utf8_lossy(&bytes).parse::<T>() {
+        Ok(v) => Some(v),
+        Err(e) => {
+            error!("Failed to decode {}: {}", key, e);
+            None
+        }
+    }
+}
+
+fn get_meta_u64(db: &DB, key: &str) -> Option<u64> {
+    get_meta_number(db, key)
+}
+
+fn put_meta(db: &DB, key: &str, value: &[u8]) {
+    let cf = cf(db, META);
+    db.put_cf(cf, key.as_bytes(), value).unwrap();
+}
+
+pub fn save_tx_status(db: &DB, tx_hash: &str, status: &TxStatus) {
+    let cf = cf(db, TX_STATUS);
+    db.put_cf(cf, tx_hash.as_bytes(), status.to_bytes())
+        .unwrap();
+}
+
+pub fn load_tx_status(db: &DB, tx_hash: &str) -> Option<TxStatus> {
+    let cf = cf(db, TX_STATUS);
+    let res = db.get_cf(cf, tx_hash.as_bytes()).unwrap();
+    res.and_then(|bytes| decode(tx_hash, &bytes))
+}
+
+/// False only if the tx is known to have failed, txs without a recorded status were indexed
+/// before statuses were and are assumed to have succeeded
+pub fn tx_succeeded(db: &DB, tx_hash: &str) -> bool {
+    !matches!(load_tx_status(db, tx_hash), Some(status) if !status.succeeded())
+}
+
+fn put_index(db: &DB, index: &str, value: &str, key: &str) {
+    let cf = cf(db, index);
+    db.put_cf(cf, format!("{}:{}", value, key).as_bytes(), b"")
+        .unwrap();
+}
+
+fn delete_index(db: &DB, index: &str, value: &str, key: &str) {
+    let cf = cf(db, index);
+    db.delete_cf(cf, format!("{}:{}", value, key).as_bytes())
+        .unwrap();
+}
+
+/// Indexes the message under `key` by the hash of its transaction, the last part of the key
+fn index_tx_hash(db: &DB, key: &str) {
+    let tx_hash = key.rsplit(':').next().unwrap();
+    put_index(db, TX_HASH_INDEX, tx_hash, key);
+}
+
+pub fn index_msg_send_to_eth(db: &DB, key: &str, data: &CustomMsgSendToEth) {
+    index_tx_hash(db, key);
+    put_index(db, SENDER_INDEX, &data.sender.to_lowercase(), key);
+    put_index(db, RECEIVER_INDEX, &data.eth_dest.to_lowercase(), key);
+    for coin in data.amount.iter() {
+        put_index(db, DENOM_INDEX, &coin.denom, key);
+    }
+    if let Some(tx_id) = data.tx_id {
+        put_index(db, OUTGOING_TX_ID_INDEX, &format!("{:020}", tx_id), key);
+    }
+}
+
+pub fn index_msg_cancel_send_to_eth(db: &DB, key: &str, data: &CustomMsgCancelSendToEth) {
+    index_tx_hash(db, key);
+    put_index(db, SENDER_INDEX, &data.sender.to_lowercase(), key);
+    put_index(
+        db,
+        OUTGOING_TX_ID_INDEX,
+        &format!("{:020}", data.transaction_id),
+        key,
+    );
+}
+
+/// Finds the key of the `msg_type` message with outgoing tx id `tx_id`
+pub fn find_by_outgoing_tx_id(db: &DB, tx_id: u64, msg_type: &str) -> Option<String> {
+    let cf = cf(db, OUTGOING_TX_ID_INDEX);
+    let prefix = format!("{:020}:", tx_id);
+    for item in db.prefix_iterator_cf(cf, prefix.as_bytes()) {
+        let (index_key, _) = item.unwrap();
+        let index_key = String::from_utf8_lossy(&index_key);
+        let key = match index_key.strip_prefix(&prefix) {
+            Some(k) => k,
+            None => break,
         };
-        let fut = search(&contact, start, end, db);
-        futures.push(fut);
+        if key.split(':').nth(1) == Some(msg_type) {
+            return Some(key.to_string());
+        }
     }
+    None
+}
 
-    let futures = futures.into_iter();
+pub fn index_msg_ibc_transfer(db: &DB, key: &str, data: &CustomMsgTransfer) {
+    index_tx_hash(db, key);
+    put_index(db, SENDER_INDEX, &data.sender.to_lowercase(), key);
+    put_index(db, RECEIVER_INDEX, &data.receiver.to_lowercase(), key);
+    for coin in data.token.iter() {
+        put_index(db, DENOM_INDEX, &coin.denom, key);
+    }
+    put_index(db, CHANNEL_INDEX, &data.source_channel, key);
+}
 
-    let mut buf = Vec::new();
+// the version of the secondary indexes covering every message downloaded before they existed,
+// databases from before schema versions record it in the default column family
+const SECONDARY_INDEXES_BUILT_KEY: &str = "secondary_indexes_built";
+const SECONDARY_INDEXES_VERSION: u64 = 2;
 
-    for fut in futures {
-        if buf.len() < EXECUTE_SIZE {
-            buf.push(fut);
+/// Indexes messages downloaded into the default column family before the secondary indexes
+/// were introduced, a no-op for databases that already built them
+fn build_secondary_indexes(db: &DB) {
+    // an unreadable version is treated as not built, indexing again is harmless
+    let built = db
+        .get(SECONDARY_INDEXES_BUILT_KEY.as_bytes())
+        .unwrap()
+        .and_then(|bytes| String::from_utf8_lossy(&bytes).parse::<u64>().ok());
+    if built >= Some(SECONDARY_INDEXES_VERSION) {
+        return;
+    }
+    info!("Building secondary indexes for existing transactions");
+    let mut indexed = 0;
+    let mut skipped = 0;
+    for item in db.iterator(rocksdb::IteratorMode::Start) {
+        let (key, value) = item.unwrap();
+        let key_str = String::from_utf8_lossy(&key);
+        let key_parts: Vec<&str> = key_str.split(':').collect();
+        if key_parts.len() != 4 {
+            continue;
+        }
+        // values were still JSON then and these were the only message types, values that don't
+        // decode are logged and left unindexed
+        let decoded = match key_parts[1] {
+            "msgSendToEth" => decode_json(&key_str, &value)
+                .map(|msg| index_msg_send_to_eth(db, &key_str, &msg))
+                .is_some(),
+            "msgCancelSendToEth" => decode_json(&key_str, &value)
+                .map(|msg| index_msg_cancel_send_to_eth(db, &key_str, &msg))
+                .is_some(),
+            "msgIbcTransfer" => decode_json(&key_str, &value)
+                .map(|msg| index_msg_ibc_transfer(db, &key_str, &msg))
+                .is_some(),
+            _ => continue,
+        };
+        if decoded {
+            indexed += 1;
         } else {
-            let _ = join_all(buf).await;
-            info!(
-                "Completed batch of {} blocks",
-                BATCH_SIZE * EXECUTE_SIZE as u64
-            );
-            buf = Vec::new();
+            skipped += 1;
         }
     }
-    let _ = join_all(buf).await;
-
-    let counter = COUNTER.read().unwrap();
+    db.put(
+        SECONDARY_INDEXES_BUILT_KEY.as_bytes(),
+        SECONDARY_INDEXES_VERSION.to_string().as_bytes(),
+    )
+    .unwrap();
     info!(
-        "Successfully downloaded {} blocks and {} tx containing {} send_to_eth msgs and {} ibc_transfer msgs in {} seconds",
-        counter.blocks,
-        counter.transactions,
-        counter.send_eth_msgs,
-        counter.ibc_msgs,
-        start.elapsed().as_secs()
+        "Indexed {} existing messages, skipped {} that could not be decoded",
+        indexed, skipped
     );
-    save_last_download_block(db, latest_block);
-    Ok(())
 }
 
 //saves serialized transactions to database
 pub fn save_msg_send_to_eth(db: &DB, key: &str, data: &CustomMsgSendToEth) {
-    let data_json = serde_json::to_string(data).unwrap();
-    db.put(key.as_bytes(), data_json.as_bytes()).unwrap();
+    // the range may be downloaded again, the send must not be counted twice
+    let previous = load_msg_send_to_eth(db, key);
+    put_msg(db, key, &data.to_bytes());
+    update_rollups(db, key, previous.as_ref(), Some(data));
+    index_msg_send_to_eth(db, key, data);
+    // block ranges are downloaded concurrently so the cancellation may already be indexed
+    if let Some(tx_id) = data.tx_id {
+        if let Some(cancel_key) = find_by_outgoing_tx_id(db, tx_id, "msgCancelSendToEth") {
+            link_cancellation(db, key, &cancel_key);
+        }
+    }
+}
+
+/// Marks the send stored under `send_key` as cancelled by the tx of `cancel_key`, unless that
+/// tx failed and the send was never cancelled
+fn link_cancellation(db: &DB, send_key: &str, cancel_key: &str) {
+    let cancel_hash = cancel_key.rsplit(':').next().unwrap();
+    if !tx_succeeded(db, cancel_hash) {
+        return;
+    }
+    let previous = match load_msg_send_to_eth(db, send_key) {
+        Some(v) => v,
+        None => return,
+    };
+    let send = CustomMsgSendToEth {
+        cancelled_by: Some(cancel_hash.to_string()),
+        ..previous.clone()
+    };
+    put_msg(db, send_key, &send.to_bytes());
+    update_rollups(db, send_key, Some(&previous), Some(&send));
+}
+
+/// Clears the cancellation of the send stored under `send_key`, for when the cancelling message is
+/// removed from the database
+pub fn unlink_cancellation(db: &DB, send_key: &str) {
+    let previous = match load_msg_send_to_eth(db, send_key) {
+        Some(v) => v,
+        None => return,
+    };
+    let send = CustomMsgSendToEth {
+        cancelled_by: None,
+        ..previous.clone()
+    };
+    put_msg(db, send_key, &send.to_bytes());
+    update_rollups(db, send_key, Some(&previous), Some(&send));
+}
+
+pub fn save_msg_cancel_send_to_eth(db: &DB, key: &str, data: &CustomMsgCancelSendToEth) {
+    put_msg(db, key, &data.to_bytes());
+    index_msg_cancel_send_to_eth(db, key, data);
+    if let Some(send_key) = find_by_outgoing_tx_id(db, data.transaction_id, "msgSendToEth") {
+        link_cancellation(db, &send_key, key);
+    }
 }
 
 pub fn save_msg_ibc_transfer(db: &DB, key: &str, data: &CustomMsgTransfer) {
-    let data_json = serde_json::to_string(data).unwrap();
-    db.put(key.as_bytes(), data_json.as_bytes()).unwrap();
+    put_msg(db, key, &data.to_bytes());
+    index_msg_ibc_transfer(db, key, data);
 }
 
 // Load & deseralize transactions
 pub fn load_msg_send_to_eth(db: &DB, key: &str) -> Option<CustomMsgSendToEth> {
-    let res = db.get(key.as_bytes()).unwrap();
-    res.map(|bytes| serde_json::from_slice::<CustomMsgSendToEth>(&bytes).unwrap())
+    get_msg(db, key).and_then(|bytes| decode(key, &bytes))
 }
 
 pub fn load_msg_ibc_transfer(db: &DB, key: &str) -> Option<CustomMsgTransfer> {
-    let res = db.get(key.as_bytes()).unwrap();
-    res.map(|bytes| serde_json::from_slice::<CustomMsgTransfer>(&bytes).unwrap())
+    get_msg(db, key).and_then(|bytes| decode(key, &bytes))
 }
 
-const VALSET_KEY: &str = "last_valset";
-pub fn save_last_valset(db: &DB, data: &Valset) {
-    let data_json = serde_json::to_string(data).unwrap();
-    db.put(VALSET_KEY.as_bytes(), data_json.as_bytes()).unwrap();
+pub fn load_msg_cancel_send_to_eth(db: &DB, key: &str) -> Option<CustomMsgCancelSendToEth> {
+    get_msg(db, key).and_then(|bytes| decode(key, &bytes))
 }
 
-pub fn load_last_valset(db: &DB) -> Option<Valset> {
-    let res = db.get(VALSET_KEY.as_bytes()).unwrap();
-    res.map(|bytes| serde_json::from_slice::<Valset>(&bytes).unwrap())
+/// The sends of one UTC day in one denom, kept up to date as sends are saved, cancelled and
+/// deleted so totals are read without going through every send
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SendToEthRollup {
+    pub amount: u128,
+    pub bridge_fee: u128,
+    pub chain_fee: u128,
+    /// Sends of an amount in this denom
+    pub count: u64,
+    /// Sends that were later cancelled, not included in the amount, bridge fee and count above
+    pub cancelled_amount: u128,
+    pub cancelled_bridge_fee: u128,
+    pub cancelled_count: u64,
 }
 
-// timestamp function using downloaded blocks as a source of truth
-const LAST_DOWNLOAD_BLOCK_KEY: &str = "last_download_block";
+impl SendToEthRollup {
+    pub fn add(&mut self, other: &SendToEthRollup) {
+        self.amount += other.amount;
+        self.bridge_fee += other.bridge_fee;
+        self.chain_fee += other.chain_fee;
+        self.count += other.count;
+        self.cancelled_amount += other.cancelled_amount;
+        self.cancelled_bridge_fee += other.cancelled_bridge_fee;
+        self.cancelled_count += other.cancelled_count;
+    }
 
-fn save_last_download_block(db: &DB, timestamp: u64) {
-    db.put(
-        LAST_DOWNLOAD_BLOCK_KEY.as_bytes(),
-        timestamp.to_string().as_bytes(),
-    )
-    .unwrap();
+    fn subtract(&mut self, other: &SendToEthRollup) {
+        self.amount = self.amount.saturating_sub(other.amount);
+        self.bridge_fee = self.bridge_fee.saturating_sub(other.bridge_fee);
+        self.chain_fee = self.chain_fee.saturating_sub(other.chain_fee);
+        self.count = self.count.saturating_sub(other.count);
+        self.cancelled_amount = self.cancelled_amount.saturating_sub(other.cancelled_amount);
+        self.cancelled_bridge_fee = self
+            .cancelled_bridge_fee
+            .saturating_sub(other.cancelled_bridge_fee);
+        self.cancelled_count = self.cancelled_count.saturating_sub(other.cancelled_count);
+    }
+
+    /// What a send adds to the rollup of each denom it involves. Cancelled sends return the
+    /// amount and bridge fee to the sender so those are counted separately, the chain fee is kept
+    /// by the chain either way.
+    fn of_send(msg: &CustomMsgSendToEth) -> HashMap<String, SendToEthRollup> {
+        let parse = |coin: &CustomCoin| coin.amount.parse::<u128>().unwrap_or_default();
+        let cancelled = msg.cancelled_by.is_some();
+        let mut rollups: HashMap<String, SendToEthRollup> = HashMap::new();
+        for coin in &msg.amount {
+            let rollup = rollups.entry(coin.denom.clone()).or_default();
+            if cancelled {
+                rollup.cancelled_amount += parse(coin);
+                rollup.cancelled_count += 1;
+            } else {
+                rollup.amount += parse(coin);
+                rollup.count += 1;
+            }
+        }
+        for coin in &msg.bridge_fee {
+            let rollup = rollups.entry(coin.denom.clone()).or_default();
+            if cancelled {
+                rollup.cancelled_bridge_fee += parse(coin);
+            } else {
+                rollup.bridge_fee += parse(coin);
+            }
+        }
+        for coin in &msg.chain_fee {
+            rollups.entry(coin.denom.clone()).or_default().chain_fee += parse(coin);
+        }
+        rollups
+    }
 }
 
-fn load_last_download_block(db: &DB) -> Option<u64> {
-    let res = db.get(LAST_DOWNLOAD_BLOCK_KEY.as_bytes()).unwrap();
-    res.map(|bytes| String::from_utf8_lossy(&bytes).parse::<u64>().unwrap())
+/// The UTC day of a unix timestamp as `YYYY-MM-DD`
+pub fn rollup_date(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .unwrap_or_default()
+        .format("%Y-%m-%d")
+        .to_string()
+}
+
+fn rollup_key(date: &str, succeeded: bool, denom: &str) -> String {
+    let status = if succeeded { "ok" } else { "failed" };
+    format!("{}:{}:{}", date, status, denom)
+}
+
+lazy_static! {
+    // rollups are read, modified and written back, one update at a time
+    static ref ROLLUPS_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// Replaces `previous`, the send stored under `key` before, with `current` in the rollups of
+/// its day, either is None for a send that is being added or deleted. The tx status must be saved
+/// before the send and deleted after it.
+fn update_rollups(
+    db: &DB,
+    key: &str,
+    previous: Option<&CustomMsgSendToEth>,
+    current: Option<&CustomMsgSendToEth>,
+) {
+    let key_parts: Vec<&str> = key.split(':').collect();
+    let timestamp = match key_parts.get(2).and_then(|t| t.parse::<i64>().ok()) {
+        Some(v) => v,
+        None => return,
+    };
+    let date = rollup_date(timestamp);
+    let succeeded = tx_succeeded(db, key_parts[key_parts.len() - 1]);
+    let cf = cf(db, SEND_TO_ETH_ROLLUPS);
+
+    let _lock = ROLLUPS_LOCK.lock().unwrap();
+    let load = |denom: &str| -> SendToEthRollup {
+        let key = rollup_key(&date, succeeded, denom);
+        db.get_cf(cf, key.as_bytes())
+            .unwrap()
+            .and_then(|bytes| decode(&key, &bytes))
+            .unwrap_or_default()
+    };
+    let mut rows: HashMap<String, SendToEthRollup> = HashMap::new();
+    for (denom, rollup) in previous.map(SendToEthRollup::of_send).unwrap_or_default() {
+        rows.entry(denom.clone())
+            .or_insert_with(|| load(&denom))
+            .subtract(&rollup);
+    }
+    for (denom, rollup) in current.map(SendToEthRollup::of_send).unwrap_or_default() {
+        rows.entry(denom.clone())
+            .or_insert_with(|| load(&denom))
+            .add(&rollup);
+    }
+    for (denom, rollup) in rows {
+        let key = rollup_key(&date, succeeded, &denom);
+        if rollup == SendToEthRollup::default() {
+            db.delete_cf(cf, key.as_bytes()).unwrap();
+        } else {
+            db.put_cf(cf, key.as_bytes(), rollup.to_bytes()).unwrap();
+        }
+    }
+}
+
+/// A row of the send to eth rollups
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RollupEntry {
+    /// `YYYY-MM-DD`
+    pub date: String,
+    /// False for sends from txs that failed on chain
+    pub succeeded: bool,
+    pub denom: String,
+    pub rollup: SendToEthRollup,
+}
+
+/// The rollups of every day from `from`, or the first day with a send, to `to`, both `YYYY-MM-DD`
+/// and inclusive
+pub fn load_rollups(db: &DB, from: Option<&str>, to: &str) -> Vec<RollupEntry> {
+    let cf = cf(db, SEND_TO_ETH_ROLLUPS);
+    let mode = match from {
+        Some(from) => rocksdb::IteratorMode::From(from.as_bytes(), rocksdb::Direction::Forward),
+        None => rocksdb::IteratorMode::Start,
+    };
+    let mut entries = Vec::new();
+    for item in db.iterator_cf(cf, mode) {
+        let (key, value) = item.unwrap();
+        let key = String::from_utf8_lossy(&key);
+        let key_parts: Vec<&str> = key.splitn(3, ':').collect();
+        if key_parts.len() != 3 {
+            continue;
+        }
+        if key_parts[0] > to {
+            break;
+        }
+        if let Some(rollup) = decode(&key, &value) {
+            entries.push(RollupEntry {
+                date: key_parts[0].to_string(),
+                succeeded: key_parts[1] == "ok",
+                denom: key_parts[2].to_string(),
+                rollup,
+            });
+        }
+    }
+    entries
+}
+
+/// The first day with a send as `YYYY-MM-DD`
+pub fn first_rollup_date(db: &DB) -> Option<String> {
+    let cf = cf(db, SEND_TO_ETH_ROLLUPS);
+    let (key, _) = db
+        .iterator_cf(cf, rocksdb::IteratorMode::Start)
+        .next()?
+        .unwrap();
+    let key = String::from_utf8_lossy(&key);
+    key.split(':').next().map(|date| date.to_string())
+}
+
+pub fn claim_key(event_nonce: u64, orchestrator: &str) -> String {
+    format!("{:020}:{}", event_nonce, orchestrator.to_lowercase())
+}
+
+/// The indexed claim with the highest event nonce of every orchestrator
+pub fn load_last_claims(db: &DB) -> Vec<CustomClaim> {
+    let cf = cf(db, ORCHESTRATOR_INDEX);
+    let mut claims = Vec::new();
+    let mut iter = db.raw_iterator_cf(cf);
+    iter.seek_to_first();
+    while let Some(entry) = iter.key().map(|k| String::from_utf8_lossy(k).to_string()) {
+        let orchestrator = match entry.split_once(':') {
+            Some((orchestrator, _)) => orchestrator.to_string(),
+            None => break,
+        };
+        // ';' sorts right after ':', so this is just past the orchestrator's last entry
+        let end = format!("{};", orchestrator);
+        iter.seek_for_prev(end.as_bytes());
+        let last = iter.key().map(|k| String::from_utf8_lossy(k).to_string());
+        if let Some(claim) = last
+            .as_ref()
+            .and_then(|k| k.split_once(':'))
+            .and_then(|(_, claim_key)| load_claim(db, claim_key))
+        {
+            claims.push(claim);
+        }
+        iter.seek(end.as_bytes());
+    }
+    claims
+}
+
+pub fn save_claim(db: &DB, data: &CustomClaim) {
+    let cf = cf(db, CLAIMS);
+    let key = claim_key(data.event_nonce, &data.orchestrator);
+    db.put_cf(cf, key.as_bytes(), data.to_bytes()).unwrap();
+    record_claimed_batch(db, data);
+    index_claimed_deposit(db, data);
+    put_index(
+        db,
+        ORCHESTRATOR_INDEX,
+        &data.orchestrator.to_lowercase(),
+        &key,
+    );
+}
+
+pub fn load_claim(db: &DB, key: &str) -> Option<CustomClaim> {
+    let cf = cf(db, CLAIMS);
+    let res = db.get_cf(cf, key.as_bytes()).unwrap();
+    res.and_then(|bytes| decode(key, &bytes))
+}
+
+/// Every orchestrator's claim for the event with `event_nonce`, in the order they were submitted
+pub fn load_event_claims(db: &DB, event_nonce: u64) -> Vec<CustomClaim> {
+    let cf = cf(db, CLAIMS);
+    let prefix = format!("{:020}:", event_nonce);
+    let mut claims: Vec<CustomClaim> = db
+        .prefix_iterator_cf(cf, prefix.as_bytes())
+        .map(|item| item.unwrap())
+        .take_while(|(key, _)| key.starts_with(prefix.as_bytes()))
+        .filter_map(|(key, value)| decode(&String::from_utf8_lossy(&key), &value))
+        .collect();
+    claims.sort_by_key(|c| c.block_number);
+    claims
+}
+
+pub fn save_confirm(db: &DB, data: &CustomConfirm) {
+    let cf = cf(db, CONFIRMS);
+    let orchestrator = data.orchestrator.to_lowercase();
+    let key = format!(
+        "{:012}:{}:{}:{}",
+        data.block_number,
+        data.details.msg_type(),
+        data.details.item_id(),
+        orchestrator
+    );
+    db.put_cf(cf, key.as_bytes(), data.to_bytes()).unwrap();
+    put_index(db, CONFIRM_ORCHESTRATOR_INDEX, &orchestrator, &key);
+}
+
+pub fn load_confirm(db: &DB, key: &str) -> Option<CustomConfirm> {
+    let cf = cf(db, CONFIRMS);
+    let res = db.get_cf(cf, key.as_bytes()).unwrap();
+    res.and_then(|bytes| decode(key, &bytes))
+}
+
+/// Every confirm submitted at or after Gravity block `from_height`, in block order, decoded as
+/// they are read
+pub fn load_confirms_since(db: &DB, from_height: u64) -> impl Iterator<Item = CustomConfirm> + '_ {
+    let cf = cf(db, CONFIRMS);
+    let start = format!("{:012}:", from_height);
+    db.iterator_cf(
+        cf,
+        rocksdb::IteratorMode::From(start.as_bytes(), rocksdb::Direction::Forward),
+    )
+    .filter_map(|item| {
+        let (key, value) = item.unwrap();
+        decode(&String::from_utf8_lossy(&key), &value)
+    })
+}
+
+/// A batch a send to eth was seen in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BatchedSend {
+    pub token_contract: String,
+    pub batch_nonce: u64,
+    /// Ethereum block height after which the batch can no longer be executed
+    pub batch_timeout: u64,
+    /// Unix time in seconds the batch was first seen
+    pub seen_at: i64,
+}
+
+fn batched_send_key(tx_id: u64, token_contract: &str, batch_nonce: u64) -> String {
+    format!(
+        "{:020}:{}:{:020}",
+        tx_id,
+        token_contract.to_lowercase(),
+        batch_nonce
+    )
+}
+
+/// Records that the send with `tx_id` is in a batch, keeping the time it was first seen
+pub fn save_batched_send(db: &DB, tx_id: u64, data: &BatchedSend) {
+    let cf = cf(db, BATCHED_SENDS);
+    let key = batched_send_key(tx_id, &data.token_contract, data.batch_nonce);
+    if db.get_cf(cf, key.as_bytes()).unwrap().is_none() {
+        db.put_cf(cf, key.as_bytes(), data.to_bytes()).unwrap();
+    }
+}
+
+/// Every batch the send with `tx_id` was seen in, oldest first
+pub fn load_send_batches(db: &DB, tx_id: u64) -> Vec<BatchedSend> {
+    let cf = cf(db, BATCHED_SENDS);
+    let prefix = format!("{:020}:", tx_id);
+    let mut batches: Vec<BatchedSend> = db
+        .prefix_iterator_cf(cf, prefix.as_bytes())
+        .map(|item| item.unwrap())
+        .take_while(|(key, _)| key.starts_with(prefix.as_bytes()))
+        .filter_map(|(key, value)| decode(&String::from_utf8_lossy(&key), &value))
+        .collect();
+    batches.sort_by_key(|b| b.batch_nonce);
+    batches
+}
+
+/// The execution of a batch on Ethereum
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExecutedBatch {
+    pub eth_block_height: u64,
+    pub event_nonce: u64,
+    /// Unix time in seconds the execution was first reported, the Gravity block time of the first
+    /// claim or the time the server read the Ethereum event, whichever came first
+    pub observed_at: i64,
+}
+
+fn executed_batch_key(token_contract: &str, batch_nonce: u64) -> String {
+    format!("{}:{:020}", token_contract.to_lowercase(), batch_nonce)
+}
+
+/// Records the execution of a batch unless it was already reported
+pub fn save_executed_batch(db: &DB, token_contract: &str, batch_nonce: u64, data: &ExecutedBatch) {
+    let cf = cf(db, EXECUTED_BATCHES);
+    let key = executed_batch_key(token_contract, batch_nonce);
+    if db.get_cf(cf, key.as_bytes()).unwrap().is_none() {
+        db.put_cf(cf, key.as_bytes(), data.to_bytes()).unwrap();
+    }
+}
+
+pub fn load_executed_batch(
+    db: &DB,
+    token_contract: &str,
+    batch_nonce: u64,
+) -> Option<ExecutedBatch> {
+    let cf = cf(db, EXECUTED_BATCHES);
+    let key = executed_batch_key(token_contract, batch_nonce);
+    let res = db.get_cf(cf, key.as_bytes()).unwrap();
+    res.and_then(|bytes| decode(&key, &bytes))
+}
+
+/// Orchestrators only claim batches they saw executed on Ethereum
+fn record_claimed_batch(db: &DB, claim: &CustomClaim) {
+    if let ClaimDetails::BatchSendToEth {
+        batch_nonce,
+        token_contract,
+    } = &claim.details
+    {
+        save_executed_batch(
+            db,
+            token_contract,
+            *batch_nonce,
+            &ExecutedBatch {
+                eth_block_height: claim.eth_block_height,
+                event_nonce: claim.event_nonce,
+                observed_at: claim.timestamp,
+            },
+        );
+    }
+}
+
+const WITHDRAWAL_TRACKING_START_KEY: &str = "withdrawal_tracking_start";
+
+pub fn save_withdrawal_tracking_start(db: &DB, timestamp: i64) {
+    put_meta(
+        db,
+        WITHDRAWAL_TRACKING_START_KEY,
+        timestamp.to_string().as_bytes(),
+    );
+}
+
+/// Unix time in seconds the batches were first recorded, the batches of earlier sends may have
+/// been missed
+pub fn load_withdrawal_tracking_start(db: &DB) -> Option<i64> {
+    get_meta_number(db, WITHDRAWAL_TRACKING_START_KEY)
+}
+
+/// A deposit to Gravity seen on Ethereum
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Deposit {
+    pub event_nonce: u64,
+    pub tx_hash: Option<String>,
+    pub erc20: String,
+    pub sender: String,
+    pub destination: String,
+    pub amount: String,
+    pub eth_block_height: u64,
+    /// Unix time in seconds the deposit was first seen on Ethereum
+    pub seen_at: i64,
+    /// Unix time in seconds the deposit was first seen past the finality delay
+    pub finalized_at: Option<i64>,
+    /// Unix time in seconds the deposit's attestation was first seen observed
+    pub observed_at: Option<i64>,
+    /// Unix time in seconds the deposit was first seen forwarded over IBC, only set for deposits
+    /// to another chain
+    pub forwarded_at: Option<i64>,
+}
+
+/// Key of a record of the Ethereum event with `event_nonce`, so records sort by nonce
+pub fn event_nonce_key(event_nonce: u64) -> String {
+    format!("{:020}", event_nonce)
+}
+
+/// Saves a deposit and indexes it, the caller keeps the times of stages already seen
+pub fn save_deposit(db: &DB, data: &Deposit) {
+    let cf = cf(db, DEPOSITS);
+    let key = event_nonce_key(data.event_nonce);
+    db.put_cf(cf, key.as_bytes(), data.to_bytes()).unwrap();
+    if let Some(tx_hash) = &data.tx_hash {
+        put_index(db, DEPOSIT_TX_HASH_INDEX, &tx_hash.to_lowercase(), &key);
+    }
+    put_index(db, DEPOSIT_SENDER_INDEX, &data.sender.to_lowercase(), &key);
+    put_index(
+        db,
+        DEPOSIT_DESTINATION_INDEX,
+        &data.destination.to_lowercase(),
+        &key,
+    );
+}
+
+/// Removes a deposit and its index entries, for a deposit a reorg rolled back before any
+/// orchestrator claimed it
+pub fn delete_deposit(db: &DB, data: &Deposit) {
+    let cf = cf(db, DEPOSITS);
+    let key = event_nonce_key(data.event_nonce);
+    db.delete_cf(cf, key.as_bytes()).unwrap();
+    if let Some(tx_hash) = &data.tx_hash {
+        delete_index(db, DEPOSIT_TX_HASH_INDEX, &tx_hash.to_lowercase(), &key);
+    }
+    delete_index(db, DEPOSIT_SENDER_INDEX, &data.sender.to_lowercase(), &key);
+    delete_index(
+        db,
+        DEPOSIT_DESTINATION_INDEX,
+        &data.destination.to_lowercase(),
+        &key,
+    );
+}
+
+pub fn load_deposit(db: &DB, event_nonce: u64) -> Option<Deposit> {
+    let cf = cf(db, DEPOSITS);
+    let key = event_nonce_key(event_nonce);
+    let res = db.get_cf(cf, key.as_bytes()).unwrap();
+    res.and_then(|bytes| decode(&key, &bytes))
+}
+
+/// Recorded deposits with a nonce from `from` to `to` inclusive, in nonce order
+pub fn load_deposits(db: &DB, from: u64, to: u64) -> Vec<Deposit> {
+    let cf = cf(db, DEPOSITS);
+    let start = event_nonce_key(from);
+    let end = event_nonce_key(to);
+    db.iterator_cf(
+        cf,
+        rocksdb::IteratorMode::From(start.as_bytes(), rocksdb::Direction::Forward),
+    )
+    .map(|item| item.unwrap())
+    .take_while(|(key, _)| &key[..] <= end.as_bytes())
+    .filter_map(|(key, value)| decode(&String::from_utf8_lossy(&key), &value))
+    .collect()
+}
+
+/// Deposit claims are indexed by sender and destination so deposits made before the server saw
+/// them on Ethereum can be looked up
+fn index_claimed_deposit(db: &DB, claim: &CustomClaim) {
+    if let ClaimDetails::SendToCosmos {
+        ethereum_sender,
+        cosmos_receiver,
+        ..
+    } = &claim.details
+    {
+        let key = event_nonce_key(claim.event_nonce);
+        put_index(
+            db,
+            DEPOSIT_SENDER_INDEX,
+            &ethereum_sender.to_lowercase(),
+            &key,
+        );
+        put_index(
+            db,
+            DEPOSIT_DESTINATION_INDEX,
+            &cosmos_receiver.to_lowercase(),
+            &key,
+        );
+    }
+}
+
+/// An event emitted by the Gravity contract on Ethereum, its details are those of the claims
+/// orchestrators submit for it
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct EthEvent {
+    pub event_nonce: u64,
+    pub block_height: u64,
+    /// Hash of the Ethereum transaction, None if the node did not report it
+    pub tx_hash: Option<String>,
+    /// Hash of the Ethereum block, None if the node did not report it
+    pub block_hash: Option<String>,
+    #[serde(flatten)]
+    pub details: ClaimDetails,
+}
+
+/// The column family holding events of the type of `details`
+pub fn eth_event_cf(details: &ClaimDetails) -> &'static str {
+    match details {
+        ClaimDetails::SendToCosmos { .. } => ETH_SEND_TO_COSMOS,
+        ClaimDetails::BatchSendToEth { .. } => ETH_BATCH_EXECUTED,
+        ClaimDetails::ValsetUpdated { .. } => ETH_VALSET_UPDATED,
+        ClaimDetails::Erc20Deployed { .. } => ETH_ERC20_DEPLOYED,
+        ClaimDetails::LogicCallExecuted { .. } => ETH_LOGIC_CALL_EXECUTED,
+    }
+}
+
+/// The column family holding events of the type named `claim_type`, as the type is named in
+/// the JSON of claims and events
+pub fn eth_event_cf_by_name(claim_type: &str) -> Option<&'static str> {
+    match claim_type {
+        "send_to_cosmos" => Some(ETH_SEND_TO_COSMOS),
+        "batch_send_to_eth" => Some(ETH_BATCH_EXECUTED),
+        "valset_updated" => Some(ETH_VALSET_UPDATED),
+        "erc20_deployed" => Some(ETH_ERC20_DEPLOYED),
+        "logic_call_executed" => Some(ETH_LOGIC_CALL_EXECUTED),
+        _ => None,
+    }
+}
+
+pub fn save_eth_event(db: &DB, data: &EthEvent) {
+    let cf = cf(db, eth_event_cf(&data.details));
+    db.put_cf(
+        cf,
+        event_nonce_key(data.event_nonce).as_bytes(),
+        data.to_bytes(),
+    )
+    .unwrap();
+}
+
+pub fn load_eth_event(db: &DB, name: &str, event_nonce: u64) -> Option<EthEvent> {
+    let handle = cf(db, name);
+    let key = event_nonce_key(event_nonce);
+    let res = db.get_cf(handle, key.as_bytes()).unwrap();
+    res.and_then(|bytes| decode(&key, &bytes))
+}
+
+/// Up to `limit` events from the column family `name` with a nonce from `from` to `to` inclusive, in nonce order or
+/// newest first if `reverse`
+pub fn load_eth_events(
+    db: &DB,
+    name: &str,
+    from: u64,
+    to: u64,
+    reverse: bool,
+    limit: usize,
+) -> Vec<EthEvent> {
+    let handle = cf(db, name);
+    let (start, end) = (event_nonce_key(from), event_nonce_key(to));
+    let mode = if reverse {
+        rocksdb::IteratorMode::From(end.as_bytes(), rocksdb::Direction::Reverse)
+    } else {
+        rocksdb::IteratorMode::From(start.as_bytes(), rocksdb::Direction::Forward)
+    };
+    db.iterator_cf(handle, mode)
+        .map(|item| item.unwrap())
+        .take_while(|(key, _)| &key[..] >= start.as_bytes() && &key[..] <= end.as_bytes())
+        .filter_map(|(key, value)| decode(&String::from_utf8_lossy(&key), &value))
+        .take(limit)
+        .collect()
+}
+
+/// The nonce of the latest stored event of a finalized block
+pub fn load_latest_eth_event_nonce(db: &DB) -> Option<u64> {
+    ETH_EVENT_COLUMN_FAMILIES
+        .iter()
+        .filter_map(|cf| load_eth_events(db, cf, 0, u64::MAX, true, 1).pop())
+        .map(|event| event.event_nonce)
+        .max()
+}
+
+/// Events from blocks that are not finalized yet, in nonce order
+pub fn load_unconfirmed_eth_events(db: &DB) -> Vec<EthEvent> {
+    let cf = cf(db, ETH_UNCONFIRMED_EVENTS);
+    db.iterator_cf(cf, rocksdb::IteratorMode::Start)
+        .map(|item| item.unwrap())
+        .filter_map(|(key, value)| decode(&String::from_utf8_lossy(&key), &value))
+        .collect()
+}
+
+/// Replaces the events from blocks that are not finalized yet with `events`
+pub fn save_unconfirmed_eth_events(db: &DB, events: &[EthEvent]) {
+    let cf = cf(db, ETH_UNCONFIRMED_EVENTS);
+    let mut batch = WriteBatch::default();
+    for item in db.iterator_cf(cf, rocksdb::IteratorMode::Start) {
+        let (key, _) = item.unwrap();
+        batch.delete_cf(cf, key);
+    }
+    for event in events {
+        batch.put_cf(
+            cf,
+            event_nonce_key(event.event_nonce).as_bytes(),
+            event.to_bytes(),
+        );
+    }
+    db.write(batch).unwrap();
+}
+
+const ETH_EVENTS_SYNCED_KEY: &str = "eth_events_synced_to";
+
+pub fn save_eth_events_synced_to(db: &DB, block: u64) {
+    put_meta(db, ETH_EVENTS_SYNCED_KEY, block.to_string().as_bytes());
+}
+
+/// The last Ethereum block whose events are all stored
+pub fn load_eth_events_synced_to(db: &DB) -> Option<u64> {
+    get_meta_u64(db, ETH_EVENTS_SYNCED_KEY)
+}
+
+const ETH_REORGS_KEY: &str = "eth_reorgs";
+const ETH_LAST_REORG_KEY: &str = "eth_last_reorg";
+
+/// Counts a reorg that rolled back stored unconfirmed events at unix time `timestamp`
+pub fn save_eth_reorg(db: &DB, timestamp: i64) {
+    let reorgs = get_meta_u64(db, ETH_REORGS_KEY).unwrap_or(0) + 1;
+    put_meta(db, ETH_REORGS_KEY, reorgs.to_string().as_bytes());
+    put_meta(db, ETH_LAST_REORG_KEY, timestamp.to_string().as_bytes());
+}
+
+/// The number of reorgs seen and the unix time of the last one, None if there was none
+pub fn load_eth_reorgs(db: &DB) -> (u64, Option<i64>) {
+    let last =
+        get_meta(db, ETH_LAST_REORG_KEY).and_then(|v| String::from_utf8_lossy(&v).parse().ok());
+    (get_meta_u64(db, ETH_REORGS_KEY).unwrap_or(0), last)
+}
+
+const LAST_OBSERVED_NONCE_KEY: &str = "last_observed_event_nonce";
+const LAST_OBSERVED_NONCE_UPDATED_KEY: &str = "last_observed_event_nonce_updated";
+
+/// Records the nonce of the latest observed attestation if it is newer than the one recorded
+pub fn save_last_observed_nonce(db: &DB, event_nonce: u64, timestamp: i64) {
+    if load_last_observed_nonce(db).is_some_and(|(nonce, _)| nonce >= event_nonce) {
+        return;
+    }
+    put_meta(
+        db,
+        LAST_OBSERVED_NONCE_KEY,
+        event_nonce.to_string().as_bytes(),
+    );
+    put_meta(
+        db,
+        LAST_OBSERVED_NONCE_UPDATED_KEY,
+        timestamp.to_string().as_bytes(),
+    );
+}
+
+/// Records the latest observed attestation in the Gravity info, if any is observed
+pub fn record_last_observed_nonce(db: &DB, info: &GravityInfo, now: i64) {
+    let observed = info
+        .attestations
+        .iter()
+        .filter(|a| a.observed)
+        .filter_map(|a| a.event_nonce)
+        .max();
+    if let Some(nonce) = observed {
+        save_last_observed_nonce(db, nonce, now);
+    }
+}
+
+/// The nonce of the latest observed attestation and the unix time it was first seen observed,
+/// Gravity observes events in nonce order so every event up to it is observed
+pub fn load_last_observed_nonce(db: &DB) -> Option<(u64, i64)> {
+    let nonce = get_meta_u64(db, LAST_OBSERVED_NONCE_KEY)?;
+    get_meta_number(db, LAST_OBSERVED_NONCE_UPDATED_KEY).map(|updated| (nonce, updated))
+}
+
+const VALSET_KEY: &str = "last_valset";
+const VALSET_UPDATED_KEY: &str = "last_valset_updated";
+pub fn save_last_valset(db: &DB, data: &Valset) {
+    let data_json = serde_json::to_string(data).unwrap();
+    put_meta(db, VALSET_KEY, data_json.as_bytes());
+    put_meta(db, VALSET_UPDATED_KEY, unix_now().to_string().as_bytes());
+    record_update(Store::LastValset, None, None, None);
+}
+
+/// Unix time the last valset was saved at
+pub fn load_last_valset_updated(db: &DB) -> Option<u64> {
+    get_meta_u64(db, VALSET_UPDATED_KEY)
+}
+
+pub fn load_last_valset(db: &DB) -> Option<Valset> {
+    get_meta(db, VALSET_KEY).and_then(|bytes| decode_json(VALSET_KEY, &bytes))
+}
+
+// timestamp function using downloaded blocks as a source of truth
+const LAST_DOWNLOAD_BLOCK_KEY: &str = "last_download_block";
+const LAST_DOWNLOAD_BLOCK_UPDATED_KEY: &str = "last_download_block_updated";
+
+fn save_last_download_block(db: &DB, timestamp: u64) {
+    put_meta(
+        db,
+        LAST_DOWNLOAD_BLOCK_KEY,
+        timestamp.to_string().as_bytes(),
+    );
+    put_meta(
+        db,
+        LAST_DOWNLOAD_BLOCK_UPDATED_KEY,
+        unix_now().to_string().as_bytes(),
+    );
+    set_indexer_height(timestamp);
+    record_update(Store::IndexerTip, Some(timestamp), None, None);
+}
+
+pub fn load_last_download_block(db: &DB) -> Option<u64> {
+    get_meta_u64(db, LAST_DOWNLOAD_BLOCK_KEY)
+}
+
+const INDEXER_START_KEY: &str = "indexer_start_block";
+
+fn save_indexer_start(db: &DB, block: u64) {
+    put_meta(db, INDEXER_START_KEY, block.to_string().as_bytes());
+}
+
+/// The first block the indexer downloaded
+pub fn load_indexer_start(db: &DB) -> Option<u64> {
+    get_meta_u64(db, INDEXER_START_KEY)
+}
+
+/// Checks an entry of the meta column family
+pub fn check_meta_entry(key: &str, value: &[u8]) -> Result<(), String> {
+    match key {
+        VALSET_KEY => serde_json::from_slice::<Valset>(value)
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        SCHEMA_VERSION_KEY
+        | VALSET_UPDATED_KEY
+        | LAST_DOWNLOAD_BLOCK_KEY
+        | LAST_DOWNLOAD_BLOCK_UPDATED_KEY
+        | INDEXER_START_KEY
+        | LAST_OBSERVED_NONCE_KEY
+        | ETH_EVENTS_SYNCED_KEY
+        | ETH_REORGS_KEY => String::from_utf8_lossy(value)
+            .parse::<u64>()
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        WITHDRAWAL_TRACKING_START_KEY | LAST_OBSERVED_NONCE_UPDATED_KEY | ETH_LAST_REORG_KEY => {
+            String::from_utf8_lossy(value)
+                .parse::<i64>()
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }
+        _ => Err("unknown key".to_string()),
+    }
+}
+
+/// A block range the indexer failed to download
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Gap {
+    pub start: u64,
+    pub end: u64,
+    /// Number of runs that failed to index it
+    pub attempts: u64,
+    pub last_error: String,
+    /// Unix time of the last failure
+    pub updated_at: u64,
+}
+
+/// Splits `first..=latest` into ranges of at most `batch_size` blocks, leaving out the ranges
+/// already indexed
+pub fn pending_ranges(db: &DB, first: u64, latest: u64, batch_size: u64) -> Vec<(u64, u64)> {
+    let cf = cf(db, INDEXED_RANGES);
+    let mut done = Vec::new();
+    for item in db.iterator_cf(cf, rocksdb::IteratorMode::Start) {
+        let (key, value) = item.unwrap();
+        let key = String::from_utf8_lossy(&key);
+        let (start, end) = match (
+            key.parse::<u64>(),
+            String::from_utf8_lossy(&value).parse::<u64>(),
+        ) {
+            (Ok(start), Ok(end)) => (start, end),
+            _ => {
+                error!("Skipped malformed indexed range {}", key);
+                continue;
+            }
+        };
+        if end >= first {
+            done.push((start.max(first), end));
+        }
+    }
+
+    let mut ranges = Vec::new();
+    let mut pos = first;
+    let mut done = done.into_iter().peekable();
+    while pos <= latest {
+        if let Some((done_start, done_end)) = done.peek().copied() {
+            if done_start <= pos {
+                pos = pos.max(done_end + 1);
+                done.next();
+                continue;
+            }
+        }
+        let mut end = latest.min(pos + batch_size - 1);
+        if let Some((done_start, _)) = done.peek() {
+            end = end.min(done_start - 1);
+        }
+        ranges.push((pos, end));
+        pos = end + 1;
+    }
+    ranges
+}
+
+/// Records that every block in `start..=end` is indexed, clearing any gap it covered, and moves
+/// the tip forward over the ranges that now follow it without a hole
+pub fn mark_range_indexed(db: &DB, start: u64, end: u64) {
+    let ranges = cf(db, INDEXED_RANGES);
+    db.put_cf(
+        ranges,
+        format!("{:012}", start).as_bytes(),
+        end.to_string().as_bytes(),
+    )
+    .unwrap();
+    let gaps = cf(db, INDEXER_GAPS);
+    for gap in load_gaps(db) {
+        if gap.start >= start && gap.start <= end {
+            db.delete_cf(gaps, format!("{:012}", gap.start).as_bytes())
+                .unwrap();
+            // a retry that failed part way leaves the rest of the gap missing
+            if gap.end > end {
+                save_gap(
+                    db,
+                    &Gap {
+                        start: end + 1,
+                        ..gap
+                    },
+                );
+            }
+        }
+    }
+    advance_tip(db);
+}
+
+/// The tip only ever moves over indexed ranges, so it never passes a gap
+fn advance_tip(db: &DB) {
+    let ranges = cf(db, INDEXED_RANGES);
+    let mut next = match (load_last_download_block(db), load_indexer_start(db)) {
+        (Some(tip), _) => tip + 1,
+        (None, Some(start)) => start,
+        (None, None) => return,
+    };
+    let mut advanced = false;
+    while let Some(end) = db
+        .get_cf(ranges, format!("{:012}", next).as_bytes())
+        .unwrap()
+    {
+        let end = match String::from_utf8_lossy(&end).parse::<u64>() {
+            Ok(end) => end,
+            Err(_) => {
+                error!(
+                    "Malformed indexed range {:012}, not moving the tip over it",
+                    next
+                );
+                break;
+            }
+        };
+        db.delete_cf(ranges, format!("{:012}", next).as_bytes())
+            .unwrap();
+        next = end + 1;
+        advanced = true;
+    }
+    if advanced {
+        save_last_download_block(db, next - 1);
+    }
+}
+
+fn save_gap(db: &DB, gap: &Gap) {
+    let cf = cf(db, INDEXER_GAPS);
+    let data_json = serde_json::to_string(gap).unwrap();
+    db.put_cf(
+        cf,
+        format!("{:012}", gap.start).as_bytes(),
+        data_json.as_bytes(),
+    )
+    .unwrap();
+}
+
+/// Records that `start..=end` failed to download, counting the attempt if it had already failed
+pub fn record_gap(db: &DB, start: u64, end: u64, error: String) {
+    let attempts = load_gaps(db)
+        .iter()
+        .find(|g| g.start == start)
+        .map(|g| g.attempts)
+        .unwrap_or_default();
+    save_gap(
+        db,
+        &Gap {
+            start,
+            end,
+            attempts: attempts + 1,
+            last_error: error,
+            updated_at: unix_now(),
+        },
+    );
+}
+
+/// Block ranges not yet indexed, by start block
+pub fn load_gaps(db: &DB) -> Vec<Gap> {
+    let cf = cf(db, INDEXER_GAPS);
+    db.iterator_cf(cf, rocksdb::IteratorMode::Start)
+        .filter_map(|item| {
+            let (key, value) = item.unwrap();
+            decode_json(&String::from_utf8_lossy(&key), &value)
+        })
+        .collect()
+}
+
+/// The last downloaded block and the unix time it was saved at
+pub fn load_last_download_block_updated(db: &DB) -> Option<(u64, u64)> {
+    let block = load_last_download_block(db)?;
+    get_meta_u64(db, LAST_DOWNLOAD_BLOCK_UPDATED_KEY).map(|updated| (block, updated))
+}
+
+// Schema versions, bump SCHEMA_VERSION and add a migration for any change to the layout of the
+// database or the stored format of a value. Databases created before schema versions keep
+// everything in the default column family and are version 0.
+pub const SCHEMA_VERSION_KEY: &str = "schema_version";
+pub const SCHEMA_VERSION: u64 = 7;
+
+/// Upgrades a database from `version - 1` to `version`, it must be safe to run again if it was
+/// interrupted
+struct Migration {
+    version: u64,
+    description: &'static str,
+    run: fn(&DB),
+}
+
+const MIGRATIONS: [Migration; 7] = [
+    Migration {
+        version: 1,
+        description: "index messages downloaded before the secondary indexes existed",
+        run: build_secondary_indexes,
+    },
+    Migration {
+        version: 2,
+        description:
+            "move messages to a column family per type and state to the meta column family",
+        run: split_default_column_family,
+    },
+    Migration {
+        version: 3,
+        description: "encode messages, tx statuses, claims and confirms as protobuf",
+        run: encode_records,
+    },
+    Migration {
+        version: 4,
+        description: "add up the sends to eth of every day",
+        run: build_send_to_eth_rollups,
+    },
+    Migration {
+        version: 5,
+        description: "record the batches claimed as executed on Ethereum",
+        run: record_executed_batches,
+    },
+    Migration {
+        version: 6,
+        description: "index the deposits of stored claims by sender and destination",
+        run: index_claimed_deposits,
+    },
+    Migration {
+        version: 7,
+        description: "add the position of each message in its tx to the message keys",
+        run: add_msg_index_to_keys,
+    },
+];
+
+pub fn load_schema_version(db: &DB) -> u64 {
+    match get_meta_u64(db, SCHEMA_VERSION_KEY) {
+        Some(version) => version,
+        // only databases from before schema versions have anything in the default column family
+        None if db.iterator(rocksdb::IteratorMode::Start).next().is_some() => 0,
+        None => SCHEMA_VERSION,
+    }
+}
+
+/// Runs every migration newer than the database's schema version in order, the version is saved
+/// after each one so an interrupted upgrade resumes where it stopped
+pub fn migrate(db: &DB) -> Result<(), String> {
+    let version = load_schema_version(db);
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "Database schema version {} is newer than the supported version {}",
+            version, SCHEMA_VERSION
+        ));
+    }
+    for migration in MIGRATIONS.iter().filter(|m| m.version > version) {
+        info!(
+            "Migrating database to schema version {}: {}",
+            migration.version, migration.description
+        );
+        (migration.run)(db);
+        put_meta(
+            db,
+            SCHEMA_VERSION_KEY,
+            migration.version.to_string().as_bytes(),
+        );
+    }
+    put_meta(
+        db,
+        SCHEMA_VERSION_KEY,
+        SCHEMA_VERSION.to_string().as_bytes(),
+    );
+    Ok(())
+}
+
+/// Moves every message from the default column family to the column family of its type and
+/// the indexer's progress and the last valset to the meta column family
+fn split_default_column_family(db: &DB) {
+    const META_KEYS: [&str; 5] = [
+        VALSET_KEY,
+        VALSET_UPDATED_KEY,
+        LAST_DOWNLOAD_BLOCK_KEY,
+        LAST_DOWNLOAD_BLOCK_UPDATED_KEY,
+        INDEXER_START_KEY,
+    ];
+    let mut moved = 0;
+    for item in db.iterator(rocksdb::IteratorMode::Start) {
+        let (key, value) = item.unwrap();
+        let key_str = String::from_utf8_lossy(&key);
+        let key_parts: Vec<&str> = key_str.split(':').collect();
+        if key_parts.len() == 4 && msg_cf(key_parts[1]).is_some() {
+            put_msg(db, &key_str, &value);
+        } else if META_KEYS.contains(&key_str.as_ref()) {
+            put_meta(db, &key_str, &value);
+        } else if key_str == SECONDARY_INDEXES_BUILT_KEY {
+            // superseded by the schema version
+        } else {
+            error!("Unknown key {} left in the default column family", key_str);
+            continue;
+        }
+        db.delete(&key).unwrap();
+        moved += 1;
+    }
+    info!("Moved {} entries out of the default column family", moved);
+}
+
+/// Re-encodes the JSON values of every message, tx status, claim and confirm as protobuf
+fn encode_records(db: &DB) {
+    encode_column_family::<CustomMsgSendToEth>(db, MSG_SEND_TO_ETH);
+    encode_column_family::<CustomMsgCancelSendToEth>(db, MSG_CANCEL_SEND_TO_ETH);
+    encode_column_family::<CustomMsgTransfer>(db, MSG_IBC_TRANSFER);
+    encode_column_family::<TxStatus>(db, TX_STATUS);
+    encode_column_family::<CustomClaim>(db, CLAIMS);
+    encode_column_family::<CustomConfirm>(db, CONFIRMS);
+}
+
+fn encode_column_family<T: DeserializeOwned + Record>(db: &DB, name: &str) {
+    let handle = cf(db, name);
+    let mut encoded = 0;
+    for item in db.iterator_cf(handle, rocksdb::IteratorMode::Start) {
+        let (key, value) = item.unwrap();
+        // values that aren't JSON were already encoded before the migration was interrupted
+        if let Ok(record) = serde_json::from_slice::<T>(&value) {
+            db.put_cf(handle, &key, record.to_bytes()).unwrap();
+            encoded += 1;
+        }
+    }
+    info!("Encoded {} entries of {}", encoded, name);
+}
+
+/// Rebuilds the send to eth rollups from every stored send
+fn build_send_to_eth_rollups(db: &DB) {
+    let rollups = cf(db, SEND_TO_ETH_ROLLUPS);
+    // an interrupted run starts over rather than counting sends twice
+    for item in db.iterator_cf(rollups, rocksdb::IteratorMode::Start) {
+        db.delete_cf(rollups, item.unwrap().0).unwrap();
+    }
+    let sends = cf(db, MSG_SEND_TO_ETH);
+    let mut added = 0;
+    for item in db.iterator_cf(sends, rocksdb::IteratorMode::Start) {
+        let (key, value) = item.unwrap();
+        let key = String::from_utf8_lossy(&key);
+        if let Some(send) = decode::<CustomMsgSendToEth>(&key, &value) {
+            update_rollups(db, &key, None, Some(&send));
+            added += 1;
+        }
+    }
+    info!("Added {} sends to the rollups", added);
+}
+
+/// Records the executed batches from every stored batch claim
+fn record_executed_batches(db: &DB) {
+    let claims = cf(db, CLAIMS);
+    let mut recorded = 0;
+    for item in db.iterator_cf(claims, rocksdb::IteratorMode::Start) {
+        let (key, value) = item.unwrap();
+        if let Some(claim) = decode::<CustomClaim>(&String::from_utf8_lossy(&key), &value) {
+            if matches!(claim.details, ClaimDetails::BatchSendToEth { .. }) {
+                record_claimed_batch(db, &claim);
+                recorded += 1;
+            }
+        }
+    }
+    info!("Recorded {} batch claims", recorded);
+}
+
+/// Indexes the sender and destination of every stored deposit claim
+fn index_claimed_deposits(db: &DB) {
+    let claims = cf(db, CLAIMS);
+    let mut indexed = 0;
+    for item in db.iterator_cf(claims, rocksdb::IteratorMode::Start) {
+        let (key, value) = item.unwrap();
+        if let Some(claim) = decode::<CustomClaim>(&String::from_utf8_lossy(&key), &value) {
+            if matches!(claim.details, ClaimDetails::SendToCosmos { .. }) {
+                index_claimed_deposit(db, &claim);
+                indexed += 1;
+            }
+        }
+    }
+    info!("Indexed {} deposit claims", indexed);
+}
+
+/// Adds the message index to the keys of every stored message and rebuilds the secondary indexes
+/// for the new keys. Only the last message of each type in a tx was kept under the old keys and
+/// its position is unknown, it is stored as the first.
+fn add_msg_index_to_keys(db: &DB) {
+    // an interrupted run starts over rather than leaving entries for the old keys behind
+    for index in MSG_INDEXES {
+        let cf = cf(db, index);
+        for item in db.iterator_cf(cf, rocksdb::IteratorMode::Start) {
+            db.delete_cf(cf, item.unwrap().0).unwrap();
+        }
+    }
+    add_msg_index::<CustomMsgSendToEth>(db, MSG_SEND_TO_ETH, index_msg_send_to_eth);
+    add_msg_index::<CustomMsgCancelSendToEth>(
+        db,
+        MSG_CANCEL_SEND_TO_ETH,
+        index_msg_cancel_send_to_eth,
+    );
+    add_msg_index::<CustomMsgTransfer>(db, MSG_IBC_TRANSFER, index_msg_ibc_transfer);
+}
+
+fn add_msg_index<T: Record>(db: &DB, name: &str, index: fn(&DB, &str, &T)) {
+    let handle = cf(db, name);
+    let mut moved = 0;
+    for item in db.iterator_cf(handle, rocksdb::IteratorMode::Start) {
+        let (key, value) = item.unwrap();
+        let key_str = String::from_utf8_lossy(&key).to_string();
+        let key_parts: Vec<&str> = key_str.split(':').collect();
+        let new_key = match key_parts.len() {
+            4 => format!(
+                "{}:{}:{}:0:{}",
+                key_parts[0], key_parts[1], key_parts[2], key_parts[3]
+            ),
+            MSG_KEY_PARTS => key_str.clone(),
+            _ => {
+                error!("Skipped message with malformed key {}", key_str);
+                continue;
+            }
+        };
+        if new_key != key_str {
+            db.put_cf(handle, new_key.as_bytes(), &value).unwrap();
+            db.delete_cf(handle, &key).unwrap();
+            moved += 1;
+        }
+        // values that don't decode are moved but left unindexed
+        if let Some(msg) = decode::<T>(&new_key, &value) {
+            index(db, &new_key, &msg);
+        }
+    }
+    info!("Added the message index to {} keys of {}", moved, name);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::upstream::fake::{
+        any, coin, encode_tx, tx_hash, FakeGravity, TestDb, CLAIM_EVENT_NONCE,
+        GRAVITY_CANCEL_BLOCK, GRAVITY_CLAIM_BLOCK, GRAVITY_CONFIRM_BLOCK, GRAVITY_FAILED_TX_BLOCK,
+        GRAVITY_LATEST_BLOCK, GRAVITY_TX_BLOCK, ORCHESTRATOR_ADDRESS, SEND_TO_ETH_TX_ID,
+    };
+    use crate::upstream::RawEvent;
+    use prost::Message;
+    use web30::amm::USDC_CONTRACT_ADDRESS;
+
+    #[actix_web::test]
+    async fn test_transactions_offline() {
+        let db = TestDb::open("transactions_offline");
+        let gravity = FakeGravity::fixture();
+
+        transactions(&db, &gravity).await.unwrap();
+        assert_eq!(load_last_download_block(&db), Some(GRAVITY_LATEST_BLOCK));
+
+        let prefix = format!("{:012}:", GRAVITY_TX_BLOCK);
+        let keys: Vec<String> = MSG_COLUMN_FAMILIES
+            .iter()
+            .flat_map(|cf| {
+                db.prefix_iterator_cf(db.cf_handle(cf).unwrap(), prefix.as_bytes())
+                    .map(|r| String::from_utf8(r.unwrap().0.to_vec()).unwrap())
+                    .take_while(|k| k.starts_with(&prefix))
+                    .collect::<Vec<String>>()
+            })
+            .collect();
+        assert_eq!(keys.len(), 2);
+
+        let send_key = keys.iter().find(|k| k.contains(":msgSendToEth:")).unwrap();
+        let send = load_msg_send_to_eth(&db, send_key).unwrap();
+        assert_eq!(send.amount[0].amount, "89200000");
+        assert_eq!(send.tx_id, Some(SEND_TO_ETH_TX_ID));
+
+        // the cancellation is linked back to the send it cancelled
+        let cancel_key =
+            find_by_outgoing_tx_id(&db, SEND_TO_ETH_TX_ID, "msgCancelSendToEth").unwrap();
+        assert!(cancel_key.starts_with(&format!("{:012}:", GRAVITY_CANCEL_BLOCK)));
+        let cancel = load_msg_cancel_send_to_eth(&db, &cancel_key).unwrap();
+        assert_eq!(cancel.transaction_id, SEND_TO_ETH_TX_ID);
+        assert_eq!(send.cancelled_by.as_deref(), cancel_key.rsplit(':').next());
+        assert!(load_tx_status(&db, send_key.rsplit(':').next().unwrap())
+            .unwrap()
+            .succeeded());
+
+        // failed txs are saved along with why they failed
+        let prefix = format!("{:012}:msgSendToEth:", GRAVITY_FAILED_TX_BLOCK);
+        let failed_key = String::from_utf8(
+            db.prefix_iterator_cf(db.cf_handle(MSG_SEND_TO_ETH).unwrap(), prefix.as_bytes())
+                .next()
+                .unwrap()
+                .unwrap()
+                .0
+                .to_vec(),
+        )
+        .unwrap();
+        let failed_hash = failed_key.rsplit(':').next().unwrap();
+        let status = load_tx_status(&db, failed_hash).unwrap();
+        assert_eq!(status.code, 5);
+        assert!(status.log.contains("insufficient funds"));
+        assert_eq!(status.fee[0].amount, "5000");
+        assert!(!tx_succeeded(&db, failed_hash));
+        let ibc_key = keys
+            .iter()
+            .find(|k| k.contains(":msgIbcTransfer:"))
+            .unwrap();
+        let ibc = load_msg_ibc_transfer(&db, ibc_key).unwrap();
+        assert_eq!(ibc.source_channel, "channel-24");
+
+        // both claims of the orchestrator's tx are kept, one per event
+        let claims = load_event_claims(&db, CLAIM_EVENT_NONCE);
+        assert_eq!(claims.len(), 1);
+        assert_eq!(claims[0].orchestrator, ORCHESTRATOR_ADDRESS);
+        assert_eq!(claims[0].block_number, GRAVITY_CLAIM_BLOCK);
+        assert!(matches!(
+            claims[0].details,
+            ClaimDetails::SendToCosmos { .. }
+        ));
+        let batch_claim =
+            load_claim(&db, &claim_key(CLAIM_EVENT_NONCE + 1, ORCHESTRATOR_ADDRESS)).unwrap();
+        assert!(matches!(
+            batch_claim.details,
+            ClaimDetails::BatchSendToEth {
+                batch_nonce: 1_200,
+                ..
+            }
+        ));
+        assert_eq!(batch_claim.tx_hash, claims[0].tx_hash);
+        // the claim records the batch as executed
+        let executed = load_executed_batch(&db, &USDC_CONTRACT_ADDRESS.to_string(), 1_200).unwrap();
+        assert_eq!(executed.event_nonce, CLAIM_EVENT_NONCE + 1);
+        assert_eq!(executed.observed_at, batch_claim.timestamp);
+        // and the deposit claim makes the deposit findable by its sender
+        let prefix = "0xf0f08f640d5553e79b91296dba6c3f10521e5174:";
+        let entry = db
+            .prefix_iterator_cf(
+                db.cf_handle(DEPOSIT_SENDER_INDEX).unwrap(),
+                prefix.as_bytes(),
+            )
+            .next()
+            .unwrap()
+            .unwrap()
+            .0;
+        assert_eq!(
+            String::from_utf8_lossy(&entry),
+            format!("{}{}", prefix, event_nonce_key(CLAIM_EVENT_NONCE))
+        );
+
+        let confirms: Vec<_> = load_confirms_since(&db, GRAVITY_CONFIRM_BLOCK).collect();
+        assert_eq!(confirms.len(), 2);
+        assert!(confirms
+            .iter()
+            .all(|c| c.orchestrator == ORCHESTRATOR_ADDRESS
+                && c.block_number == GRAVITY_CONFIRM_BLOCK));
+        assert!(confirms
+            .iter()
+            .any(|c| c.details == ConfirmDetails::Valset { nonce: 50 }));
+    }
+
+    #[actix_web::test]
+    async fn test_follow_tip() {
+        let db = TestDb::open("follow_tip");
+        let mut gravity = FakeGravity::fixture();
+        gravity.blocks.retain(|b| b.height < GRAVITY_CONFIRM_BLOCK);
+
+        transactions(&db, &gravity).await.unwrap();
+        assert_eq!(
+            load_last_download_block(&db),
+            Some(GRAVITY_CONFIRM_BLOCK - 1)
+        );
+        assert!(load_confirms_since(&db, GRAVITY_CONFIRM_BLOCK)
+            .next()
+            .is_none());
+
+        // the chain moved on, only the new blocks are downloaded
+        let gravity = FakeGravity::fixture();
+        let indexed = follow_tip(&db, &gravity).await.unwrap();
+        assert_eq!(indexed, GRAVITY_LATEST_BLOCK - GRAVITY_CONFIRM_BLOCK + 1);
+        assert_eq!(load_last_download_block(&db), Some(GRAVITY_LATEST_BLOCK));
+        assert_eq!(load_confirms_since(&db, GRAVITY_CONFIRM_BLOCK).count(), 2);
+        assert_eq!(follow_tip(&db, &gravity).await.unwrap(), 0);
+    }
+
+    #[actix_web::test]
+    async fn test_follow_tip_with_gap() {
+        let db = TestDb::open("follow_tip_with_gap");
+        let mut gravity = FakeGravity::fixture();
+        let tx = gravity
+            .blocks
+            .iter()
+            .find(|b| b.height == GRAVITY_TX_BLOCK)
+            .unwrap()
+            .txs[0]
+            .clone();
+        gravity.unavailable_tx_results = vec![tx_hash(&tx)];
+        let full = gravity.clone();
+        gravity.blocks.retain(|b| b.height < GRAVITY_CONFIRM_BLOCK);
+
+        // the block whose tx result failed is left as a gap instead of indexed as successful
+        assert!(transactions(&db, &gravity).await.is_err());
+        assert_eq!(load_last_download_block(&db), Some(GRAVITY_TX_BLOCK - 1));
+        assert!(load_tx_status(&db, &tx_hash(&tx)).is_none());
+        assert_eq!(load_gaps(&db)[0].start, GRAVITY_TX_BLOCK);
+
+        // the new blocks are indexed while the gap keeps failing
+        let indexed = follow_tip(&db, &full).await.unwrap();
+        assert_eq!(indexed, GRAVITY_LATEST_BLOCK - GRAVITY_CONFIRM_BLOCK + 1);
+        assert_eq!(load_confirms_since(&db, GRAVITY_CONFIRM_BLOCK).count(), 2);
+        assert_eq!(load_last_download_block(&db), Some(GRAVITY_TX_BLOCK - 1));
+        assert_eq!(load_gaps(&db)[0].attempts, 2);
+
+        // once the node returns the result the gap is filled
+        let gravity = FakeGravity::fixture();
+        let indexed = follow_tip(&db, &gravity).await.unwrap();
+        assert_eq!(indexed, GRAVITY_CONFIRM_BLOCK - GRAVITY_TX_BLOCK);
+        assert!(load_gaps(&db).is_empty());
+        assert!(load_tx_status(&db, &tx_hash(&tx)).is_some());
+        assert_eq!(load_last_download_block(&db), Some(GRAVITY_LATEST_BLOCK));
+    }
+
+    #[actix_web::test]
+    async fn test_several_sends_in_one_tx() {
+        let db = TestDb::open("several_sends_in_one_tx");
+        let mut gravity = FakeGravity::fixture();
+        let send = |amount: u128| MsgSendToEth {
+            sender: "gravity1alice".to_string(),
+            eth_dest: "0xb0b".to_string(),
+            amount: Some(coin("ugraviton", amount).into()),
+            bridge_fee: Some(coin("ugraviton", 10).into()),
+            chain_fee: None,
+        };
+        let outgoing_tx_id = |msg_index: u32, tx_id: u64| RawEvent {
+            msg_index,
+            kind: "gravity.v1.EventOutgoingTxId".to_string(),
+            attributes: vec![("tx_id".to_string(), format!("\"{}\"", tx_id))],
+        };
+        let tx = encode_tx(vec![
+            any("/gravity.v1.MsgSendToEth", send(100).encode_to_vec()),
+            any("/gravity.v1.MsgSendToEth", send(200).encode_to_vec()),
+        ]);
+        gravity.tx_results.insert(
+            tx_hash(&tx),
+            RawTxResult {
+                events: vec![outgoing_tx_id(0, 2_001), outgoing_tx_id(1, 2_002)],
+                ..Default::default()
+            },
+        );
+        let block = gravity
+            .blocks
+            .iter_mut()
+            .find(|b| b.height == GRAVITY_LATEST_BLOCK)
+            .unwrap();
+        let date = rollup_date(block.time);
+        block.txs.push(tx);
+
+        // each send is kept and tracked by its own outgoing tx id
+        transactions(&db, &gravity).await.unwrap();
+        let first = find_by_outgoing_tx_id(&db, 2_001, "msgSendToEth").unwrap();
+        let second = find_by_outgoing_tx_id(&db, 2_002, "msgSendToEth").unwrap();
+        assert_ne!(first, second);
+        assert_eq!(
+            load_msg_send_to_eth(&db, &first).unwrap().amount[0].amount,
+            "100"
+        );
+        assert_eq!(
+            load_msg_send_to_eth(&db, &second).unwrap().amount[0].amount,
+            "200"
+        );
+        let rollup = load_rollups(&db, Some(date.as_str()), &date)
+            .into_iter()
+            .find(|r| r.succeeded && r.denom == "ugraviton")
+            .unwrap();
+        assert_eq!(rollup.rollup.count, 2);
+        assert_eq!(rollup.rollup.amount, 300);
+    }
+
+    #[actix_web::test]
+    async fn test_missing_block_is_a_gap() {
+        let db = TestDb::open("missing_block_is_a_gap");
+        let mut gravity = FakeGravity::fixture();
+        gravity.blocks.retain(|b| b.height != GRAVITY_CANCEL_BLOCK);
+
+        // the blocks before the missing one are indexed, the rest of the range is not
+        assert!(transactions(&db, &gravity).await.is_err());
+        assert_eq!(
+            load_last_download_block(&db),
+            Some(GRAVITY_CANCEL_BLOCK - 1)
+        );
+        assert!(find_by_outgoing_tx_id(&db, SEND_TO_ETH_TX_ID, "msgSendToEth").is_some());
+        let gaps = load_gaps(&db);
+        assert_eq!(gaps.len(), 1);
+        assert_eq!(gaps[0].start, GRAVITY_CANCEL_BLOCK);
+        assert_eq!(gaps[0].end, GRAVITY_LATEST_BLOCK);
+
+        let gravity = FakeGravity::fixture();
+        follow_tip(&db, &gravity).await.unwrap();
+        assert!(load_gaps(&db).is_empty());
+        assert_eq!(load_last_download_block(&db), Some(GRAVITY_LATEST_BLOCK));
+    }
+
+    #[test]
+    fn test_range_checkpoints() {
+        let db = TestDb::open("range_checkpoints");
+        save_indexer_start(&db, 100);
+        assert_eq!(
+            pending_ranges(&db, 100, 1_000, 300),
+            vec![(100, 399), (400, 699), (700, 999), (1_000, 1_000)]
+        );
+
+        // a later range finishing first does not move the tip past the missing one
+        mark_range_indexed(&db, 400, 699);
+        record_gap(&db, 100, 399, "unavailable".to_string());
+        record_gap(&db, 100, 399, "unavailable".to_string());
+        assert_eq!(load_last_download_block(&db), None);
+        assert_eq!(load_gaps(&db)[0].attempts, 2);
+
+        // after a restart only the ranges not yet indexed are downloaded
+        assert_eq!(
+            pending_ranges(&db, 100, 1_000, 300),
+            vec![(100, 399), (700, 999), (1_000, 1_000)]
+        );
+
+        // a retry that fails part way leaves the rest as a gap
+        mark_range_indexed(&db, 100, 199);
+        assert_eq!(load_last_download_block(&db), Some(199));
+        assert_eq!(load_gaps(&db)[0].start, 200);
+
+        mark_range_indexed(&db, 200, 399);
+        assert_eq!(load_last_download_block(&db), Some(699));
+        assert!(load_gaps(&db).is_empty());
+        assert_eq!(
+            pending_ranges(&db, 700, 1_000, 300),
+            vec![(700, 999), (1_000, 1_000)]
+        );
+    }
+
+    #[test]
+    fn test_migrate_legacy_database() {
+        let db = TestDb::open("migrate_legacy_database");
+        assert_eq!(load_schema_version(&db), SCHEMA_VERSION);
+
+        // everything lived in the default column family before schema versions
+        let meta = db.cf_handle(META).unwrap();
+        db.delete_cf(meta, SCHEMA_VERSION_KEY.as_bytes()).unwrap();
+        let key = "000000000010:msgSendToEth:1700000010:A";
+        let send = serde_json::json!({
+            "sender": "gravity1Sender",
+            "eth_dest": "0xB0B",
+            "amount": [{"denom": "ugraviton", "amount": "100"}],
+            "bridge_fee": [],
+            "chain_fee": []
+        });
+        db.put(key.as_bytes(), send.to_string().as_bytes()).unwrap();
+        // undecodable values are skipped rather than stopping the migration
+        let corrupt_key = "000000000011:msgSendToEth:1700000011:B";
+        db.put(corrupt_key.as_bytes(), b"{\"sender\":").unwrap();
+        db.put(LAST_DOWNLOAD_BLOCK_KEY.as_bytes(), b"40").unwrap();
+        db.put(LAST_DOWNLOAD_BLOCK_UPDATED_KEY.as_bytes(), b"soon")
+            .unwrap();
+        db.put(SECONDARY_INDEXES_BUILT_KEY.as_bytes(), b"1")
+            .unwrap();
+        db.put(b"unknown", b"1").unwrap();
+        assert_eq!(load_schema_version(&db), 0);
+
+        migrate(&db).unwrap();
+        assert_eq!(load_schema_version(&db), SCHEMA_VERSION);
+        // the only message of its tx is the first one
+        assert!(load_msg_send_to_eth(&db, key).is_none());
+        let key = "000000000010:msgSendToEth:1700000010:0:A";
+        assert_eq!(
+            load_msg_send_to_eth(&db, key).unwrap().sender,
+            "gravity1Sender"
+        );
+        assert_eq!(load_last_download_block(&db), Some(40));
+        assert_eq!(load_last_download_block_updated(&db), None);
+        assert!(load_msg_send_to_eth(&db, corrupt_key).is_none());
+        assert!(get_msg(&db, "000000000011:msgSendToEth:1700000011:0:B").is_some());
+        // the send was re-encoded as protobuf, running the migration again leaves it as is
+        encode_records(&db);
+        assert_eq!(
+            load_msg_send_to_eth(&db, key).unwrap().sender,
+            "gravity1Sender"
+        );
+        let rollups = load_rollups(&db, None, "2023-11-14");
+        assert_eq!(rollups.len(), 1);
+        assert_eq!(rollups[0].date, "2023-11-14");
+        assert_eq!(rollups[0].rollup.amount, 100);
+        let sender_index = db.cf_handle(SENDER_INDEX).unwrap();
+        let sender_entries: Vec<Box<[u8]>> = db
+            .iterator_cf(sender_index, rocksdb::IteratorMode::Start)
+            .map(|item| item.unwrap().0)
+            .collect();
+        assert_eq!(
+            sender_entries,
+            vec![format!("gravity1sender:{}", key)
+                .into_bytes()
+                .into_boxed_slice()]
+        );
+        let left: Vec<Box<[u8]>> = db
+            .iterator(rocksdb::IteratorMode::Start)
+            .map(|item| item.unwrap().0)
+            .collect();
+        assert_eq!(left, vec![b"unknown".to_vec().into_boxed_slice()]);
+
+        // a database written by a newer version is not opened
+        put_meta(
+            &db,
+            SCHEMA_VERSION_KEY,
+            (SCHEMA_VERSION + 1).to_string().as_bytes(),
+        );
+        assert!(migrate(&db).is_err());
+    }
 }
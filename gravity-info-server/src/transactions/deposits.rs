@@ -0,0 +1,488 @@
+//! Follows each deposit from Ethereum through the finality delay, the orchestrators' attestation
+//! and, for deposits to another chain, the IBC auto forward. Deposits are only in the Ethereum
+//! info while they are recent and attestations and pending forwards only while they are open, so
+//! this thread records when each stage was first seen. Orchestrator claims are indexed as they are
+//! downloaded, so deposits made before the server ran are still found from their claims.
+
+use crate::config::get_config;
+use crate::gravity_info::{info_tracker_thread, EthInfo, GravityInfo, ETH_INFO_BLOCKS};
+use crate::transactions::database::{
+    cf, delete_deposit, load_deposit, load_deposits, load_event_claims, load_last_observed_nonce,
+    record_last_observed_nonce, save_deposit, ClaimDetails, Deposit, DEPOSIT_TX_HASH_INDEX,
+};
+use log::{error, info};
+use rocksdb::DB;
+use serde::Serialize;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DepositState {
+    /// On Ethereum but not yet in a finalized block, orchestrators won't claim it yet
+    Unfinalized,
+    /// In a finalized block, waiting for orchestrators to claim it
+    Finalized,
+    /// Claimed by some orchestrators, waiting for enough voting power to observe it
+    Attesting,
+    /// Observed and minted to its destination on Gravity
+    Observed,
+    /// Observed and minted on Gravity, waiting to be forwarded over IBC to its destination chain
+    Forwarding,
+    /// Observed and sent over IBC to its destination chain
+    Forwarded,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DepositStatus {
+    pub event_nonce: u64,
+    pub state: DepositState,
+    /// Hash of the Ethereum transaction, None if the deposit is only known from its claims
+    pub tx_hash: Option<String>,
+    pub erc20: String,
+    pub sender: String,
+    pub destination: String,
+    pub amount: String,
+    pub eth_block_height: u64,
+    /// Unix time in seconds the server first saw the deposit on Ethereum
+    pub seen_at: Option<i64>,
+    /// Unix time in seconds the deposit was first seen in a finalized block
+    pub finalized_at: Option<i64>,
+    /// Unix time in seconds of the Gravity block of the first claim
+    pub first_claimed_at: Option<i64>,
+    /// Unix time in seconds the deposit was first seen observed
+    pub observed_at: Option<i64>,
+    /// Unix time in seconds the deposit was first seen forwarded over IBC
+    pub forwarded_at: Option<i64>,
+    /// Number of orchestrators that claimed the deposit
+    pub claims: usize,
+}
+
+/// Records the deposits in the in memory Ethereum info and when each reaches the next stage
+pub fn deposit_tracker_thread(db: Arc<DB>) {
+    info!("Starting deposit tracker");
+
+    info_tracker_thread(db, track_deposits);
+}
+
+/// True if the deposit is to another chain and is forwarded over IBC once observed
+fn is_foreign(destination: &str) -> bool {
+    !destination.starts_with(&format!("{}1", get_config().gravity.prefix))
+}
+
+/// Sets the time of every stage the deposit has newly reached, returns true if any was set.
+/// `pending_forwards` is None if it is not known which deposits are waiting to be forwarded.
+fn advance(
+    deposit: &mut Deposit,
+    finalized: bool,
+    last_observed_nonce: Option<u64>,
+    pending_forwards: Option<&[u64]>,
+    now: i64,
+) -> bool {
+    let before = deposit.clone();
+    let observed = last_observed_nonce.is_some_and(|nonce| nonce >= deposit.event_nonce);
+    if (finalized || observed) && deposit.finalized_at.is_none() {
+        deposit.finalized_at = Some(now);
+    }
+    if observed && deposit.observed_at.is_none() {
+        deposit.observed_at = Some(now);
+    }
+    if let Some(pending) = pending_forwards {
+        if observed
+            && is_foreign(&deposit.destination)
+            && deposit.forwarded_at.is_none()
+            && !pending.contains(&deposit.event_nonce)
+        {
+            deposit.forwarded_at = Some(now);
+        }
+    }
+    *deposit != before
+}
+
+fn track_deposits(db: &DB, gravity: Option<&GravityInfo>, eth: Option<&EthInfo>, now: i64) {
+    let previous_observed = load_last_observed_nonce(db).map(|(nonce, _)| nonce);
+    if let Some(gravity) = gravity {
+        record_last_observed_nonce(db, gravity, now);
+    }
+    let last_observed = load_last_observed_nonce(db).map(|(nonce, _)| nonce);
+    let pending_forwards: Option<Vec<u64>> = gravity.map(|g| {
+        g.pending_ibc_auto_forwards
+            .iter()
+            .map(|f| f.event_nonce)
+            .collect()
+    });
+    let pending_forwards = pending_forwards.as_deref();
+
+    if let Some(eth) = eth {
+        for event in eth.deposit_events.iter() {
+            let eth_block_height = match event.block_height.to_string().parse() {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(
+                        "Skipped deposit {} with invalid block height {}: {}",
+                        event.event_nonce, event.block_height, e
+                    );
+                    continue;
+                }
+            };
+            let mut stored = load_deposit(db, event.event_nonce);
+            // a reorg replaced the deposit with this nonce before it was finalized
+            if let Some(d) = stored
+                .as_ref()
+                .filter(|d| d.finalized_at.is_none() && d.tx_hash != event.tx_hash)
+            {
+                info!("Deposit {} was replaced by a reorg", d.event_nonce);
+                delete_deposit(db, d);
+                stored = None;
+            }
+            let (mut deposit, new) = match stored {
+                Some(deposit) => (deposit, false),
+                None => (
+                    Deposit {
+                        event_nonce: event.event_nonce,
+                        tx_hash: event.tx_hash.clone(),
+                        erc20: event.erc20.to_string(),
+                        sender: event.sender.to_string(),
+                        destination: event.destination.to_string(),
+                        amount: event.amount.to_string(),
+                        eth_block_height,
+                        seen_at: now,
+                        finalized_at: None,
+                        observed_at: None,
+                        forwarded_at: None,
+                    },
+                    true,
+                ),
+            };
+            let advanced = advance(
+                &mut deposit,
+                event.confirmed,
+                last_observed,
+                pending_forwards,
+                now,
+            );
+            if new || advanced {
+                save_deposit(db, &deposit);
+            }
+        }
+
+        // unfinalized deposits in the blocks of the Ethereum info that are no longer in it were
+        // rolled back by a reorg, unless orchestrators have claimed them since
+        match eth.latest_eth_block.to_string().parse::<u64>() {
+            Ok(latest) => {
+                let oldest = latest.saturating_sub(ETH_INFO_BLOCKS);
+                for deposit in load_deposits(db, last_observed.map_or(0, |n| n + 1), u64::MAX) {
+                    if deposit.finalized_at.is_none()
+                        && deposit.eth_block_height > oldest
+                        && !eth
+                            .deposit_events
+                            .iter()
+                            .any(|e| e.event_nonce == deposit.event_nonce)
+                        && load_event_claims(db, deposit.event_nonce).is_empty()
+                    {
+                        info!("Deposit {} was rolled back by a reorg", deposit.event_nonce);
+                        delete_deposit(db, &deposit);
+                    }
+                }
+            }
+            Err(e) => error!(
+                "Invalid latest Ethereum block {}, not checking for reorgs: {}",
+                eth.latest_eth_block, e
+            ),
+        }
+    }
+
+    // deposits observed since the last run that are no longer in the Ethereum info
+    if let (Some(previous), Some(last)) = (previous_observed, last_observed) {
+        if last > previous {
+            for mut deposit in load_deposits(db, previous + 1, last) {
+                if advance(&mut deposit, false, last_observed, pending_forwards, now) {
+                    save_deposit(db, &deposit);
+                }
+            }
+        }
+    }
+}
+
+/// The status of the deposit with `event_nonce`, a deposit is finalized once
+/// `finalized_eth_height` reaches its block. `pending_forwards` are the nonces of the deposits
+/// waiting to be forwarded over IBC, None if unknown.
+pub fn load_deposit_status(
+    db: &DB,
+    event_nonce: u64,
+    finalized_eth_height: Option<u64>,
+    pending_forwards: Option<&[u64]>,
+) -> Option<DepositStatus> {
+    let deposit = load_deposit(db, event_nonce);
+    let claims = load_event_claims(db, event_nonce);
+    let (tx_hash, erc20, sender, destination, amount, eth_block_height) = match &deposit {
+        Some(d) => (
+            d.tx_hash.clone(),
+            d.erc20.clone(),
+            d.sender.clone(),
+            d.destination.clone(),
+            d.amount.clone(),
+            d.eth_block_height,
+        ),
+        // a deposit from before the server ran is only known from its claims
+        None => claims.iter().find_map(|claim| match &claim.details {
+            ClaimDetails::SendToCosmos {
+                token_contract,
+                amount,
+                ethereum_sender,
+                cosmos_receiver,
+            } => Some((
+                None,
+                token_contract.clone(),
+                ethereum_sender.clone(),
+                cosmos_receiver.clone(),
+                amount.clone(),
+                claim.eth_block_height,
+            )),
+            _ => None,
+        })?,
+    };
+
+    let last_observed = load_last_observed_nonce(db);
+    let finalized_at = deposit.as_ref().and_then(|d| d.finalized_at);
+    let observed_at = deposit
+        .as_ref()
+        .and_then(|d| d.observed_at)
+        .or(match last_observed {
+            Some((nonce, updated)) if nonce == event_nonce => Some(updated),
+            _ => None,
+        });
+    let forwarded_at = deposit.as_ref().and_then(|d| d.forwarded_at);
+    let observed = last_observed.is_some_and(|(nonce, _)| nonce >= event_nonce);
+    let state = if observed && !is_foreign(&destination) {
+        DepositState::Observed
+    } else if observed {
+        match (forwarded_at, pending_forwards) {
+            (Some(_), _) => DepositState::Forwarded,
+            (None, Some(pending)) if !pending.contains(&event_nonce) => DepositState::Forwarded,
+            _ => DepositState::Forwarding,
+        }
+    } else if !claims.is_empty() {
+        DepositState::Attesting
+    } else if finalized_at.is_some()
+        || finalized_eth_height.is_some_and(|height| height >= eth_block_height)
+    {
+        DepositState::Finalized
+    } else {
+        DepositState::Unfinalized
+    };
+    Some(DepositStatus {
+        event_nonce,
+        state,
+        tx_hash,
+        erc20,
+        sender,
+        destination,
+        amount,
+        eth_block_height,
+        seen_at: deposit.as_ref().map(|d| d.seen_at),
+        finalized_at,
+        first_claimed_at: claims.first().map(|c| c.timestamp),
+        observed_at,
+        forwarded_at,
+        claims: claims.len(),
+    })
+}
+
+/// The status of every deposit in the Ethereum tx with `tx_hash`
+pub fn load_tx_deposits(
+    db: &DB,
+    tx_hash: &str,
+    finalized_eth_height: Option<u64>,
+    pending_forwards: Option<&[u64]>,
+) -> Vec<DepositStatus> {
+    let prefix = format!("{}:", tx_hash.to_lowercase());
+    let cf = cf(db, DEPOSIT_TX_HASH_INDEX);
+    let mut deposits = Vec::new();
+    for item in db.prefix_iterator_cf(cf, prefix.as_bytes()) {
+        let (index_key, _) = match item {
+            Ok(v) => v,
+            Err(e) => {
+                error!("RocksDB iterator error: {}", e);
+                break;
+            }
+        };
+        let index_key = String::from_utf8_lossy(&index_key);
+        let nonce = match index_key.strip_prefix(&prefix).map(|k| k.parse()) {
+            Some(Ok(nonce)) => nonce,
+            Some(Err(_)) => {
+                error!("Index entry points at malformed key {}", index_key);
+                continue;
+            }
+            None => break,
+        };
+        if let Some(deposit) =
+            load_deposit_status(db, nonce, finalized_eth_height, pending_forwards)
+        {
+            deposits.push(deposit);
+        }
+    }
+    deposits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gravity_info::{DepositWithMetadata, InternalIbcAutoForward};
+    use crate::transactions::database::{save_claim, CustomClaim};
+    use crate::upstream::fake::{
+        self, observed, send_to_cosmos_log, TestDb, BRIDGE_ADDRESS, ETH_FINALIZED_BLOCK,
+        ETH_LATEST_BLOCK, WETH_ADDRESS,
+    };
+    use gravity_utils::types::SendToCosmosEvent;
+
+    const SENDER: &str = "0xf0f08f640d5553e79b91296dba6c3f10521e5174";
+    const GRAVITY_DESTINATION: &str = "gravity1cwulk79y2t8zljgvlutqs5gzx4gruwmj0txnnm";
+    const OSMOSIS_DESTINATION: &str = "osmo1cwulk79y2t8zljgvlutqs5gzx4gruwmjrq8mqp";
+
+    fn eth_info(deposits: &[(&str, u64, u64)]) -> EthInfo {
+        let logs: Vec<_> = deposits
+            .iter()
+            .map(|(destination, nonce, block)| {
+                send_to_cosmos_log(
+                    BRIDGE_ADDRESS.parse().unwrap(),
+                    WETH_ADDRESS.parse().unwrap(),
+                    SENDER.parse().unwrap(),
+                    destination,
+                    1_000_000_000_000_000_000,
+                    *nonce,
+                    *block,
+                )
+            })
+            .collect();
+        EthInfo {
+            deposit_events: SendToCosmosEvent::from_logs(&logs)
+                .unwrap()
+                .into_iter()
+                .filter_map(|event| {
+                    let tx_hash = format!("0x{:064x}", event.event_nonce);
+                    DepositWithMetadata::convert(event, Some(tx_hash), ETH_FINALIZED_BLOCK.into())
+                })
+                .collect(),
+            latest_eth_block: ETH_LATEST_BLOCK.into(),
+            latest_finalized_eth_block: ETH_FINALIZED_BLOCK.into(),
+            ..Default::default()
+        }
+    }
+
+    fn claim(event_nonce: u64, orchestrator: &str, timestamp: i64) -> CustomClaim {
+        fake::claim(
+            event_nonce,
+            orchestrator,
+            timestamp,
+            ETH_FINALIZED_BLOCK - 300,
+            ClaimDetails::SendToCosmos {
+                token_contract: WETH_ADDRESS.to_string(),
+                amount: "1000000".to_string(),
+                ethereum_sender: SENDER.to_string(),
+                cosmos_receiver: GRAVITY_DESTINATION.to_string(),
+            },
+        )
+    }
+
+    #[test]
+    fn test_deposit_lifecycle() {
+        let db = TestDb::open("deposit_lifecycle");
+        let started = 1_700_000_000;
+        let finalized = Some(ETH_FINALIZED_BLOCK);
+        let status = |nonce: u64, pending: Option<&[u64]>| {
+            load_deposit_status(&db, nonce, finalized, pending).unwrap()
+        };
+
+        // a finalized deposit to Gravity and a recent one to Osmosis
+        let eth = eth_info(&[
+            (GRAVITY_DESTINATION, 500, ETH_FINALIZED_BLOCK - 10),
+            (OSMOSIS_DESTINATION, 501, ETH_LATEST_BLOCK - 10),
+        ]);
+        track_deposits(&db, None, Some(&eth), started);
+        let deposit = status(500, None);
+        assert_eq!(deposit.state, DepositState::Finalized);
+        assert_eq!(deposit.seen_at, Some(started));
+        assert_eq!(deposit.finalized_at, Some(started));
+        assert_eq!(deposit.tx_hash, Some(format!("0x{:064x}", 500)));
+        let deposit = status(501, None);
+        assert_eq!(deposit.state, DepositState::Unfinalized);
+        assert_eq!(deposit.finalized_at, None);
+
+        // orchestrators claim the first deposit
+        save_claim(&db, &claim(500, "gravity1alice", started + 30));
+        save_claim(&db, &claim(500, "gravity1bob", started + 40));
+        let deposit = status(500, None);
+        assert_eq!(deposit.state, DepositState::Attesting);
+        assert_eq!(deposit.claims, 2);
+        assert_eq!(deposit.first_claimed_at, Some(started + 30));
+
+        // both are observed, the second waits to be forwarded to Osmosis
+        let gravity = GravityInfo {
+            attestations: vec![observed(499), observed(501)],
+            pending_ibc_auto_forwards: vec![InternalIbcAutoForward {
+                event_nonce: 501,
+                foreign_receiver: OSMOSIS_DESTINATION.to_string(),
+                ibc_channel: "channel-10".to_string(),
+                amount: "1000000000000000000".to_string(),
+                denom: format!("gravity{}", WETH_ADDRESS),
+            }],
+            ..Default::default()
+        };
+        track_deposits(&db, Some(&gravity), Some(&eth), started + 60);
+        let pending = [501];
+        let deposit = status(500, Some(&pending));
+        assert_eq!(deposit.state, DepositState::Observed);
+        assert_eq!(deposit.observed_at, Some(started + 60));
+        let deposit = status(501, Some(&pending));
+        assert_eq!(deposit.state, DepositState::Forwarding);
+        assert_eq!(deposit.finalized_at, Some(started + 60));
+        assert_eq!(deposit.forwarded_at, None);
+
+        // the deposit is forwarded once it leaves the queue
+        let gravity = GravityInfo {
+            attestations: vec![observed(501)],
+            ..Default::default()
+        };
+        track_deposits(&db, Some(&gravity), Some(&eth), started + 120);
+        let deposit = status(501, Some(&[]));
+        assert_eq!(deposit.state, DepositState::Forwarded);
+        assert_eq!(deposit.forwarded_at, Some(started + 120));
+        assert_eq!(status(500, Some(&[])).forwarded_at, None);
+        let deposits = load_tx_deposits(&db, &format!("0x{:064X}", 501), finalized, None);
+        assert_eq!(deposits.len(), 1);
+        assert_eq!(deposits[0].destination, OSMOSIS_DESTINATION);
+
+        // a deposit from before the server ran is only known from its claims
+        save_claim(&db, &claim(400, "gravity1alice", started - 600));
+        let deposit = status(400, None);
+        assert_eq!(deposit.state, DepositState::Observed);
+        assert_eq!(deposit.tx_hash, None);
+        assert_eq!(deposit.seen_at, None);
+        assert_eq!(deposit.sender, SENDER);
+        assert!(load_deposit_status(&db, 450, finalized, None).is_none());
+    }
+
+    #[test]
+    fn test_deposit_reorg() {
+        let db = TestDb::open("deposit_reorg");
+        let started = 1_700_000_000;
+        let tx_hash = format!("0x{:064x}", 600);
+
+        track_deposits(
+            &db,
+            None,
+            Some(&eth_info(&[(
+                OSMOSIS_DESTINATION,
+                600,
+                ETH_LATEST_BLOCK - 5,
+            )])),
+            started,
+        );
+        assert_eq!(load_tx_deposits(&db, &tx_hash, None, None).len(), 1);
+
+        // the block of the deposit is reorged out before it is finalized
+        track_deposits(&db, None, Some(&eth_info(&[])), started + 60);
+        assert!(load_deposit(&db, 600).is_none());
+        assert!(load_tx_deposits(&db, &tx_hash, None, None).is_empty());
+    }
+}
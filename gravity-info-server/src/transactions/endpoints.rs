@@ -1,17 +1,29 @@
-use crate::transactions::database::CustomCoin;
-use crate::transactions::database::{ApiResponse, CustomMsgSendToEth, CustomMsgTransfer};
+use crate::config::get_config;
+use crate::gravity_info::{get_eth_info, get_gravity_info};
+use crate::transactions::database::{
+    cf, claim_key, eth_event_cf_by_name, event_nonce_key, first_rollup_date, get_indexer_status,
+    get_msg, load_claim, load_confirm, load_confirms_since, load_eth_events,
+    load_eth_events_synced_to, load_eth_reorgs, load_event_claims, load_last_download_block,
+    load_rollups, load_tx_status, load_unconfirmed_eth_events, msg_cf, tx_succeeded, ApiResponse,
+    ConfirmDetails, CustomClaim, CustomConfirm, EthEvent, RollupEntry, TxStatus, CHANNEL_INDEX,
+    CONFIRM_ORCHESTRATOR_INDEX, DENOM_INDEX, DEPOSIT_DESTINATION_INDEX, DEPOSIT_SENDER_INDEX,
+    MSG_KEY_PARTS, ORCHESTRATOR_INDEX, RECEIVER_INDEX, SENDER_INDEX, TX_HASH_INDEX,
+};
+use crate::transactions::deposits::{load_deposit_status, load_tx_deposits, DepositStatus};
+use crate::transactions::handlers::handler_by_name;
+use crate::transactions::withdrawals::{load_tx_withdrawals, load_withdrawal, Withdrawal};
 
 use actix_web::Responder;
 use actix_web::{web, HttpResponse};
-use chrono::{DateTime, Datelike, Local, Utc};
+use chrono::{DateTime, Datelike, Days, Local, Months, NaiveDate, Utc};
 
 use log::error;
 
-use rocksdb::DB;
+use rocksdb::{Direction, IteratorMode, DB};
 
-use serde::Serialize;
-use std::collections::HashMap;
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 
 #[derive(Debug, Serialize)]
 struct TimeFrameData {
@@ -21,9 +33,18 @@ struct TimeFrameData {
 #[derive(Debug, Serialize)]
 struct TimeFrame {
     period: String,
+    /// First and last UTC day included as `YYYY-MM-DD`, from is None for all time
+    from: Option<String>,
+    to: String,
     amount_totals: HashMap<String, u128>,
     bridge_fee_totals: HashMap<String, u128>,
     chain_fee_totals: HashMap<String, u128>,
+    /// Number of sends by the denom of their amount
+    send_counts: HashMap<String, u64>,
+    /// Sends in this period that were later cancelled, not included in the totals above
+    cancelled_amount_totals: HashMap<String, u128>,
+    cancelled_bridge_fee_totals: HashMap<String, u128>,
+    cancelled_send_counts: HashMap<String, u64>,
 }
 
 #[derive(Serialize)]
@@ -33,280 +54,1694 @@ struct BlockTransactions {
     formatted_date: String,
 }
 
-type BlockData = (String, Vec<ApiResponse>);
+/// Default and maximum number of messages returned per page
+const DEFAULT_PAGE_LIMIT: usize = 100;
+const MAX_PAGE_LIMIT: usize = 1000;
 
-fn process_fee(fee: Vec<CustomCoin>, totals: &HashMap<String, u128>) -> HashMap<String, u128> {
-    let mut new_totals = totals.clone();
-    for custom_coin in fee {
-        let decimal_value = custom_coin.amount.parse::<u128>().unwrap();
-        let denom = custom_coin.denom.clone();
-        *new_totals.entry(denom).or_default() += decimal_value;
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Query parameters accepted by the transaction listing endpoints, all heights and times are inclusive
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TxQuery {
+    /// The `next_cursor` of the previous page, results continue after this key
+    pub cursor: Option<String>,
+    pub from_height: Option<u64>,
+    pub to_height: Option<u64>,
+    /// Unix time in seconds
+    pub from_time: Option<i64>,
+    pub to_time: Option<i64>,
+    pub order: Option<Order>,
+    pub limit: Option<usize>,
+    /// Messages from txs that failed on chain are left out unless this is set
+    pub include_failed: Option<bool>,
+}
+
+/// Splits the send to eth totals into days, weeks starting on Monday, calendar months or years
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bucket {
+    Day,
+    Week,
+    Month,
+    Year,
+    #[default]
+    All,
+}
+
+/// Most buckets returned by the send to eth totals, a little over five years of days
+const MAX_BUCKETS: usize = 2000;
+
+/// Without `from`, `to` or `bucket` the totals are returned for fixed periods ending today
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TotalsQuery {
+    /// Sends from txs that failed on chain are left out unless this is set
+    pub include_failed: Option<bool>,
+    /// First UTC day included as `YYYY-MM-DD`, the first day with a send by default
+    pub from: Option<String>,
+    /// Last UTC day included as `YYYY-MM-DD`, today by default
+    pub to: Option<String>,
+    /// The whole range is a single bucket by default
+    pub bucket: Option<Bucket>,
+}
+
+#[derive(Serialize)]
+struct TransactionPage {
+    blocks: Vec<BlockTransactions>,
+    /// Pass as `cursor` to get the next page, None once the range is exhausted
+    next_cursor: Option<String>,
+}
+
+/// A parsed `{height}:{type}:{timestamp}:{msg_index}:{hash}` transaction key
+struct TxKey<'a> {
+    height: u64,
+    msg_type: &'a str,
+    timestamp: i64,
+    tx_hash: &'a str,
+}
+
+fn parse_tx_key(key: &str) -> Option<TxKey<'_>> {
+    let key_parts: Vec<&str> = key.split(':').collect();
+    if key_parts.len() != MSG_KEY_PARTS || key_parts[3].parse::<u32>().is_err() {
+        return None;
     }
-    new_totals
+    Some(TxKey {
+        height: key_parts[0].parse().ok()?,
+        msg_type: key_parts[1],
+        timestamp: key_parts[2].parse().ok()?,
+        tx_hash: key_parts[4],
+    })
 }
 
-pub async fn get_all_msg_send_to_eth_transactions(db: web::Data<Arc<DB>>) -> impl Responder {
-    let mut response_data: HashMap<u64, BlockData> = HashMap::new();
+fn format_date(timestamp: i64) -> String {
+    let datetime_utc = DateTime::from_timestamp(timestamp, 0).unwrap();
+    let datetime_local: DateTime<Local> = datetime_utc.into();
+    format!(
+        "{:02}-{:02}-{}",
+        datetime_local.month(),
+        datetime_local.day(),
+        datetime_local.year()
+    )
+}
 
-    let iterator = db.iterator(rocksdb::IteratorMode::Start);
+#[derive(Serialize)]
+struct TxMessage {
+    msg_type: String,
+    data: serde_json::Value,
+}
 
+#[derive(Serialize)]
+struct TransactionDetails {
+    tx_hash: String,
+    block_number: u64,
+    timestamp: i64,
+    formatted_date: String,
+    /// The indexed message types the transaction contained, without duplicates
+    msg_types: Vec<String>,
+    status: Option<TxStatus>,
+    messages: Vec<TxMessage>,
+}
+
+/// Validated paging parameters of a `TxQuery`
+struct PageParams {
+    order: Order,
+    limit: usize,
+    from_height: u64,
+    to_height: u64,
+    from_time: i64,
+    to_time: i64,
+    include_failed: bool,
+}
+
+fn page_params(query: &TxQuery, msg_type: Option<&str>) -> Result<PageParams, String> {
+    let limit = page_limit(query.limit)?;
+    if let Some(cursor) = &query.cursor {
+        match parse_tx_key(cursor) {
+            Some(key) if msg_type.is_none() || msg_type == Some(key.msg_type) => {}
+            _ => return Err("Invalid cursor".to_string()),
+        }
+    }
+    Ok(PageParams {
+        order: query.order.unwrap_or_default(),
+        limit,
+        from_height: query.from_height.unwrap_or(0),
+        to_height: query.to_height.unwrap_or(u64::MAX),
+        from_time: query.from_time.unwrap_or(i64::MIN),
+        to_time: query.to_time.unwrap_or(i64::MAX),
+        include_failed: query.include_failed.unwrap_or(false),
+    })
+}
+
+/// Returns up to `limit` message keys in the requested range, seeking straight to the start of the
+/// range and stopping as soon as it leaves it rather than scanning the whole database. Keys sort by
+/// height and block timestamps never decrease with height, so the time bounds end the scan too.
+/// `name` is the column family of the messages themselves or an index column family, whose entries
+/// are the message keys behind `prefix`.
+fn scan_keys(
+    db: &DB,
+    name: &str,
+    prefix: &str,
+    msg_type: Option<&str>,
+    cursor: Option<&str>,
+    params: &PageParams,
+) -> Vec<String> {
+    // ':' sorts just before ';' so these bound every key at a given height
+    let start_key = match (cursor, params.order) {
+        (Some(cursor), _) => format!("{}{}", prefix, cursor),
+        (None, Order::Asc) => format!("{}{:012}:", prefix, params.from_height),
+        (None, Order::Desc) => format!("{}{:012};", prefix, params.to_height.min(999_999_999_999)),
+    };
+    let direction = match params.order {
+        Order::Asc => Direction::Forward,
+        Order::Desc => Direction::Reverse,
+    };
+    let mode = IteratorMode::From(start_key.as_bytes(), direction);
+    let iterator = db.iterator_cf(cf(db, name), mode);
+
+    let mut keys = Vec::new();
     for item in iterator {
-        match item {
-            Ok((key, value)) => {
-                let key_str = String::from_utf8_lossy(&key);
-                let key_parts: Vec<&str> = key_str.split(':').collect();
-                if key_parts.len() == 4 && key_parts[1] == "msgSendToEth" {
-                    let msg_send_to_eth: CustomMsgSendToEth =
-                        serde_json::from_slice(&value).unwrap();
-                    let block_number = key_parts[0].parse::<u64>().unwrap();
-
-                    let timestamp = key_parts[2].parse::<i64>().unwrap();
-
-                    let datetime_utc = DateTime::from_timestamp(timestamp, 0).unwrap(); // we can safely unwrap because of the `continue` above
-
-                    let datetime_local: DateTime<Local> = datetime_utc.into();
-
-                    // Extract month, day, and year
-                    let month = datetime_local.month();
-                    let day = datetime_local.day();
-                    let year = datetime_local.year();
-
-                    // Format the date string
-                    let formatted_date = format!("{:02}-{:02}-{}", month, day, year);
-                    let api_response = ApiResponse {
-                        tx_hash: key_parts[3].to_string(),
-                        data: serde_json::to_value(&msg_send_to_eth).unwrap(),
-                    };
-
-                    response_data
-                        .entry(block_number)
-                        .or_insert((formatted_date, Vec::new()))
-                        .1
-                        .push(api_response);
-                }
-            }
+        let key = match item {
+            Ok((key, _)) => key,
             Err(err) => {
                 error!("RocksDB iterator error: {}", err);
+                continue;
+            }
+        };
+        let key_str = String::from_utf8_lossy(&key);
+        let tx_key_str = match key_str.strip_prefix(prefix) {
+            Some(k) => k,
+            None => break,
+        };
+        if cursor == Some(tx_key_str) {
+            continue;
+        }
+        // skips index entries of another value sharing this prefix
+        let tx_key = match parse_tx_key(tx_key_str) {
+            Some(k) => k,
+            None => continue,
+        };
+        let past_end = match params.order {
+            Order::Asc => tx_key.height > params.to_height || tx_key.timestamp > params.to_time,
+            Order::Desc => {
+                tx_key.height < params.from_height || tx_key.timestamp < params.from_time
+            }
+        };
+        if past_end {
+            break;
+        }
+        if msg_type.is_some_and(|t| t != tx_key.msg_type)
+            || tx_key.timestamp < params.from_time
+            || tx_key.timestamp > params.to_time
+        {
+            continue;
+        }
+        if !params.include_failed && !tx_succeeded(db, tx_key.tx_hash) {
+            continue;
+        }
+        keys.push(tx_key_str.to_string());
+        if keys.len() == params.limit {
+            break;
+        }
+    }
+    keys
+}
+
+/// Decodes a stored message into its API representation
+fn decode_msg(msg_type: &str, value: &[u8]) -> Option<serde_json::Value> {
+    handler_by_name(msg_type)?.to_json(value)
+}
+
+/// Loads the messages stored under `keys` and groups them by block, `next_cursor` is set when
+/// the page is full
+fn build_page(db: &DB, keys: Vec<String>, limit: usize, with_msg_type: bool) -> TransactionPage {
+    let next_cursor = if keys.len() == limit {
+        keys.last().cloned()
+    } else {
+        None
+    };
+    let mut blocks: Vec<BlockTransactions> = Vec::new();
+    for key in keys.iter() {
+        let tx_key = match parse_tx_key(key) {
+            Some(k) => k,
+            None => {
+                error!("Index entry points at malformed key {}", key);
+                continue;
+            }
+        };
+        let value = match get_msg(db, key) {
+            Some(v) => v,
+            None => {
+                error!("Index entry points at missing message {}", key);
+                continue;
+            }
+        };
+        let data = match decode_msg(tx_key.msg_type, &value) {
+            Some(v) => v,
+            None => continue,
+        };
+        let api_response = ApiResponse {
+            tx_hash: tx_key.tx_hash.to_string(),
+            msg_type: with_msg_type.then(|| tx_key.msg_type.to_string()),
+            status: load_tx_status(db, tx_key.tx_hash),
+            data,
+        };
+        match blocks.last_mut() {
+            Some(block) if block.block_number == tx_key.height => {
+                block.transactions.push(api_response)
             }
+            _ => blocks.push(BlockTransactions {
+                block_number: tx_key.height,
+                transactions: vec![api_response],
+                formatted_date: format_date(tx_key.timestamp),
+            }),
         }
     }
+    TransactionPage {
+        blocks,
+        next_cursor,
+    }
+}
 
-    // Converting the HashMap to a Vec and sorting it by block number
-    let mut response_data: Vec<_> = response_data.into_iter().collect();
-    response_data.sort_by(|a, b| a.0.cmp(&b.0));
+/// Reads one page of `msg_type` messages
+fn transaction_page(db: &DB, msg_type: &str, query: &TxQuery) -> Result<TransactionPage, String> {
+    let params = page_params(query, Some(msg_type))?;
+    let cf = msg_cf(msg_type).ok_or("Unknown message type")?;
+    let keys = scan_keys(db, cf, "", Some(msg_type), query.cursor.as_deref(), &params);
+    Ok(build_page(db, keys, params.limit, false))
+}
 
-    // Convert Vec of tuples into Vec of BlockTransactions
-    let response_data: Vec<_> = response_data
-        .into_iter()
-        .map(
-            |(block_number, (formatted_date, transactions))| BlockTransactions {
-                block_number,
-                formatted_date,
-                transactions,
-            },
-        )
+/// Reads one page of messages of any type with `value` in any of the given index column families
+fn indexed_page(
+    db: &DB,
+    indexes: &[&str],
+    value: &str,
+    query: &TxQuery,
+) -> Result<TransactionPage, String> {
+    let params = page_params(query, None)?;
+    let prefix = format!("{}:", value);
+    // each index is already in order, so the first `limit` keys of their union are found in the
+    // first `limit` keys of each one
+    let mut keys: Vec<String> = indexes
+        .iter()
+        .flat_map(|index| scan_keys(db, index, &prefix, None, query.cursor.as_deref(), &params))
         .collect();
+    keys.sort();
+    keys.dedup();
+    if params.order == Order::Desc {
+        keys.reverse();
+    }
+    keys.truncate(params.limit);
+    Ok(build_page(db, keys, params.limit, true))
+}
 
-    HttpResponse::Ok().json(response_data)
+/// Looks up the indexed messages of a transaction, None if it contained none
+fn transaction_details(db: &DB, tx_hash: &str) -> Option<TransactionDetails> {
+    let tx_hash = tx_hash.to_uppercase();
+    let prefix = format!("{}:", tx_hash);
+    let cf = cf(db, TX_HASH_INDEX);
+
+    let mut details: Option<TransactionDetails> = None;
+    for item in db.prefix_iterator_cf(cf, prefix.as_bytes()) {
+        let (index_key, _) = item.unwrap();
+        let index_key = String::from_utf8_lossy(&index_key);
+        let key = match index_key.strip_prefix(&prefix) {
+            Some(k) => k,
+            None => break,
+        };
+        let tx_key = match parse_tx_key(key) {
+            Some(k) => k,
+            None => {
+                error!("Index entry points at malformed key {}", key);
+                continue;
+            }
+        };
+        let data = match get_msg(db, key) {
+            Some(value) => decode_msg(tx_key.msg_type, &value),
+            None => {
+                error!("Index entry points at missing message {}", key);
+                None
+            }
+        };
+        let data = match data {
+            Some(d) => d,
+            None => continue,
+        };
+        let details = details.get_or_insert_with(|| TransactionDetails {
+            tx_hash: tx_hash.clone(),
+            block_number: tx_key.height,
+            timestamp: tx_key.timestamp,
+            formatted_date: format_date(tx_key.timestamp),
+            msg_types: Vec::new(),
+            status: load_tx_status(db, &tx_hash),
+            messages: Vec::new(),
+        });
+        if !details.msg_types.iter().any(|t| t == tx_key.msg_type) {
+            details.msg_types.push(tx_key.msg_type.to_string());
+        }
+        details.messages.push(TxMessage {
+            msg_type: tx_key.msg_type.to_string(),
+            data,
+        });
+    }
+    details
 }
 
-pub async fn get_all_msg_ibc_transfer_transactions(db: web::Data<Arc<DB>>) -> impl Responder {
-    let mut response_data: HashMap<u64, BlockData> = HashMap::new();
+pub async fn get_transaction_by_hash(db: web::Data<Arc<DB>>, tx_hash: &str) -> impl Responder {
+    match transaction_details(&db, tx_hash) {
+        Some(details) => HttpResponse::Ok().json(details),
+        None => HttpResponse::NotFound().json("Transaction not found"),
+    }
+}
 
-    let iterator = db.iterator(rocksdb::IteratorMode::Start);
+pub async fn get_transaction_indexer_status(db: web::Data<Arc<DB>>) -> impl Responder {
+    HttpResponse::Ok().json(get_indexer_status(&db))
+}
 
-    for item in iterator {
-        match item {
-            Ok((key, value)) => {
-                let key_str = String::from_utf8_lossy(&key);
-                let key_parts: Vec<&str> = key_str.split(':').collect();
-                if key_parts.len() == 4 && key_parts[1] == "msgIbcTransfer" {
-                    let msg_ibc_transfer: CustomMsgTransfer =
-                        serde_json::from_slice(&value).unwrap();
-                    let block_number = key_parts[0].parse::<u64>().unwrap();
-
-                    let timestamp = key_parts[2].parse::<i64>().unwrap();
-
-                    let datetime_utc = DateTime::from_timestamp(timestamp, 0).unwrap(); // we can safely unwrap because of the `continue` above
-
-                    let datetime_local: DateTime<Local> = datetime_utc.into();
-
-                    // Extract month, day, and year
-                    let month = datetime_local.month();
-                    let day = datetime_local.day();
-                    let year = datetime_local.year();
-
-                    // Format the date string
-                    let formatted_date = format!("{:02}-{:02}-{}", month, day, year);
-                    let api_response = ApiResponse {
-                        tx_hash: key_parts[3].to_string(),
-                        data: serde_json::to_value(&msg_ibc_transfer).unwrap(),
-                    };
-
-                    response_data
-                        .entry(block_number)
-                        .or_insert((formatted_date, Vec::new()))
-                        .1
-                        .push(api_response);
-                }
-            }
+pub async fn get_all_msg_send_to_eth_transactions(
+    db: web::Data<Arc<DB>>,
+    query: web::Query<TxQuery>,
+) -> impl Responder {
+    match transaction_page(&db, "msgSendToEth", &query) {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(e) => HttpResponse::BadRequest().json(e),
+    }
+}
+
+pub async fn get_all_msg_ibc_transfer_transactions(
+    db: web::Data<Arc<DB>>,
+    query: web::Query<TxQuery>,
+) -> impl Responder {
+    match transaction_page(&db, "msgIbcTransfer", &query) {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(e) => HttpResponse::BadRequest().json(e),
+    }
+}
+
+pub async fn get_all_msg_cancel_send_to_eth_transactions(
+    db: web::Data<Arc<DB>>,
+    query: web::Query<TxQuery>,
+) -> impl Responder {
+    match transaction_page(&db, "msgCancelSendToEth", &query) {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(e) => HttpResponse::BadRequest().json(e),
+    }
+}
+
+/// Messages of any type sent from or to `address`, a Gravity address or an Ethereum or IBC destination
+pub async fn get_transactions_by_address(
+    db: web::Data<Arc<DB>>,
+    address: &str,
+    query: web::Query<TxQuery>,
+) -> impl Responder {
+    let address = address.to_lowercase();
+    match indexed_page(&db, &[SENDER_INDEX, RECEIVER_INDEX], &address, &query) {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(e) => HttpResponse::BadRequest().json(e),
+    }
+}
+
+pub async fn get_transactions_by_denom(
+    db: web::Data<Arc<DB>>,
+    denom: &str,
+    query: web::Query<TxQuery>,
+) -> impl Responder {
+    match indexed_page(&db, &[DENOM_INDEX], denom, &query) {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(e) => HttpResponse::BadRequest().json(e),
+    }
+}
+
+pub async fn get_transactions_by_channel(
+    db: web::Data<Arc<DB>>,
+    channel: &str,
+    query: web::Query<TxQuery>,
+) -> impl Responder {
+    match indexed_page(&db, &[CHANNEL_INDEX], channel, &query) {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(e) => HttpResponse::BadRequest().json(e),
+    }
+}
+
+#[derive(Serialize)]
+struct WithdrawalPage {
+    withdrawals: Vec<Withdrawal>,
+    /// Pass as `cursor` to get the next page, None once the range is exhausted
+    next_cursor: Option<String>,
+}
+
+fn latest_eth_height() -> Option<u64> {
+    get_eth_info().and_then(|info| info.latest_eth_block.to_string().parse().ok())
+}
+
+fn finalized_eth_height() -> Option<u64> {
+    get_eth_info().and_then(|info| info.latest_finalized_eth_block.to_string().parse().ok())
+}
+
+/// Reads one page of the withdrawals of the sends to eth from `sender`
+fn withdrawal_page(db: &DB, sender: &str, query: &TxQuery) -> Result<WithdrawalPage, String> {
+    let params = page_params(query, Some("msgSendToEth"))?;
+    let prefix = format!("{}:", sender.to_lowercase());
+    let keys = scan_keys(
+        db,
+        SENDER_INDEX,
+        &prefix,
+        Some("msgSendToEth"),
+        query.cursor.as_deref(),
+        &params,
+    );
+    let next_cursor = if keys.len() == params.limit {
+        keys.last().cloned()
+    } else {
+        None
+    };
+    let latest_eth_height = latest_eth_height();
+    let withdrawals = keys
+        .iter()
+        .filter_map(|key| load_withdrawal(db, key, latest_eth_height))
+        .collect();
+    Ok(WithdrawalPage {
+        withdrawals,
+        next_cursor,
+    })
+}
+
+/// Where each send to eth in the tx with `tx_hash` is on its way to Ethereum
+pub async fn get_withdrawal(db: web::Data<Arc<DB>>, tx_hash: &str) -> impl Responder {
+    let withdrawals = load_tx_withdrawals(&db, tx_hash, latest_eth_height());
+    if withdrawals.is_empty() {
+        HttpResponse::NotFound().json("No send to eth found in this transaction")
+    } else {
+        HttpResponse::Ok().json(withdrawals)
+    }
+}
+
+pub async fn get_withdrawals_by_sender(
+    db: web::Data<Arc<DB>>,
+    sender: &str,
+    query: web::Query<TxQuery>,
+) -> impl Responder {
+    match withdrawal_page(&db, sender, &query) {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(e) => HttpResponse::BadRequest().json(e),
+    }
+}
+
+/// Query parameters accepted by the orchestrator claims, deposit and Ethereum event endpoints,
+/// nonces are inclusive
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ClaimQuery {
+    /// The `next_cursor` of the previous page, results continue after this event nonce
+    pub cursor: Option<u64>,
+    pub from_nonce: Option<u64>,
+    pub to_nonce: Option<u64>,
+    pub order: Option<Order>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct ClaimPage {
+    claims: Vec<CustomClaim>,
+    /// Pass as `cursor` to get the next page, None once the range is exhausted
+    next_cursor: Option<u64>,
+}
+
+/// One page of the entries for one value in an index whose keys start with a number, such as
+/// an orchestrator's claims or an address's deposits
+struct NumberedScan<'a> {
+    value: &'a str,
+    /// Inclusive bounds on the number each key starts with
+    from: u64,
+    to: u64,
+    order: Order,
+    /// Results continue after this key
+    cursor: Option<&'a str>,
+    limit: usize,
+}
+
+/// Returns the keys of the entries in `index` selected by `scan`, whose keys start with a number
+/// zero padded to `width` digits
+fn scan_numbered_index(db: &DB, index: &str, width: usize, scan: &NumberedScan) -> Vec<String> {
+    let NumberedScan {
+        value,
+        from,
+        to,
+        order,
+        cursor,
+        limit,
+    } = *scan;
+    let prefix = format!("{}:", value.to_lowercase());
+    let max = 10u64.checked_pow(width as u32).map_or(u64::MAX, |m| m - 1);
+    // ':' sorts just before ';' so these bound every entry for a number, including keys that
+    // are just the number
+    let (start_key, direction) = match (cursor, order) {
+        (Some(cursor), Order::Asc) => (format!("{}{}", prefix, cursor), Direction::Forward),
+        (Some(cursor), Order::Desc) => (format!("{}{}", prefix, cursor), Direction::Reverse),
+        (None, Order::Asc) => (
+            format!("{}{:0width$}", prefix, from, width = width),
+            Direction::Forward,
+        ),
+        (None, Order::Desc) => (
+            format!("{}{:0width$};", prefix, to.min(max), width = width),
+            Direction::Reverse,
+        ),
+    };
+    let cf = cf(db, index);
+
+    let mut keys = Vec::new();
+    for item in db.iterator_cf(cf, IteratorMode::From(start_key.as_bytes(), direction)) {
+        let (index_key, _) = match item {
+            Ok(v) => v,
             Err(err) => {
                 error!("RocksDB iterator error: {}", err);
+                continue;
             }
+        };
+        let index_key = String::from_utf8_lossy(&index_key);
+        let key = match index_key.strip_prefix(&prefix) {
+            Some(k) => k,
+            None => break,
+        };
+        if cursor == Some(key) {
+            continue;
+        }
+        let number = match key.split(':').next().and_then(|n| n.parse::<u64>().ok()) {
+            Some(n) => n,
+            None => continue,
+        };
+        if number < from || number > to {
+            break;
+        }
+        keys.push(key.to_string());
+        if keys.len() == limit {
+            break;
         }
     }
+    keys
+}
 
-    // Converting the HashMap to a Vec and sorting it by block number
-    let mut response_data: Vec<_> = response_data.into_iter().collect();
-    response_data.sort_by(|a, b| a.0.cmp(&b.0));
+fn page_limit(limit: Option<usize>) -> Result<usize, String> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+    if limit == 0 || limit > MAX_PAGE_LIMIT {
+        return Err(format!("limit must be between 1 and {}", MAX_PAGE_LIMIT));
+    }
+    Ok(limit)
+}
 
-    // Convert Vec of tuples into Vec of BlockTransactions
-    let response_data: Vec<_> = response_data
-        .into_iter()
-        .map(
-            |(block_number, (formatted_date, transactions))| BlockTransactions {
-                block_number,
-                formatted_date,
-                transactions,
-            },
-        )
+/// Reads one page of the claims `orchestrator` submitted, by event nonce
+fn claim_page(db: &DB, orchestrator: &str, query: &ClaimQuery) -> Result<ClaimPage, String> {
+    let limit = page_limit(query.limit)?;
+    let cursor = query.cursor.map(|nonce| claim_key(nonce, orchestrator));
+    let scan = NumberedScan {
+        value: orchestrator,
+        from: query.from_nonce.unwrap_or(0),
+        to: query.to_nonce.unwrap_or(u64::MAX),
+        order: query.order.unwrap_or_default(),
+        cursor: cursor.as_deref(),
+        limit,
+    };
+    let keys = scan_numbered_index(db, ORCHESTRATOR_INDEX, 20, &scan);
+    let claims: Vec<CustomClaim> = keys
+        .iter()
+        .filter_map(|key| {
+            let claim = load_claim(db, key);
+            if claim.is_none() {
+                error!("Index entry points at missing claim {}", key);
+            }
+            claim
+        })
         .collect();
+    let next_cursor = if keys.len() == limit {
+        claims.last().map(|c| c.event_nonce)
+    } else {
+        None
+    };
+    Ok(ClaimPage {
+        claims,
+        next_cursor,
+    })
+}
+
+/// Every orchestrator's claim for one Ethereum event, in the order they were submitted
+pub async fn get_event_claims(db: web::Data<Arc<DB>>, event_nonce: u64) -> impl Responder {
+    let claims = load_event_claims(&db, event_nonce);
+    if claims.is_empty() {
+        HttpResponse::NotFound().json("No claims found for this event")
+    } else {
+        HttpResponse::Ok().json(claims)
+    }
+}
 
-    HttpResponse::Ok().json(response_data)
+pub async fn get_orchestrator_claims(
+    db: web::Data<Arc<DB>>,
+    orchestrator: &str,
+    query: web::Query<ClaimQuery>,
+) -> impl Responder {
+    match claim_page(&db, orchestrator, &query) {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(e) => HttpResponse::BadRequest().json(e),
+    }
 }
 
-pub async fn get_send_to_eth_transaction_totals(db: web::Data<Arc<DB>>) -> impl Responder {
-    // Define the time frame duration in seconds
-    const ONE_DAY: u64 = 24 * 60 * 60;
-    const SEVEN_DAYS: u64 = 7 * ONE_DAY;
-    const THIRTY_DAYS: u64 = 30 * ONE_DAY;
-    const ONE_YEAR: u64 = 365 * ONE_DAY;
+#[derive(Serialize)]
+struct DepositPage {
+    deposits: Vec<DepositStatus>,
+    /// Pass as `cursor` to get the next page, None once the range is exhausted
+    next_cursor: Option<u64>,
+}
 
-    let mut amount_totals_1day: HashMap<String, u128> = HashMap::new();
-    let mut bridge_fee_totals_1day: HashMap<String, u128> = HashMap::new();
-    let mut chain_fee_totals_1day: HashMap<String, u128> = HashMap::new();
+/// The nonces of the deposits waiting to be forwarded over IBC, None until Gravity has been read
+fn pending_forwards() -> Option<Vec<u64>> {
+    get_gravity_info().map(|info| {
+        info.pending_ibc_auto_forwards
+            .iter()
+            .map(|f| f.event_nonce)
+            .collect()
+    })
+}
+
+/// Reads one page of the deposits in `index` for `address`, by event nonce
+fn deposit_page(
+    db: &DB,
+    index: &str,
+    address: &str,
+    query: &ClaimQuery,
+) -> Result<DepositPage, String> {
+    let limit = page_limit(query.limit)?;
+    let cursor = query.cursor.map(event_nonce_key);
+    let scan = NumberedScan {
+        value: address,
+        from: query.from_nonce.unwrap_or(0),
+        to: query.to_nonce.unwrap_or(u64::MAX),
+        order: query.order.unwrap_or_default(),
+        cursor: cursor.as_deref(),
+        limit,
+    };
+    let keys = scan_numbered_index(db, index, 20, &scan);
+    let finalized_eth_height = finalized_eth_height();
+    let pending_forwards = pending_forwards();
+    let deposits = keys
+        .iter()
+        .filter_map(|key| {
+            let nonce = key.parse().ok()?;
+            let deposit =
+                load_deposit_status(db, nonce, finalized_eth_height, pending_forwards.as_deref());
+            if deposit.is_none() {
+                error!("Index entry points at missing deposit {}", key);
+            }
+            deposit
+        })
+        .collect();
+    let next_cursor = if keys.len() == limit {
+        keys.last().and_then(|key| key.parse().ok())
+    } else {
+        None
+    };
+    Ok(DepositPage {
+        deposits,
+        next_cursor,
+    })
+}
 
-    let mut amount_totals_7days: HashMap<String, u128> = HashMap::new();
-    let mut bridge_fee_totals_7days: HashMap<String, u128> = HashMap::new();
-    let mut chain_fee_totals_7days: HashMap<String, u128> = HashMap::new();
+/// Where each deposit in the Ethereum tx with `tx_hash` is on its way to its destination
+pub async fn get_deposit(db: web::Data<Arc<DB>>, tx_hash: &str) -> impl Responder {
+    let pending_forwards = pending_forwards();
+    let deposits = load_tx_deposits(
+        &db,
+        tx_hash,
+        finalized_eth_height(),
+        pending_forwards.as_deref(),
+    );
+    if deposits.is_empty() {
+        HttpResponse::NotFound().json("No deposit found in this transaction")
+    } else {
+        HttpResponse::Ok().json(deposits)
+    }
+}
 
-    let mut amount_totals_30days: HashMap<String, u128> = HashMap::new();
-    let mut bridge_fee_totals_30days: HashMap<String, u128> = HashMap::new();
-    let mut chain_fee_totals_30days: HashMap<String, u128> = HashMap::new();
+pub async fn get_deposit_by_nonce(db: web::Data<Arc<DB>>, event_nonce: u64) -> impl Responder {
+    let pending_forwards = pending_forwards();
+    match load_deposit_status(
+        &db,
+        event_nonce,
+        finalized_eth_height(),
+        pending_forwards.as_deref(),
+    ) {
+        Some(deposit) => HttpResponse::Ok().json(deposit),
+        None => HttpResponse::NotFound().json("No deposit found with this event nonce"),
+    }
+}
 
-    let mut amount_totals_1year: HashMap<String, u128> = HashMap::new();
-    let mut bridge_fee_totals_1year: HashMap<String, u128> = HashMap::new();
-    let mut chain_fee_totals_1year: HashMap<String, u128> = HashMap::new();
+pub async fn get_deposits_by_sender(
+    db: web::Data<Arc<DB>>,
+    sender: &str,
+    query: web::Query<ClaimQuery>,
+) -> impl Responder {
+    match deposit_page(&db, DEPOSIT_SENDER_INDEX, sender, &query) {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(e) => HttpResponse::BadRequest().json(e),
+    }
+}
 
-    let mut amount_totals_alltime: HashMap<String, u128> = HashMap::new();
-    let mut bridge_fee_totals_alltime: HashMap<String, u128> = HashMap::new();
-    let mut chain_fee_totals_alltime: HashMap<String, u128> = HashMap::new();
+pub async fn get_deposits_by_destination(
+    db: web::Data<Arc<DB>>,
+    destination: &str,
+    query: web::Query<ClaimQuery>,
+) -> impl Responder {
+    match deposit_page(&db, DEPOSIT_DESTINATION_INDEX, destination, &query) {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(e) => HttpResponse::BadRequest().json(e),
+    }
+}
 
-    let iterator = db.iterator(rocksdb::IteratorMode::Start);
+#[derive(Serialize)]
+struct EthEventPage {
+    events: Vec<EthEvent>,
+    /// Pass as `cursor` to get the next page, None once the range is exhausted
+    next_cursor: Option<u64>,
+}
 
-    for item in iterator {
-        match item {
-            Ok((key, value)) => {
-                let key_str = String::from_utf8_lossy(&key);
-                let key_parts: Vec<&str> = key_str.split(':').collect();
-                if key_parts.len() == 4 && key_parts[1] == "msgSendToEth" {
-                    let msg_send_to_eth: CustomMsgSendToEth =
-                        serde_json::from_slice(&value).unwrap();
-                    let timestamp = key_parts[2].parse::<i64>().unwrap();
-
-                    let amount = msg_send_to_eth.amount.clone();
-                    let bridge_fee = msg_send_to_eth.bridge_fee.clone();
-                    let chain_fee = msg_send_to_eth.chain_fee.clone();
-
-                    amount_totals_alltime = process_fee(amount.clone(), &amount_totals_alltime);
-                    bridge_fee_totals_alltime =
-                        process_fee(bridge_fee.clone(), &bridge_fee_totals_alltime);
-                    chain_fee_totals_alltime =
-                        process_fee(chain_fee.clone(), &chain_fee_totals_alltime);
-
-                    // process data
-                    if timestamp
-                        >= (Utc::now() - chrono::Duration::seconds(ONE_DAY as i64)).timestamp()
-                    {
-                        // 1-day time frame
-                        amount_totals_1day = process_fee(amount.clone(), &amount_totals_1day);
-                        bridge_fee_totals_1day =
-                            process_fee(bridge_fee.clone(), &bridge_fee_totals_1day);
-                        chain_fee_totals_1day =
-                            process_fee(chain_fee.clone(), &chain_fee_totals_1day);
-                    }
-                    if timestamp
-                        >= (Utc::now() - chrono::Duration::seconds(SEVEN_DAYS as i64)).timestamp()
-                    {
-                        // 7-day time frame
-                        amount_totals_7days = process_fee(amount.clone(), &amount_totals_7days);
-                        bridge_fee_totals_7days =
-                            process_fee(bridge_fee.clone(), &bridge_fee_totals_7days);
-                        chain_fee_totals_7days =
-                            process_fee(chain_fee.clone(), &chain_fee_totals_7days);
-                    }
-                    if timestamp
-                        >= (Utc::now() - chrono::Duration::seconds(THIRTY_DAYS as i64)).timestamp()
-                    {
-                        // 30-day time frame
-                        amount_totals_30days = process_fee(amount.clone(), &amount_totals_30days);
-                        bridge_fee_totals_30days =
-                            process_fee(bridge_fee.clone(), &bridge_fee_totals_30days);
-                        chain_fee_totals_30days =
-                            process_fee(chain_fee.clone(), &chain_fee_totals_30days);
-                    }
-                    if timestamp
-                        >= (Utc::now() - chrono::Duration::seconds(ONE_YEAR as i64)).timestamp()
-                    {
-                        // 1-year time frame
-                        amount_totals_1year = process_fee(amount.clone(), &amount_totals_1year);
-                        bridge_fee_totals_1year =
-                            process_fee(bridge_fee.clone(), &bridge_fee_totals_1year);
-                        chain_fee_totals_1year =
-                            process_fee(chain_fee.clone(), &chain_fee_totals_1year);
-                    }
-                }
+/// Reads one page of the stored Ethereum events of `event_type`, by event nonce
+fn eth_event_page(db: &DB, event_type: &str, query: &ClaimQuery) -> Result<EthEventPage, String> {
+    let cf = eth_event_cf_by_name(event_type)
+        .ok_or_else(|| format!("Unknown event type {}", event_type))?;
+    let limit = page_limit(query.limit)?;
+    let order = query.order.unwrap_or_default();
+    let mut from = query.from_nonce.unwrap_or(0);
+    let mut to = query.to_nonce.unwrap_or(u64::MAX);
+    match (query.cursor, order) {
+        (Some(cursor), Order::Asc) => from = from.max(cursor.saturating_add(1)),
+        (Some(0), Order::Desc) => {
+            return Ok(EthEventPage {
+                events: Vec::new(),
+                next_cursor: None,
+            })
+        }
+        (Some(cursor), Order::Desc) => to = to.min(cursor - 1),
+        (None, _) => {}
+    }
+    if from > to {
+        return Ok(EthEventPage {
+            events: Vec::new(),
+            next_cursor: None,
+        });
+    }
+    let events = load_eth_events(db, cf, from, to, order == Order::Desc, limit);
+    let next_cursor = if events.len() == limit {
+        events.last().map(|e| e.event_nonce)
+    } else {
+        None
+    };
+    Ok(EthEventPage {
+        events,
+        next_cursor,
+    })
+}
+
+/// Every event of `event_type` the Gravity contract emitted in a finalized block, one of
+/// send_to_cosmos, batch_send_to_eth, valset_updated, erc20_deployed or logic_call_executed
+pub async fn get_eth_events(
+    db: web::Data<Arc<DB>>,
+    event_type: &str,
+    query: web::Query<ClaimQuery>,
+) -> impl Responder {
+    match eth_event_page(&db, event_type, &query) {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(e) => HttpResponse::BadRequest().json(e),
+    }
+}
+
+/// Every event of the blocks after the last synced one, these can still be rolled back by a reorg
+pub async fn get_unconfirmed_eth_events(db: web::Data<Arc<DB>>) -> impl Responder {
+    HttpResponse::Ok().json(load_unconfirmed_eth_events(&db))
+}
+
+#[derive(Serialize)]
+struct EthEventSyncStatus {
+    deploy_block: u64,
+    /// Every event up to and including this Ethereum block is stored, None before the first chunk
+    synced_to: Option<u64>,
+    /// Number of events in the blocks after `synced_to`
+    unconfirmed: usize,
+    /// Number of reorgs that rolled back unconfirmed events
+    reorgs: u64,
+    /// Unix time in seconds of the last of those reorgs
+    last_reorg_at: Option<i64>,
+}
+
+pub async fn get_eth_event_status(db: web::Data<Arc<DB>>) -> impl Responder {
+    let (reorgs, last_reorg_at) = load_eth_reorgs(&db);
+    HttpResponse::Ok().json(EthEventSyncStatus {
+        deploy_block: get_config().ethereum.deploy_block,
+        synced_to: load_eth_events_synced_to(&db),
+        unconfirmed: load_unconfirmed_eth_events(&db).len(),
+        reorgs,
+        last_reorg_at,
+    })
+}
+
+/// Query parameters accepted by the orchestrator confirms endpoint, heights are inclusive
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ConfirmQuery {
+    /// The `next_cursor` of the previous page, results continue after this key
+    pub cursor: Option<String>,
+    pub from_height: Option<u64>,
+    pub to_height: Option<u64>,
+    pub order: Option<Order>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct ConfirmPage {
+    confirms: Vec<CustomConfirm>,
+    /// Pass as `cursor` to get the next page, None once the range is exhausted
+    next_cursor: Option<String>,
+}
+
+/// Reads one page of the confirms `orchestrator` submitted, by height
+fn confirm_page(db: &DB, orchestrator: &str, query: &ConfirmQuery) -> Result<ConfirmPage, String> {
+    let limit = page_limit(query.limit)?;
+    let scan = NumberedScan {
+        value: orchestrator,
+        from: query.from_height.unwrap_or(0),
+        to: query.to_height.unwrap_or(u64::MAX),
+        order: query.order.unwrap_or_default(),
+        cursor: query.cursor.as_deref(),
+        limit,
+    };
+    let keys = scan_numbered_index(db, CONFIRM_ORCHESTRATOR_INDEX, 12, &scan);
+    let next_cursor = if keys.len() == limit {
+        keys.last().cloned()
+    } else {
+        None
+    };
+    let confirms = keys
+        .iter()
+        .filter_map(|key| {
+            let confirm = load_confirm(db, key);
+            if confirm.is_none() {
+                error!("Index entry points at missing confirm {}", key);
             }
-            Err(err) => {
-                error!("RocksDB iterator error: {}", err);
+            confirm
+        })
+        .collect();
+    Ok(ConfirmPage {
+        confirms,
+        next_cursor,
+    })
+}
+
+pub async fn get_orchestrator_confirms(
+    db: web::Data<Arc<DB>>,
+    orchestrator: &str,
+    query: web::Query<ConfirmQuery>,
+) -> impl Responder {
+    match confirm_page(&db, orchestrator, &query) {
+        Ok(page) => HttpResponse::Ok().json(page),
+        Err(e) => HttpResponse::BadRequest().json(e),
+    }
+}
+
+/// The `signed_*_window` params, in Gravity blocks. A validator that has not signed a batch,
+/// valset or logic call by the time it is older than its window is slashed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+struct SigningWindows {
+    batches: u64,
+    valsets: u64,
+    logic_calls: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+struct SigningStats {
+    /// Items of this type signed by any validator within the window
+    requested: u64,
+    signed: u64,
+    /// Items this validator has not signed, it is slashed for any still unsigned when they
+    /// leave the window
+    missed: u64,
+    /// Blocks from the first signature any validator submitted for an item to this validator's
+    average_latency_blocks: Option<f64>,
+    max_latency_blocks: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ValidatorSigning {
+    orchestrator: String,
+    batches: SigningStats,
+    valsets: SigningStats,
+    logic_calls: SigningStats,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SigningReport {
+    latest_block: u64,
+    windows: SigningWindows,
+    /// Validators with the most missed confirms first
+    validators: Vec<ValidatorSigning>,
+}
+
+lazy_static! {
+    /// The last report built, it only changes once new blocks are indexed or the windows change
+    static ref SIGNING_REPORT: Mutex<Option<SigningReport>> = Mutex::new(None);
+}
+
+/// The first height each item was signed at and the height each orchestrator signed it at
+type SignedItems = HashMap<String, (u64, HashMap<String, u64>)>;
+
+fn signing_stats(items: &SignedItems, orchestrator: &str) -> SigningStats {
+    let mut stats = SigningStats {
+        requested: items.len() as u64,
+        ..Default::default()
+    };
+    let mut total_latency = 0;
+    for (first_height, signers) in items.values() {
+        if let Some(height) = signers.get(orchestrator) {
+            let latency = height - first_height;
+            stats.signed += 1;
+            total_latency += latency;
+            stats.max_latency_blocks = Some(stats.max_latency_blocks.unwrap_or(0).max(latency));
+        }
+    }
+    stats.missed = stats.requested - stats.signed;
+    if stats.signed > 0 {
+        stats.average_latency_blocks = Some(total_latency as f64 / stats.signed as f64);
+    }
+    stats
+}
+
+/// Reports how promptly each validator that signed anything within the windows signed the
+/// batches, valsets and logic calls requested in them. The indexer does not see when items
+/// are created, so latency is measured from the first signature any validator submitted.
+fn signing_report(db: &DB, latest_block: u64, windows: SigningWindows) -> SigningReport {
+    let longest = windows
+        .batches
+        .max(windows.valsets)
+        .max(windows.logic_calls);
+    let mut batches = SignedItems::new();
+    let mut valsets = SignedItems::new();
+    let mut logic_calls = SignedItems::new();
+    let mut orchestrators = HashSet::new();
+    for confirm in load_confirms_since(db, latest_block.saturating_sub(longest)) {
+        let (items, window) = match confirm.details {
+            ConfirmDetails::Batch { .. } => (&mut batches, windows.batches),
+            ConfirmDetails::Valset { .. } => (&mut valsets, windows.valsets),
+            ConfirmDetails::LogicCall { .. } => (&mut logic_calls, windows.logic_calls),
+        };
+        if confirm.block_number < latest_block.saturating_sub(window) {
+            continue;
+        }
+        let orchestrator = confirm.orchestrator.to_lowercase();
+        // confirms are read in block order so the first one seen is the earliest
+        let (_, signers) = items
+            .entry(confirm.details.item_id())
+            .or_insert((confirm.block_number, HashMap::new()));
+        signers
+            .entry(orchestrator.clone())
+            .or_insert(confirm.block_number);
+        orchestrators.insert(orchestrator);
+    }
+
+    let mut validators: Vec<ValidatorSigning> = orchestrators
+        .into_iter()
+        .map(|orchestrator| ValidatorSigning {
+            batches: signing_stats(&batches, &orchestrator),
+            valsets: signing_stats(&valsets, &orchestrator),
+            logic_calls: signing_stats(&logic_calls, &orchestrator),
+            orchestrator,
+        })
+        .collect();
+    validators.sort_by(|a, b| {
+        let missed =
+            |v: &ValidatorSigning| v.batches.missed + v.valsets.missed + v.logic_calls.missed;
+        missed(b)
+            .cmp(&missed(a))
+            .then_with(|| a.orchestrator.cmp(&b.orchestrator))
+    });
+    SigningReport {
+        latest_block,
+        windows,
+        validators,
+    }
+}
+
+pub async fn get_signing_report(db: web::Data<Arc<DB>>) -> impl Responder {
+    let params = match get_gravity_info() {
+        Some(info) => info.params,
+        None => {
+            return HttpResponse::InternalServerError()
+                .json("Info not yet generated, please query in 5 minutes")
+        }
+    };
+    let latest_block = match load_last_download_block(&db) {
+        Some(block) => block,
+        None => {
+            return HttpResponse::InternalServerError()
+                .json("Transactions not yet downloaded, please query later")
+        }
+    };
+    let windows = SigningWindows {
+        batches: params.signed_batches_window,
+        valsets: params.signed_valsets_window,
+        logic_calls: params.signed_logic_calls_window,
+    };
+    // the lock is held while the report is built so concurrent requests read the confirms once
+    let mut cached = SIGNING_REPORT.lock().unwrap();
+    match cached.as_ref() {
+        Some(report) if report.latest_block == latest_block && report.windows == windows => {
+            HttpResponse::Ok().json(report)
+        }
+        _ => {
+            let report = signing_report(&db, latest_block, windows);
+            let response = HttpResponse::Ok().json(&report);
+            *cached = Some(report);
+            response
+        }
+    }
+}
+
+impl TimeFrame {
+    fn new(period: String, from: Option<String>, to: String) -> Self {
+        TimeFrame {
+            period,
+            from,
+            to,
+            amount_totals: HashMap::new(),
+            bridge_fee_totals: HashMap::new(),
+            chain_fee_totals: HashMap::new(),
+            send_counts: HashMap::new(),
+            cancelled_amount_totals: HashMap::new(),
+            cancelled_bridge_fee_totals: HashMap::new(),
+            cancelled_send_counts: HashMap::new(),
+        }
+    }
+
+    fn contains(&self, date: &str) -> bool {
+        let after_from = match &self.from {
+            Some(from) => from.as_str() <= date,
+            None => true,
+        };
+        after_from && date <= self.to.as_str()
+    }
+
+    fn add(&mut self, entry: &RollupEntry) {
+        let denom = &entry.denom;
+        let rollup = &entry.rollup;
+        let mut add = |totals: &mut HashMap<String, u128>, amount: u128| {
+            if amount > 0 {
+                *totals.entry(denom.clone()).or_default() += amount;
+            }
+        };
+        add(&mut self.amount_totals, rollup.amount);
+        add(&mut self.bridge_fee_totals, rollup.bridge_fee);
+        add(&mut self.chain_fee_totals, rollup.chain_fee);
+        add(&mut self.cancelled_amount_totals, rollup.cancelled_amount);
+        add(
+            &mut self.cancelled_bridge_fee_totals,
+            rollup.cancelled_bridge_fee,
+        );
+        let mut count = |counts: &mut HashMap<String, u64>, count: u64| {
+            if count > 0 {
+                *counts.entry(denom.clone()).or_default() += count;
             }
+        };
+        count(&mut self.send_counts, rollup.count);
+        count(&mut self.cancelled_send_counts, rollup.cancelled_count);
+    }
+}
+
+fn format_day(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+fn parse_day(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date {}, expected YYYY-MM-DD", date))
+}
+
+fn today(now: i64) -> NaiveDate {
+    DateTime::from_timestamp(now, 0).unwrap().date_naive()
+}
+
+/// Send to eth totals over the last day, week, month, year and all time, in whole UTC days
+/// ending today
+fn send_to_eth_totals(db: &DB, now: i64, include_failed: bool) -> TimeFrameData {
+    // period name and how many days back it reaches, None for all time
+    const PERIODS: [(&str, Option<u64>); 5] = [
+        ("1 day", Some(1)),
+        ("7 days", Some(7)),
+        ("30 days", Some(30)),
+        ("1 year", Some(365)),
+        ("All time", None),
+    ];
+    let today = today(now);
+    let mut time_frames: Vec<TimeFrame> = PERIODS
+        .iter()
+        .map(|(period, days)| {
+            TimeFrame::new(
+                period.to_string(),
+                days.map(|d| format_day(today - Days::new(d - 1))),
+                format_day(today),
+            )
+        })
+        .collect();
+    for entry in load_rollups(db, None, &format_day(today)) {
+        if !include_failed && !entry.succeeded {
+            continue;
+        }
+        for time_frame in time_frames.iter_mut().filter(|t| t.contains(&entry.date)) {
+            time_frame.add(&entry);
         }
     }
+    TimeFrameData { time_frames }
+}
 
-    let response_data = TimeFrameData {
-        time_frames: vec![
-            TimeFrame {
-                period: "1 day".to_string(),
-                amount_totals: amount_totals_1day,
-                bridge_fee_totals: bridge_fee_totals_1day,
-                chain_fee_totals: chain_fee_totals_1day,
-            },
-            TimeFrame {
-                period: "7 days".to_string(),
-                amount_totals: amount_totals_7days,
-                bridge_fee_totals: bridge_fee_totals_7days,
-                chain_fee_totals: chain_fee_totals_7days,
-            },
-            TimeFrame {
-                period: "30 days".to_string(),
-                amount_totals: amount_totals_30days,
-                bridge_fee_totals: bridge_fee_totals_30days,
-                chain_fee_totals: chain_fee_totals_30days,
-            },
-            TimeFrame {
-                period: "1 year".to_string(),
-                amount_totals: amount_totals_1year,
-                bridge_fee_totals: bridge_fee_totals_1year,
-                chain_fee_totals: chain_fee_totals_1year,
+/// The first and last day of each bucket from `from` to `to`, the first and last buckets are
+/// cut short if the range does not start or end on a bucket boundary
+fn bucket_ranges(from: NaiveDate, to: NaiveDate, bucket: Bucket) -> Vec<(NaiveDate, NaiveDate)> {
+    let mut ranges = Vec::new();
+    let mut start = from;
+    while start <= to {
+        let next = match bucket {
+            Bucket::Day => start + Days::new(1),
+            Bucket::Week => start + Days::new(7 - start.weekday().num_days_from_monday() as u64),
+            Bucket::Month => start.with_day(1).unwrap() + Months::new(1),
+            Bucket::Year => start.with_ordinal(1).unwrap() + Months::new(12),
+            Bucket::All => to + Days::new(1),
+        };
+        ranges.push((start, (next - Days::new(1)).min(to)));
+        start = next;
+    }
+    ranges
+}
+
+/// Send to eth totals from `query.from` to `query.to` split into buckets of `query.bucket`
+fn send_to_eth_buckets(db: &DB, query: &TotalsQuery, now: i64) -> Result<TimeFrameData, String> {
+    let to = match &query.to {
+        Some(to) => parse_day(to)?,
+        None => today(now),
+    };
+    let from = match &query.from {
+        Some(from) => parse_day(from)?,
+        None => match first_rollup_date(db) {
+            Some(first) => parse_day(&first)?.min(to),
+            None => to,
+        },
+    };
+    if from > to {
+        return Err("from must not be after to".to_string());
+    }
+    let bucket = query.bucket.unwrap_or_default();
+    let ranges = bucket_ranges(from, to, bucket);
+    if ranges.len() > MAX_BUCKETS {
+        return Err(format!(
+            "More than {} buckets, use a larger bucket size or a shorter range",
+            MAX_BUCKETS
+        ));
+    }
+    let mut time_frames: Vec<TimeFrame> = ranges
+        .into_iter()
+        .map(|(start, end)| {
+            TimeFrame::new(format_day(start), Some(format_day(start)), format_day(end))
+        })
+        .collect();
+    let include_failed = query.include_failed.unwrap_or(false);
+    for entry in load_rollups(db, Some(&format_day(from)), &format_day(to)) {
+        if !include_failed && !entry.succeeded {
+            continue;
+        }
+        // the buckets are sorted and don't overlap
+        let i = time_frames.partition_point(|t| t.from.as_deref() <= Some(entry.date.as_str()));
+        if let Some(time_frame) = i.checked_sub(1).map(|i| &mut time_frames[i]) {
+            time_frame.add(&entry);
+        }
+    }
+    Ok(TimeFrameData { time_frames })
+}
+
+pub async fn get_send_to_eth_transaction_totals(
+    db: web::Data<Arc<DB>>,
+    query: web::Query<TotalsQuery>,
+) -> impl Responder {
+    let now = Utc::now().timestamp();
+    if query.from.is_none() && query.to.is_none() && query.bucket.is_none() {
+        return HttpResponse::Ok().json(send_to_eth_totals(
+            &db,
+            now,
+            query.include_failed.unwrap_or(false),
+        ));
+    }
+    match send_to_eth_buckets(&db, &query, now) {
+        Ok(totals) => HttpResponse::Ok().json(totals),
+        Err(e) => HttpResponse::BadRequest().json(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transactions::database::{
+        delete_msg, save_claim, save_confirm, save_msg_cancel_send_to_eth, save_msg_ibc_transfer,
+        save_msg_send_to_eth, save_tx_status, ClaimDetails, CustomMsgCancelSendToEth,
+        CustomMsgSendToEth, CustomMsgTransfer,
+    };
+    use crate::upstream::fake::TestDb;
+
+    fn put_send(db: &DB, height: u64, hash: &str, sender: &str) {
+        let key = format!(
+            "{:012}:msgSendToEth:{}:0:{}",
+            height,
+            1_700_000_000 + height,
+            hash
+        );
+        let msg: CustomMsgSendToEth = serde_json::from_value(serde_json::json!({
+            "sender": sender,
+            "eth_dest": "0xB0B",
+            "amount": [{"denom": "ugraviton", "amount": "100"}],
+            "bridge_fee": [],
+            "chain_fee": []
+        }))
+        .unwrap();
+        save_msg_send_to_eth(db, &key, &msg);
+    }
+
+    fn failed_status() -> TxStatus {
+        TxStatus {
+            code: 5,
+            log: "insufficient funds".to_string(),
+            gas_wanted: 200_000,
+            gas_used: 65_000,
+            fee: Vec::new(),
+        }
+    }
+
+    fn heights(page: &TransactionPage) -> Vec<u64> {
+        page.blocks
+            .iter()
+            .flat_map(|b| b.transactions.iter().map(move |_| b.block_number))
+            .collect()
+    }
+
+    #[test]
+    fn test_transaction_page() {
+        let db = TestDb::open("transaction_page");
+        for (height, hash) in [(10, "A"), (20, "B"), (20, "C"), (30, "D"), (40, "E")] {
+            put_send(&db, height, hash, "gravity1sender");
+        }
+
+        let page = transaction_page(&db, "msgSendToEth", &TxQuery::default()).unwrap();
+        assert_eq!(heights(&page), vec![10, 20, 20, 30, 40]);
+        assert_eq!(page.blocks.len(), 4);
+        assert!(page.next_cursor.is_none());
+
+        // walk the range two messages at a time
+        let mut query = TxQuery {
+            limit: Some(2),
+            ..Default::default()
+        };
+        let page = transaction_page(&db, "msgSendToEth", &query).unwrap();
+        assert_eq!(heights(&page), vec![10, 20]);
+        query.cursor = page.next_cursor;
+        let page = transaction_page(&db, "msgSendToEth", &query).unwrap();
+        assert_eq!(heights(&page), vec![20, 30]);
+
+        let query = TxQuery {
+            from_height: Some(15),
+            to_height: Some(30),
+            order: Some(Order::Desc),
+            ..Default::default()
+        };
+        let page = transaction_page(&db, "msgSendToEth", &query).unwrap();
+        assert_eq!(heights(&page), vec![30, 20, 20]);
+
+        let query = TxQuery {
+            to_time: Some(1_700_000_020),
+            order: Some(Order::Desc),
+            ..Default::default()
+        };
+        let page = transaction_page(&db, "msgSendToEth", &query).unwrap();
+        assert_eq!(heights(&page), vec![20, 20, 10]);
+
+        let query = TxQuery {
+            cursor: Some("000000000020:msgIbcTransfer:1700000020:0:IBC".to_string()),
+            ..Default::default()
+        };
+        assert!(transaction_page(&db, "msgSendToEth", &query).is_err());
+
+        // failed txs are left out unless asked for
+        save_tx_status(&db, "E", &failed_status());
+        let page = transaction_page(&db, "msgSendToEth", &TxQuery::default()).unwrap();
+        assert_eq!(heights(&page), vec![10, 20, 20, 30]);
+        let query = TxQuery {
+            include_failed: Some(true),
+            ..Default::default()
+        };
+        let page = transaction_page(&db, "msgSendToEth", &query).unwrap();
+        assert_eq!(heights(&page), vec![10, 20, 20, 30, 40]);
+        let failed = &page.blocks[3].transactions[0];
+        assert_eq!(failed.status.as_ref().unwrap().code, 5);
+    }
+
+    #[test]
+    fn test_indexed_page() {
+        let db = TestDb::open("indexed_page");
+        put_send(&db, 10, "A", "gravity1alice");
+        put_send(&db, 30, "C", "gravity1bob");
+        let transfer: CustomMsgTransfer = serde_json::from_value(serde_json::json!({
+            "source_port": "transfer",
+            "source_channel": "channel-10",
+            "token": [{"denom": "ugraviton", "amount": "5"}],
+            "sender": "gravity1bob",
+            "receiver": "osmo1alice",
+            "timeout_height": null,
+            "timeout_timestamp": 0
+        }))
+        .unwrap();
+        save_msg_ibc_transfer(&db, "000000000020:msgIbcTransfer:1700000020:0:B", &transfer);
+
+        let address = |addr: &str, query: &TxQuery| {
+            indexed_page(
+                &db,
+                &[SENDER_INDEX, RECEIVER_INDEX],
+                &addr.to_lowercase(),
+                query,
+            )
+            .unwrap()
+        };
+        assert_eq!(
+            heights(&address("gravity1bob", &TxQuery::default())),
+            vec![20, 30]
+        );
+        // Ethereum destinations match regardless of checksum case
+        assert_eq!(
+            heights(&address("0xb0b", &TxQuery::default())),
+            vec![10, 30]
+        );
+        assert_eq!(
+            heights(&address("osmo1alice", &TxQuery::default())),
+            vec![20]
+        );
+        let query = TxQuery {
+            order: Some(Order::Desc),
+            limit: Some(1),
+            ..Default::default()
+        };
+        let page = address("gravity1bob", &query);
+        assert_eq!(heights(&page), vec![30]);
+        let page = address(
+            "gravity1bob",
+            &TxQuery {
+                cursor: page.next_cursor,
+                ..query
             },
-            TimeFrame {
-                period: "All time".to_string(),
-                amount_totals: amount_totals_alltime,
-                bridge_fee_totals: bridge_fee_totals_alltime,
-                chain_fee_totals: chain_fee_totals_alltime,
+        );
+        assert_eq!(heights(&page), vec![20]);
+        assert_eq!(
+            page.blocks[0].transactions[0].msg_type.as_deref(),
+            Some("msgIbcTransfer")
+        );
+
+        let page = indexed_page(&db, &[DENOM_INDEX], "ugraviton", &TxQuery::default()).unwrap();
+        assert_eq!(heights(&page), vec![10, 20, 30]);
+        let page = indexed_page(&db, &[CHANNEL_INDEX], "channel-1", &TxQuery::default()).unwrap();
+        assert!(page.blocks.is_empty());
+        let page = indexed_page(&db, &[CHANNEL_INDEX], "channel-10", &TxQuery::default()).unwrap();
+        assert_eq!(heights(&page), vec![20]);
+
+        // hashes are matched case insensitively, malformed index entries are skipped
+        let tx_hash_index = db.cf_handle(TX_HASH_INDEX).unwrap();
+        db.put_cf(tx_hash_index, b"B:malformed", b"").unwrap();
+        let details = transaction_details(&db, "b").unwrap();
+        assert_eq!(details.block_number, 20);
+        assert_eq!(details.msg_types, vec!["msgIbcTransfer"]);
+        assert_eq!(details.messages[0].data["receiver"], "osmo1alice");
+        assert!(transaction_details(&db, "F").is_none());
+    }
+
+    #[test]
+    fn test_totals_separate_cancelled_sends() {
+        let db = TestDb::open("totals_cancelled");
+        let send = |tx_id: u64| -> CustomMsgSendToEth {
+            serde_json::from_value(serde_json::json!({
+                "sender": "gravity1alice",
+                "eth_dest": "0xb0b",
+                "amount": [{"denom": "ugraviton", "amount": "100"}],
+                "bridge_fee": [{"denom": "ugraviton", "amount": "10"}],
+                "chain_fee": [{"denom": "ugraviton", "amount": "1"}],
+                "tx_id": tx_id
+            }))
+            .unwrap()
+        };
+        save_msg_send_to_eth(&db, "000000000010:msgSendToEth:1700000010:0:A", &send(1));
+        save_msg_send_to_eth(&db, "000000000020:msgSendToEth:1700000020:0:B", &send(2));
+        let cancel: CustomMsgCancelSendToEth = serde_json::from_value(serde_json::json!({
+            "transaction_id": 2,
+            "sender": "gravity1alice"
+        }))
+        .unwrap();
+        save_msg_cancel_send_to_eth(
+            &db,
+            "000000000030:msgCancelSendToEth:1700000030:0:C",
+            &cancel,
+        );
+
+        save_msg_send_to_eth(&db, "000000000040:msgSendToEth:1700000040:0:D", &send(3));
+        save_tx_status(&db, "D", &failed_status());
+
+        let totals = send_to_eth_totals(&db, 1_700_000_100, false);
+        let all_time = totals.time_frames.last().unwrap();
+        assert_eq!(all_time.amount_totals["ugraviton"], 100);
+        assert_eq!(all_time.bridge_fee_totals["ugraviton"], 10);
+        assert_eq!(all_time.chain_fee_totals["ugraviton"], 2);
+        assert_eq!(all_time.cancelled_amount_totals["ugraviton"], 100);
+        assert_eq!(all_time.cancelled_bridge_fee_totals["ugraviton"], 10);
+        let details = transaction_details(&db, "B").unwrap();
+        assert_eq!(details.messages[0].data["cancelled_by"], "C");
+
+        let totals = send_to_eth_totals(&db, 1_700_000_100, true);
+        let all_time = totals.time_frames.last().unwrap();
+        assert_eq!(all_time.amount_totals["ugraviton"], 200);
+    }
+
+    #[test]
+    fn test_totals_buckets() {
+        let db = TestDb::open("totals_buckets");
+        const DAY: i64 = 24 * 60 * 60;
+        // 2023-11-14
+        const START: i64 = 1_699_920_000;
+        let send = |amount: u64| -> CustomMsgSendToEth {
+            serde_json::from_value(serde_json::json!({
+                "sender": "gravity1alice",
+                "eth_dest": "0xb0b",
+                "amount": [{"denom": "ugraviton", "amount": amount.to_string()}],
+                "bridge_fee": [{"denom": "ugraviton", "amount": "10"}],
+                "chain_fee": [{"denom": "ibc/usdc", "amount": "1"}]
+            }))
+            .unwrap()
+        };
+        let key = |day: i64, hash: &str| {
+            format!(
+                "{:012}:msgSendToEth:{}:0:{}",
+                100 + day,
+                START + day * DAY + 60,
+                hash
+            )
+        };
+        save_msg_send_to_eth(&db, &key(0, "A"), &send(100));
+        save_msg_send_to_eth(&db, &key(0, "B"), &send(200));
+        save_msg_send_to_eth(&db, &key(6, "C"), &send(300));
+        save_msg_send_to_eth(&db, &key(20, "D"), &send(400));
+        // downloading a block again doesn't count its sends twice
+        save_msg_send_to_eth(&db, &key(20, "D"), &send(400));
+
+        let query = |from: &str, to: &str, bucket: Bucket| TotalsQuery {
+            include_failed: None,
+            from: Some(from.to_string()),
+            to: Some(to.to_string()),
+            bucket: Some(bucket),
+        };
+        let now = START + 30 * DAY;
+        let totals =
+            send_to_eth_buckets(&db, &query("2023-11-14", "2023-12-13", Bucket::Week), now)
+                .unwrap();
+        let weeks: Vec<(&str, &str, u128)> = totals
+            .time_frames
+            .iter()
+            .map(|t| {
+                (
+                    t.from.as_deref().unwrap(),
+                    t.to.as_str(),
+                    t.amount_totals
+                        .get("ugraviton")
+                        .copied()
+                        .unwrap_or_default(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            weeks,
+            vec![
+                ("2023-11-14", "2023-11-19", 300),
+                ("2023-11-20", "2023-11-26", 300),
+                ("2023-11-27", "2023-12-03", 0),
+                ("2023-12-04", "2023-12-10", 400),
+                ("2023-12-11", "2023-12-13", 0),
+            ]
+        );
+        let first = &totals.time_frames[0];
+        assert_eq!(first.send_counts["ugraviton"], 2);
+        assert_eq!(first.bridge_fee_totals["ugraviton"], 20);
+        assert_eq!(first.chain_fee_totals["ibc/usdc"], 2);
+
+        let totals =
+            send_to_eth_buckets(&db, &query("2023-11-01", "2023-12-31", Bucket::Month), now)
+                .unwrap();
+        assert_eq!(totals.time_frames.len(), 2);
+        assert_eq!(totals.time_frames[0].amount_totals["ugraviton"], 600);
+        assert_eq!(totals.time_frames[1].to, "2023-12-31");
+
+        // the whole indexed history by default
+        let totals = send_to_eth_buckets(&db, &TotalsQuery::default(), now).unwrap();
+        assert_eq!(totals.time_frames.len(), 1);
+        assert_eq!(totals.time_frames[0].from.as_deref(), Some("2023-11-14"));
+        assert_eq!(totals.time_frames[0].amount_totals["ugraviton"], 1000);
+
+        // the fixed periods end today
+        let totals = send_to_eth_totals(&db, START + 20 * DAY + 120, false);
+        assert_eq!(totals.time_frames[0].amount_totals["ugraviton"], 400);
+        assert_eq!(totals.time_frames[1].amount_totals["ugraviton"], 400);
+        assert_eq!(totals.time_frames[2].amount_totals["ugraviton"], 1000);
+
+        // deleted sends leave the rollups
+        delete_msg(&db, &key(0, "A"));
+        let totals = send_to_eth_totals(&db, now, false);
+        assert_eq!(totals.time_frames[4].amount_totals["ugraviton"], 900);
+        assert_eq!(totals.time_frames[4].send_counts["ugraviton"], 3);
+
+        assert!(
+            send_to_eth_buckets(&db, &query("2023-12-01", "2023-11-01", Bucket::Day), now).is_err()
+        );
+        assert!(
+            send_to_eth_buckets(&db, &query("2020-01-01", "2030-01-01", Bucket::Day), now).is_err()
+        );
+        assert!(
+            send_to_eth_buckets(&db, &query("14-11-2023", "2023-12-01", Bucket::Day), now).is_err()
+        );
+    }
+
+    #[test]
+    fn test_claim_page() {
+        let db = TestDb::open("claim_page");
+        let claim = |orchestrator: &str, event_nonce: u64| CustomClaim {
+            orchestrator: orchestrator.to_string(),
+            event_nonce,
+            eth_block_height: 15_000_000 + event_nonce,
+            block_number: 100 + event_nonce,
+            timestamp: 1_700_000_000,
+            tx_hash: format!("TX{}", event_nonce),
+            details: ClaimDetails::BatchSendToEth {
+                batch_nonce: event_nonce,
+                token_contract: "0xa0b8".to_string(),
             },
-        ],
-    };
+        };
+        for nonce in [1, 2, 3, 4, 10] {
+            save_claim(&db, &claim("gravity1alice", nonce));
+        }
+        save_claim(&db, &claim("gravity1bob", 2));
+        let nonces =
+            |page: &ClaimPage| -> Vec<u64> { page.claims.iter().map(|c| c.event_nonce).collect() };
+
+        let page = claim_page(&db, "gravity1alice", &ClaimQuery::default()).unwrap();
+        assert_eq!(nonces(&page), vec![1, 2, 3, 4, 10]);
+        assert!(page.next_cursor.is_none());
+
+        let mut query = ClaimQuery {
+            limit: Some(2),
+            order: Some(Order::Desc),
+            ..Default::default()
+        };
+        let page = claim_page(&db, "gravity1alice", &query).unwrap();
+        assert_eq!(nonces(&page), vec![10, 4]);
+        query.cursor = page.next_cursor;
+        let page = claim_page(&db, "gravity1alice", &query).unwrap();
+        assert_eq!(nonces(&page), vec![3, 2]);
 
-    HttpResponse::Ok().json(response_data)
+        let query = ClaimQuery {
+            from_nonce: Some(2),
+            to_nonce: Some(4),
+            ..Default::default()
+        };
+        let page = claim_page(&db, "GRAVITY1ALICE", &query).unwrap();
+        assert_eq!(nonces(&page), vec![2, 3, 4]);
+
+        let event = load_event_claims(&db, 2);
+        assert_eq!(event.len(), 2);
+        assert!(load_event_claims(&db, 5).is_empty());
+    }
+
+    #[test]
+    fn test_signing_report() {
+        let db = TestDb::open("signing_report");
+        let confirm = |orchestrator: &str, height: u64, details: ConfirmDetails| CustomConfirm {
+            orchestrator: orchestrator.to_string(),
+            eth_signer: "0xsigner".to_string(),
+            block_number: height,
+            timestamp: 1_700_000_000 + height as i64,
+            tx_hash: format!("TX{}", height),
+            details,
+        };
+        let batch = |nonce: u64| ConfirmDetails::Batch {
+            nonce,
+            token_contract: "0xA0b8".to_string(),
+        };
+        // outside the batch window
+        save_confirm(&db, &confirm("gravity1bob", 10, batch(1)));
+        save_confirm(&db, &confirm("gravity1alice", 100, batch(2)));
+        save_confirm(&db, &confirm("gravity1bob", 104, batch(2)));
+        save_confirm(&db, &confirm("gravity1alice", 110, batch(3)));
+        save_confirm(
+            &db,
+            &confirm("gravity1alice", 120, ConfirmDetails::Valset { nonce: 7 }),
+        );
+        save_confirm(
+            &db,
+            &confirm("gravity1bob", 130, ConfirmDetails::Valset { nonce: 7 }),
+        );
+
+        let windows = SigningWindows {
+            batches: 100,
+            valsets: 200,
+            logic_calls: 100,
+        };
+        let report = signing_report(&db, 150, windows);
+        assert_eq!(report.validators.len(), 2);
+        let bob = &report.validators[0];
+        assert_eq!(bob.orchestrator, "gravity1bob");
+        assert_eq!(bob.batches.requested, 2);
+        assert_eq!(bob.batches.missed, 1);
+        assert_eq!(bob.batches.max_latency_blocks, Some(4));
+        assert_eq!(bob.valsets.signed, 1);
+        assert_eq!(bob.valsets.average_latency_blocks, Some(10.0));
+        let alice = &report.validators[1];
+        assert_eq!(alice.batches.missed, 0);
+        assert_eq!(alice.batches.max_latency_blocks, Some(0));
+        assert_eq!(alice.logic_calls.requested, 0);
+        assert!(alice.logic_calls.average_latency_blocks.is_none());
+
+        let mut query = ConfirmQuery {
+            limit: Some(2),
+            ..Default::default()
+        };
+        let page = confirm_page(&db, "gravity1bob", &query).unwrap();
+        let heights: Vec<u64> = page.confirms.iter().map(|c| c.block_number).collect();
+        assert_eq!(heights, vec![10, 104]);
+        query.cursor = page.next_cursor;
+        let page = confirm_page(&db, "gravity1bob", &query).unwrap();
+        assert_eq!(page.confirms.len(), 1);
+        assert_eq!(page.confirms[0].block_number, 130);
+        assert!(page.next_cursor.is_none());
+    }
 }
@@ -0,0 +1,366 @@
+//! Keeps every event the Gravity contract has emitted on Ethereum. The in memory Ethereum info
+//! only holds the last 7,200 blocks and is lost on restart, this store is synced once from the
+//! configured deploy block and then incrementally every minute, `log_chunk_size` blocks per
+//! request. Events of finalized blocks never change, the events of the blocks after them are
+//! kept apart as unconfirmed and replaced on every sync. An unconfirmed event that disappears or
+//! moves to another block was rolled back by a reorg, these are counted in the sync status.
+
+use crate::config::get_config;
+use crate::gravity_info::{get_gravity_info, log_block_hash, log_tx_hash, REQUEST_TIMEOUT};
+use crate::health::unix_now;
+use crate::metrics::{record_collector_run, record_eth_reorg, ETH_EVENTS_COLLECTOR};
+use crate::transactions::database::{
+    eth_event_cf, load_eth_event, load_eth_events_synced_to, load_unconfirmed_eth_events,
+    save_eth_event, save_eth_events_synced_to, save_eth_reorg, save_unconfirmed_eth_events,
+    ClaimDetails, CustomBridgeValidator, EthEvent,
+};
+use crate::upstream::failover::FailoverEthereum;
+use crate::upstream::EthereumSource;
+use actix_web::rt::System;
+use clarity::{Address as EthAddress, Uint256};
+use futures::future::join5;
+use gravity_utils::error::GravityError;
+use gravity_utils::types::event_signatures::*;
+use gravity_utils::types::{
+    Erc20DeployedEvent, LogicCallExecutedEvent, SendToCosmosEvent, TransactionBatchExecutedEvent,
+    ValsetUpdatedEvent,
+};
+use log::{error, info, warn};
+use rocksdb::DB;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const LOOP_TIME: Duration = Duration::from_secs(60);
+
+pub fn eth_event_sync_thread(db: Arc<DB>) {
+    info!("Starting Ethereum event sync");
+
+    thread::spawn(move || loop {
+        let runner = System::new();
+        runner.block_on(async {
+            let start = Instant::now();
+            let config = get_config();
+            // the contract address is read from the Gravity params
+            let bridge = match get_gravity_info() {
+                Some(info) => info.params.bridge_ethereum_address,
+                None => return,
+            };
+            let web3 = FailoverEthereum::from_config(&config.ethereum, REQUEST_TIMEOUT);
+            let synced = sync_eth_events(
+                &db,
+                &web3,
+                bridge,
+                config.ethereum.deploy_block,
+                config.ethereum.log_chunk_size,
+            )
+            .await;
+            // unconfirmed events start after the last synced block, so only once it is current
+            let result = match synced {
+                Ok(stored) => sync_unconfirmed_eth_events(&db, &web3, bridge, unix_now() as i64)
+                    .await
+                    .map(|_| stored),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(stored) => {
+                    if stored > 0 {
+                        info!("Stored {} Ethereum events", stored);
+                    }
+                    record_collector_run(ETH_EVENTS_COLLECTOR, start, true);
+                }
+                Err(e) => {
+                    error!("Failed to sync Ethereum events {:?}", e);
+                    record_collector_run(ETH_EVENTS_COLLECTOR, start, false);
+                }
+            }
+        });
+        thread::sleep(LOOP_TIME);
+    });
+}
+
+fn to_u64(value: Uint256) -> u64 {
+    value.to_string().parse().unwrap()
+}
+
+/// Stores the events of every finalized block after the last synced one, starting at
+/// `deploy_block` on the first run. Progress is saved after every chunk so an interrupted sync
+/// resumes where it stopped. Returns the number of events stored.
+pub async fn sync_eth_events(
+    db: &DB,
+    web3: &dyn EthereumSource,
+    bridge: EthAddress,
+    deploy_block: u64,
+    chunk_size: u64,
+) -> Result<usize, GravityError> {
+    let finalized = to_u64(web3.finalized_block_number().await?);
+    let mut start = load_eth_events_synced_to(db).map_or(deploy_block, |block| block + 1);
+    let mut stored = 0;
+    while start <= finalized {
+        let end = (start + chunk_size.max(1) - 1).min(finalized);
+        let events = get_eth_events(web3, bridge, start, end).await?;
+        for event in events.iter() {
+            save_eth_event(db, event);
+        }
+        save_eth_events_synced_to(db, end);
+        stored += events.len();
+        start = end + 1;
+    }
+    Ok(stored)
+}
+
+/// Replaces the stored events of the blocks after the last synced one with those on chain now.
+/// A stored unconfirmed event that is neither on chain now nor stored as finalized since, or is
+/// in a block with another hash, was rolled back by a reorg. Returns the number rolled back.
+pub async fn sync_unconfirmed_eth_events(
+    db: &DB,
+    web3: &dyn EthereumSource,
+    bridge: EthAddress,
+    now: i64,
+) -> Result<usize, GravityError> {
+    let synced_to = match load_eth_events_synced_to(db) {
+        Some(block) => block,
+        None => return Ok(0),
+    };
+    let latest = to_u64(web3.block_number().await?);
+    let events = if latest > synced_to {
+        get_eth_events(web3, bridge, synced_to + 1, latest).await?
+    } else {
+        Vec::new()
+    };
+
+    let mut rolled_back = 0;
+    for previous in load_unconfirmed_eth_events(db) {
+        let current = match events
+            .iter()
+            .find(|e| e.event_nonce == previous.event_nonce)
+        {
+            Some(event) => Some(event.clone()),
+            None => load_eth_event(db, eth_event_cf(&previous.details), previous.event_nonce),
+        };
+        if current.as_ref() != Some(&previous) {
+            warn!(
+                "Ethereum event {} in block {} was rolled back by a reorg",
+                previous.event_nonce, previous.block_height
+            );
+            rolled_back += 1;
+        }
+    }
+    save_unconfirmed_eth_events(db, &events);
+    if rolled_back > 0 {
+        save_eth_reorg(db, now);
+        record_eth_reorg(rolled_back as u64);
+    }
+    Ok(rolled_back)
+}
+
+/// Every event `bridge` emitted from block `start` to `end` inclusive, in nonce order
+async fn get_eth_events(
+    web3: &dyn EthereumSource,
+    bridge: EthAddress,
+    start: u64,
+    end: u64,
+) -> Result<Vec<EthEvent>, GravityError> {
+    let logs = |event: &'static str| web3.get_logs(start.into(), end.into(), bridge, event);
+    let (deposits, batches, valsets, erc20_deploys, logic_calls) = join5(
+        logs(SENT_TO_COSMOS_EVENT_SIG),
+        logs(TRANSACTION_BATCH_EXECUTED_EVENT_SIG),
+        logs(VALSET_UPDATED_EVENT_SIG),
+        logs(ERC20_DEPLOYED_EVENT_SIG),
+        logs(LOGIC_CALL_EVENT_SIG),
+    )
+    .await;
+    let (deposits, batches, valsets, erc20_deploys, logic_calls) =
+        (deposits?, batches?, valsets?, erc20_deploys?, logic_calls?);
+
+    // every log is parsed into one event, in order
+    let mut events = Vec::new();
+    for (e, log) in SendToCosmosEvent::from_logs(&deposits)?
+        .into_iter()
+        .zip(deposits.iter())
+    {
+        events.push(EthEvent {
+            event_nonce: e.event_nonce,
+            block_height: to_u64(e.block_height),
+            tx_hash: log_tx_hash(log),
+            block_hash: log_block_hash(log),
+            details: ClaimDetails::SendToCosmos {
+                token_contract: e.erc20.to_string(),
+                amount: e.amount.to_string(),
+                ethereum_sender: e.sender.to_string(),
+                cosmos_receiver: e.destination,
+            },
+        });
+    }
+    for (e, log) in TransactionBatchExecutedEvent::from_logs(&batches)?
+        .into_iter()
+        .zip(batches.iter())
+    {
+        events.push(EthEvent {
+            event_nonce: e.event_nonce,
+            block_height: to_u64(e.block_height),
+            tx_hash: log_tx_hash(log),
+            block_hash: log_block_hash(log),
+            details: ClaimDetails::BatchSendToEth {
+                batch_nonce: e.batch_nonce,
+                token_contract: e.erc20.to_string(),
+            },
+        });
+    }
+    for (e, log) in ValsetUpdatedEvent::from_logs(&valsets)?
+        .into_iter()
+        .zip(valsets.iter())
+    {
+        events.push(EthEvent {
+            event_nonce: e.event_nonce,
+            block_height: to_u64(e.block_height),
+            tx_hash: log_tx_hash(log),
+            block_hash: log_block_hash(log),
+            details: ClaimDetails::ValsetUpdated {
+                valset_nonce: e.valset_nonce,
+                members: e
+                    .members
+                    .into_iter()
+                    .map(|m| CustomBridgeValidator {
+                        power: m.power,
+                        ethereum_address: m.eth_address.to_string(),
+                    })
+                    .collect(),
+                reward_amount: e.reward_amount.to_string(),
+                // the denom of the reward is only known on Gravity
+                reward_denom: String::new(),
+                reward_token: e.reward_token.map(|t| t.to_string()).unwrap_or_default(),
+            },
+        });
+    }
+    for (e, log) in Erc20DeployedEvent::from_logs(&erc20_deploys)?
+        .into_iter()
+        .zip(erc20_deploys.iter())
+    {
+        events.push(EthEvent {
+            event_nonce: e.event_nonce,
+            block_height: to_u64(e.block_height),
+            tx_hash: log_tx_hash(log),
+            block_hash: log_block_hash(log),
+            details: ClaimDetails::Erc20Deployed {
+                cosmos_denom: e.cosmos_denom,
+                token_contract: e.erc20_address.to_string(),
+                name: e.name,
+                symbol: e.symbol,
+                decimals: e.decimals.into(),
+            },
+        });
+    }
+    for (e, log) in LogicCallExecutedEvent::from_logs(&logic_calls)?
+        .into_iter()
+        .zip(logic_calls.iter())
+    {
+        events.push(EthEvent {
+            event_nonce: e.event_nonce,
+            block_height: to_u64(e.block_height),
+            tx_hash: log_tx_hash(log),
+            block_hash: log_block_hash(log),
+            details: ClaimDetails::LogicCallExecuted {
+                invalidation_id: hex::encode(e.invalidation_id),
+                invalidation_nonce: e.invalidation_nonce,
+            },
+        });
+    }
+    events.sort_by_key(|e| e.event_nonce);
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transactions::database::{
+        load_eth_events, load_eth_reorgs, ETH_BATCH_EXECUTED, ETH_SEND_TO_COSMOS,
+    };
+    use crate::upstream::fake::{
+        send_to_cosmos_log, FakeEthereum, TestDb, BRIDGE_ADDRESS, ETH_FINALIZED_BLOCK, WETH_ADDRESS,
+    };
+
+    fn nonces(events: &[EthEvent]) -> Vec<u64> {
+        events.iter().map(|e| e.event_nonce).collect()
+    }
+
+    /// A run of the sync thread, returns the number of events rolled back
+    async fn sync(db: &DB, eth: &FakeEthereum, now: i64) -> usize {
+        let bridge = BRIDGE_ADDRESS.parse().unwrap();
+        sync_eth_events(db, eth, bridge, 15_870_000, 1_000)
+            .await
+            .unwrap();
+        sync_unconfirmed_eth_events(db, eth, bridge, now)
+            .await
+            .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn test_sync_eth_events() {
+        let db = TestDb::open("sync_eth_events");
+        let mut eth = FakeEthereum::fixture();
+        let bridge = BRIDGE_ADDRESS.parse().unwrap();
+
+        // the deposit at 15,876,578 is not finalized yet
+        let stored = sync_eth_events(&db, &eth, bridge, 15_870_000, 1_000)
+            .await
+            .unwrap();
+        assert_eq!(stored, 2);
+        assert_eq!(load_eth_events_synced_to(&db), Some(ETH_FINALIZED_BLOCK));
+        let batch = load_eth_event(&db, ETH_BATCH_EXECUTED, 19_315).unwrap();
+        assert_eq!(batch.block_height, 15_876_100);
+        assert!(matches!(
+            batch.details,
+            ClaimDetails::BatchSendToEth {
+                batch_nonce: 6312,
+                ..
+            }
+        ));
+        let deposit = load_eth_event(&db, ETH_SEND_TO_COSMOS, 19_443).unwrap();
+        assert!(deposit.tx_hash.is_some_and(|h| h.starts_with("0x")));
+
+        // the next run only reads the blocks finalized since
+        eth.finalized_block_number = 15_876_600u64.into();
+        let stored = sync_eth_events(&db, &eth, bridge, 15_870_000, 1_000)
+            .await
+            .unwrap();
+        assert_eq!(stored, 1);
+        let deposits = load_eth_events(&db, ETH_SEND_TO_COSMOS, 0, u64::MAX, true, 10);
+        assert_eq!(nonces(&deposits), vec![19_444, 19_443]);
+    }
+
+    #[actix_web::test]
+    async fn test_eth_event_reorg() {
+        let db = TestDb::open("eth_event_reorg");
+        let mut eth = FakeEthereum::fixture();
+        let bridge = BRIDGE_ADDRESS.parse().unwrap();
+
+        // the deposit at 15,876,578 is kept apart until it is finalized
+        assert_eq!(sync(&db, &eth, 1_000).await, 0);
+        assert_eq!(nonces(&load_unconfirmed_eth_events(&db)), vec![19_444]);
+        assert!(load_eth_event(&db, ETH_SEND_TO_COSMOS, 19_444).is_none());
+        eth.finalized_block_number = 15_876_600u64.into();
+        assert_eq!(sync(&db, &eth, 1_060).await, 0);
+        assert!(load_unconfirmed_eth_events(&db).is_empty());
+        assert!(load_eth_event(&db, ETH_SEND_TO_COSMOS, 19_444).is_some());
+
+        // a new deposit disappears before it is finalized
+        eth.block_number = 15_876_700u64.into();
+        eth.logs.push(send_to_cosmos_log(
+            bridge,
+            WETH_ADDRESS.parse().unwrap(),
+            WETH_ADDRESS.parse().unwrap(),
+            "gravity1cwulk79y2t8zljgvlutqs5gzx4gruwmj0txnnm",
+            1_000,
+            19_445,
+            15_876_650,
+        ));
+        assert_eq!(sync(&db, &eth, 1_120).await, 0);
+        assert_eq!(nonces(&load_unconfirmed_eth_events(&db)), vec![19_445]);
+        assert_eq!(load_eth_reorgs(&db), (0, None));
+        eth.logs.pop();
+        assert_eq!(sync(&db, &eth, 1_180).await, 1);
+        assert!(load_unconfirmed_eth_events(&db).is_empty());
+        assert_eq!(load_eth_reorgs(&db), (1, Some(1_180)));
+    }
+}
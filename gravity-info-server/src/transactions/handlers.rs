@@ -0,0 +1,457 @@
+//! The message types the block indexer understands. Each one is a `MsgHandler` registered in
+//! `HANDLERS`, which decodes the message, saves it and names it for keys, metrics and config.
+//! Indexing a new message type only takes a handler here, types listed in
+//! `[indexer] disabled_msg_types` are skipped by the indexer but stay readable through the API.
+
+use crate::config::get_config;
+use crate::transactions::database::{
+    save_claim, save_confirm, save_msg_cancel_send_to_eth, save_msg_ibc_transfer,
+    save_msg_send_to_eth, ClaimDetails, ConfirmDetails, CustomBridgeValidator, CustomClaim,
+    CustomConfirm, CustomMsgCancelSendToEth, CustomMsgSendToEth, CustomMsgTransfer,
+    MSG_CANCEL_SEND_TO_ETH, MSG_IBC_TRANSFER, MSG_SEND_TO_ETH,
+};
+use crate::transactions::records::Record;
+use crate::upstream::RawTxResult;
+use cosmos_sdk_proto_althea::ibc::applications::transfer::v1::MsgTransfer;
+use deep_space::utils::decode_any;
+use gravity_proto::gravity::v1::{
+    MsgBatchSendToEthClaim, MsgCancelSendToEth, MsgConfirmBatch, MsgConfirmLogicCall,
+    MsgErc20DeployedClaim, MsgLogicCallExecutedClaim, MsgSendToCosmosClaim, MsgSendToEth,
+    MsgValsetConfirm, MsgValsetUpdatedClaim,
+};
+use lazy_static::lazy_static;
+use rocksdb::DB;
+use serde::Serialize;
+
+/// Where a message was found
+pub struct MsgContext<'a> {
+    pub block_number: u64,
+    pub timestamp: i64,
+    pub tx_hash: &'a str,
+    /// Position of the message in its tx
+    pub msg_index: u32,
+    /// None if the tx result was unavailable
+    pub tx_result: Option<&'a RawTxResult>,
+}
+
+impl MsgContext<'_> {
+    /// Key of a message of type `name` stored in its handler's column family, the message index
+    /// keeps several messages of one type in a tx apart
+    pub fn key(&self, name: &str) -> String {
+        format!(
+            "{:012}:{}:{}:{}:{}",
+            self.block_number, name, self.timestamp, self.msg_index, self.tx_hash
+        )
+    }
+
+    pub fn tx_failed(&self) -> bool {
+        self.tx_result.is_some_and(|r| r.code != 0)
+    }
+}
+
+pub trait MsgHandler: Send + Sync {
+    fn type_url(&self) -> &'static str;
+    /// Name of the message type in keys, metrics, counters and the `[indexer]` config
+    fn name(&self) -> &'static str;
+    /// Decodes `message` and saves it, returns false if it could not be decoded or was skipped
+    fn save(&self, db: &DB, ctx: &MsgContext, message: prost_types::Any) -> bool;
+    /// Column family holding the messages of this type under `MsgContext::key`, None for
+    /// handlers storing their messages elsewhere
+    fn column_family(&self) -> Option<&'static str> {
+        None
+    }
+    /// Decodes a message this handler stored in its column family into its API representation
+    fn to_json(&self, _value: &[u8]) -> Option<serde_json::Value> {
+        None
+    }
+}
+
+lazy_static! {
+    static ref HANDLERS: Vec<Box<dyn MsgHandler>> = vec![
+        Box::new(SendToEthHandler),
+        Box::new(CancelSendToEthHandler),
+        Box::new(IbcTransferHandler),
+        Box::new(ClaimHandler {
+            type_url: "/gravity.v1.MsgSendToCosmosClaim",
+            name: "msgSendToCosmosClaim",
+        }),
+        Box::new(ClaimHandler {
+            type_url: "/gravity.v1.MsgBatchSendToEthClaim",
+            name: "msgBatchSendToEthClaim",
+        }),
+        Box::new(ClaimHandler {
+            type_url: "/gravity.v1.MsgValsetUpdatedClaim",
+            name: "msgValsetUpdatedClaim",
+        }),
+        Box::new(ClaimHandler {
+            type_url: "/gravity.v1.MsgERC20DeployedClaim",
+            name: "msgErc20DeployedClaim",
+        }),
+        Box::new(ClaimHandler {
+            type_url: "/gravity.v1.MsgLogicCallExecutedClaim",
+            name: "msgLogicCallExecutedClaim",
+        }),
+        Box::new(ConfirmHandler {
+            type_url: "/gravity.v1.MsgConfirmBatch",
+            name: "msgConfirmBatch",
+        }),
+        Box::new(ConfirmHandler {
+            type_url: "/gravity.v1.MsgValsetConfirm",
+            name: "msgValsetConfirm",
+        }),
+        Box::new(ConfirmHandler {
+            type_url: "/gravity.v1.MsgConfirmLogicCall",
+            name: "msgConfirmLogicCall",
+        }),
+    ];
+}
+
+/// Every registered handler, enabled or not
+pub fn handlers() -> impl Iterator<Item = &'static dyn MsgHandler> {
+    HANDLERS.iter().map(|h| h.as_ref())
+}
+
+/// Looks up a handler by the name its messages are stored under, whether or not it is enabled
+pub fn handler_by_name(name: &str) -> Option<&'static dyn MsgHandler> {
+    handlers().find(|h| h.name() == name)
+}
+
+/// The handlers the indexer runs, those not disabled in the config
+pub fn enabled_handlers() -> Vec<&'static dyn MsgHandler> {
+    without(&get_config().indexer.disabled_msg_types)
+}
+
+fn without(disabled: &[String]) -> Vec<&'static dyn MsgHandler> {
+    handlers()
+        .filter(|h| !disabled.iter().any(|d| d == h.name()))
+        .collect()
+}
+
+/// Decodes a stored message, the API's JSON is only produced here from the decoded type
+fn stored_to_json<T: Record + Serialize>(value: &[u8]) -> Option<serde_json::Value> {
+    let msg = T::from_bytes(value).ok()?;
+    serde_json::to_value(&msg).ok()
+}
+
+struct SendToEthHandler;
+
+impl MsgHandler for SendToEthHandler {
+    fn type_url(&self) -> &'static str {
+        "/gravity.v1.MsgSendToEth"
+    }
+
+    fn name(&self) -> &'static str {
+        "msgSendToEth"
+    }
+
+    fn save(&self, db: &DB, ctx: &MsgContext, message: prost_types::Any) -> bool {
+        let msg: MsgSendToEth = match decode_any(message) {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+        let mut custom_msg = CustomMsgSendToEth::from(&msg);
+        custom_msg.tx_id = ctx.tx_result.and_then(|r| outgoing_tx_id(r, ctx.msg_index));
+        save_msg_send_to_eth(db, &ctx.key(self.name()), &custom_msg);
+        true
+    }
+
+    fn column_family(&self) -> Option<&'static str> {
+        Some(MSG_SEND_TO_ETH)
+    }
+
+    fn to_json(&self, value: &[u8]) -> Option<serde_json::Value> {
+        stored_to_json::<CustomMsgSendToEth>(value)
+    }
+}
+
+struct CancelSendToEthHandler;
+
+impl MsgHandler for CancelSendToEthHandler {
+    fn type_url(&self) -> &'static str {
+        "/gravity.v1.MsgCancelSendToEth"
+    }
+
+    fn name(&self) -> &'static str {
+        "msgCancelSendToEth"
+    }
+
+    fn save(&self, db: &DB, ctx: &MsgContext, message: prost_types::Any) -> bool {
+        let msg: MsgCancelSendToEth = match decode_any(message) {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+        let custom_msg = CustomMsgCancelSendToEth::from(&msg);
+        save_msg_cancel_send_to_eth(db, &ctx.key(self.name()), &custom_msg);
+        true
+    }
+
+    fn column_family(&self) -> Option<&'static str> {
+        Some(MSG_CANCEL_SEND_TO_ETH)
+    }
+
+    fn to_json(&self, value: &[u8]) -> Option<serde_json::Value> {
+        stored_to_json::<CustomMsgCancelSendToEth>(value)
+    }
+}
+
+struct IbcTransferHandler;
+
+impl MsgHandler for IbcTransferHandler {
+    fn type_url(&self) -> &'static str {
+        "/ibc.applications.transfer.v1.MsgTransfer"
+    }
+
+    fn name(&self) -> &'static str {
+        "msgIbcTransfer"
+    }
+
+    fn save(&self, db: &DB, ctx: &MsgContext, message: prost_types::Any) -> bool {
+        let msg: MsgTransfer = match decode_any(message) {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+        let custom_msg = CustomMsgTransfer::from(&msg);
+        save_msg_ibc_transfer(db, &ctx.key(self.name()), &custom_msg);
+        true
+    }
+
+    fn column_family(&self) -> Option<&'static str> {
+        Some(MSG_IBC_TRANSFER)
+    }
+
+    fn to_json(&self, value: &[u8]) -> Option<serde_json::Value> {
+        stored_to_json::<CustomMsgTransfer>(value)
+    }
+}
+
+/// Orchestrator claims, saved in the `CLAIMS` column family
+struct ClaimHandler {
+    type_url: &'static str,
+    name: &'static str,
+}
+
+impl MsgHandler for ClaimHandler {
+    fn type_url(&self) -> &'static str {
+        self.type_url
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn save(&self, db: &DB, ctx: &MsgContext, message: prost_types::Any) -> bool {
+        // a failed claim was never counted by the chain, and would overwrite the orchestrator's
+        // successful claim for the same event
+        if ctx.tx_failed() {
+            return false;
+        }
+        let (orchestrator, event_nonce, eth_block_height, details) = match decode_claim(message) {
+            Some(v) => v,
+            None => return false,
+        };
+        save_claim(
+            db,
+            &CustomClaim {
+                orchestrator,
+                event_nonce,
+                eth_block_height,
+                block_number: ctx.block_number,
+                timestamp: ctx.timestamp,
+                tx_hash: ctx.tx_hash.to_string(),
+                details,
+            },
+        );
+        true
+    }
+}
+
+/// Batch, valset and logic call confirms, saved in the `CONFIRMS` column family
+struct ConfirmHandler {
+    type_url: &'static str,
+    name: &'static str,
+}
+
+impl MsgHandler for ConfirmHandler {
+    fn type_url(&self) -> &'static str {
+        self.type_url
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn save(&self, db: &DB, ctx: &MsgContext, message: prost_types::Any) -> bool {
+        // the chain never saw a signature from a failed tx
+        if ctx.tx_failed() {
+            return false;
+        }
+        let (orchestrator, eth_signer, details) = match decode_confirm(message) {
+            Some(v) => v,
+            None => return false,
+        };
+        save_confirm(
+            db,
+            &CustomConfirm {
+                orchestrator,
+                eth_signer,
+                block_number: ctx.block_number,
+                timestamp: ctx.timestamp,
+                tx_hash: ctx.tx_hash.to_string(),
+                details,
+            },
+        );
+        true
+    }
+}
+
+/// Decodes a claim message into the orchestrator that submitted it, its event nonce, the
+/// Ethereum block height of the event and the claim type specific fields
+pub fn decode_claim(message: prost_types::Any) -> Option<(String, u64, u64, ClaimDetails)> {
+    match message.type_url.as_str() {
+        "/gravity.v1.MsgSendToCosmosClaim" => {
+            let msg: MsgSendToCosmosClaim = decode_any(message).ok()?;
+            Some((
+                msg.orchestrator,
+                msg.event_nonce,
+                msg.eth_block_height,
+                ClaimDetails::SendToCosmos {
+                    token_contract: msg.token_contract,
+                    amount: msg.amount,
+                    ethereum_sender: msg.ethereum_sender,
+                    cosmos_receiver: msg.cosmos_receiver,
+                },
+            ))
+        }
+        "/gravity.v1.MsgBatchSendToEthClaim" => {
+            let msg: MsgBatchSendToEthClaim = decode_any(message).ok()?;
+            Some((
+                msg.orchestrator,
+                msg.event_nonce,
+                msg.eth_block_height,
+                ClaimDetails::BatchSendToEth {
+                    batch_nonce: msg.batch_nonce,
+                    token_contract: msg.token_contract,
+                },
+            ))
+        }
+        "/gravity.v1.MsgValsetUpdatedClaim" => {
+            let msg: MsgValsetUpdatedClaim = decode_any(message).ok()?;
+            Some((
+                msg.orchestrator,
+                msg.event_nonce,
+                msg.eth_block_height,
+                ClaimDetails::ValsetUpdated {
+                    valset_nonce: msg.valset_nonce,
+                    members: msg
+                        .members
+                        .into_iter()
+                        .map(|m| CustomBridgeValidator {
+                            power: m.power,
+                            ethereum_address: m.ethereum_address,
+                        })
+                        .collect(),
+                    reward_amount: msg.reward_amount,
+                    reward_denom: msg.reward_denom,
+                    reward_token: msg.reward_token,
+                },
+            ))
+        }
+        "/gravity.v1.MsgERC20DeployedClaim" => {
+            let msg: MsgErc20DeployedClaim = decode_any(message).ok()?;
+            Some((
+                msg.orchestrator,
+                msg.event_nonce,
+                msg.eth_block_height,
+                ClaimDetails::Erc20Deployed {
+                    cosmos_denom: msg.cosmos_denom,
+                    token_contract: msg.token_contract,
+                    name: msg.name,
+                    symbol: msg.symbol,
+                    decimals: msg.decimals,
+                },
+            ))
+        }
+        "/gravity.v1.MsgLogicCallExecutedClaim" => {
+            let msg: MsgLogicCallExecutedClaim = decode_any(message).ok()?;
+            Some((
+                msg.orchestrator,
+                msg.event_nonce,
+                msg.eth_block_height,
+                ClaimDetails::LogicCallExecuted {
+                    invalidation_id: hex::encode(msg.invalidation_id),
+                    invalidation_nonce: msg.invalidation_nonce,
+                },
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Decodes a confirm message into the orchestrator that submitted it, its Ethereum signer and
+/// what it signed
+fn decode_confirm(message: prost_types::Any) -> Option<(String, String, ConfirmDetails)> {
+    match message.type_url.as_str() {
+        "/gravity.v1.MsgConfirmBatch" => {
+            let msg: MsgConfirmBatch = decode_any(message).ok()?;
+            Some((
+                msg.orchestrator,
+                msg.eth_signer,
+                ConfirmDetails::Batch {
+                    nonce: msg.nonce,
+                    token_contract: msg.token_contract,
+                },
+            ))
+        }
+        "/gravity.v1.MsgValsetConfirm" => {
+            let msg: MsgValsetConfirm = decode_any(message).ok()?;
+            Some((
+                msg.orchestrator,
+                msg.eth_address,
+                ConfirmDetails::Valset { nonce: msg.nonce },
+            ))
+        }
+        "/gravity.v1.MsgConfirmLogicCall" => {
+            let msg: MsgConfirmLogicCall = decode_any(message).ok()?;
+            Some((
+                msg.orchestrator,
+                msg.eth_signer,
+                ConfirmDetails::LogicCall {
+                    invalidation_id: msg.invalidation_id,
+                    invalidation_nonce: msg.invalidation_nonce,
+                },
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Reads the outgoing tx id the chain assigned message `msg_index` of a tx, current versions
+/// emit it in a typed `EventOutgoingTxId` and older ones as an `outgoing_tx_id` attribute
+fn outgoing_tx_id(result: &RawTxResult, msg_index: u32) -> Option<u64> {
+    result
+        .attribute(msg_index, "tx_id")
+        .or_else(|| result.attribute(msg_index, "outgoing_tx_id"))
+        .and_then(|id| id.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_registry() {
+        let names: HashSet<&str> = handlers().map(|h| h.name()).collect();
+        let type_urls: HashSet<&str> = handlers().map(|h| h.type_url()).collect();
+        assert_eq!(names.len(), handlers().count());
+        assert_eq!(type_urls.len(), handlers().count());
+        assert_eq!(
+            handler_by_name("msgSendToEth").unwrap().type_url(),
+            "/gravity.v1.MsgSendToEth"
+        );
+
+        let enabled = without(&["msgConfirmBatch".to_string()]);
+        assert_eq!(enabled.len(), handlers().count() - 1);
+        assert!(enabled.iter().all(|h| h.name() != "msgConfirmBatch"));
+    }
+}
@@ -1,2 +1,8 @@
+pub mod admin;
 pub mod database;
+pub mod deposits;
 pub mod endpoints;
+pub mod eth_events;
+pub mod handlers;
+pub mod records;
+pub mod withdrawals;
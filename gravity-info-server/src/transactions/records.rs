@@ -0,0 +1,975 @@
+//! The binary encoding of the values stored in the transaction database. Messages, tx statuses,
+//! claims and confirms are stored as protobuf, which is several times smaller and faster to scan
+//! than JSON, and are only turned into JSON by the API. Protobuf rather than a positional format
+//! like bincode so fields can still be added to a stored type without a migration, a record
+//! written before a field existed decodes with the field's default value.
+//!
+//! The messages are declared here with prost's derives instead of generated from .proto files,
+//! field tags must never be reused or renumbered once released.
+
+use crate::transactions::database::{
+    BatchedSend, ClaimDetails, ConfirmDetails, CustomBridgeValidator, CustomClaim, CustomCoin,
+    CustomConfirm, CustomHeight, CustomMsgCancelSendToEth, CustomMsgSendToEth, CustomMsgTransfer,
+    Deposit, EthEvent, ExecutedBatch, SendToEthRollup, TxStatus,
+};
+use prost::{Message, Oneof};
+
+/// A value stored in the database in its binary encoding
+pub trait Record: Sized {
+    fn to_bytes(&self) -> Vec<u8>;
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String>;
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct CoinProto {
+    #[prost(string, tag = "1")]
+    denom: String,
+    #[prost(string, tag = "2")]
+    amount: String,
+}
+
+fn coins_to_proto(coins: &[CustomCoin]) -> Vec<CoinProto> {
+    coins
+        .iter()
+        .map(|c| CoinProto {
+            denom: c.denom.clone(),
+            amount: c.amount.clone(),
+        })
+        .collect()
+}
+
+fn coins_from_proto(coins: Vec<CoinProto>) -> Vec<CustomCoin> {
+    coins
+        .into_iter()
+        .map(|c| CustomCoin {
+            denom: c.denom,
+            amount: c.amount,
+        })
+        .collect()
+}
+
+fn decode_proto<T: Message + Default>(bytes: &[u8]) -> Result<T, String> {
+    T::decode(bytes).map_err(|e| e.to_string())
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct SendToEthProto {
+    #[prost(string, tag = "1")]
+    sender: String,
+    #[prost(string, tag = "2")]
+    eth_dest: String,
+    #[prost(message, repeated, tag = "3")]
+    amount: Vec<CoinProto>,
+    #[prost(message, repeated, tag = "4")]
+    bridge_fee: Vec<CoinProto>,
+    #[prost(message, repeated, tag = "5")]
+    chain_fee: Vec<CoinProto>,
+    #[prost(uint64, optional, tag = "6")]
+    tx_id: Option<u64>,
+    #[prost(string, optional, tag = "7")]
+    cancelled_by: Option<String>,
+}
+
+impl Record for CustomMsgSendToEth {
+    fn to_bytes(&self) -> Vec<u8> {
+        SendToEthProto {
+            sender: self.sender.clone(),
+            eth_dest: self.eth_dest.clone(),
+            amount: coins_to_proto(&self.amount),
+            bridge_fee: coins_to_proto(&self.bridge_fee),
+            chain_fee: coins_to_proto(&self.chain_fee),
+            tx_id: self.tx_id,
+            cancelled_by: self.cancelled_by.clone(),
+        }
+        .encode_to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let proto: SendToEthProto = decode_proto(bytes)?;
+        Ok(CustomMsgSendToEth {
+            sender: proto.sender,
+            eth_dest: proto.eth_dest,
+            amount: coins_from_proto(proto.amount),
+            bridge_fee: coins_from_proto(proto.bridge_fee),
+            chain_fee: coins_from_proto(proto.chain_fee),
+            tx_id: proto.tx_id,
+            cancelled_by: proto.cancelled_by,
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct CancelSendToEthProto {
+    #[prost(uint64, tag = "1")]
+    transaction_id: u64,
+    #[prost(string, tag = "2")]
+    sender: String,
+}
+
+impl Record for CustomMsgCancelSendToEth {
+    fn to_bytes(&self) -> Vec<u8> {
+        CancelSendToEthProto {
+            transaction_id: self.transaction_id,
+            sender: self.sender.clone(),
+        }
+        .encode_to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let proto: CancelSendToEthProto = decode_proto(bytes)?;
+        Ok(CustomMsgCancelSendToEth {
+            transaction_id: proto.transaction_id,
+            sender: proto.sender,
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct HeightProto {
+    #[prost(uint64, tag = "1")]
+    revision_number: u64,
+    #[prost(uint64, tag = "2")]
+    revision_height: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct IbcTransferProto {
+    #[prost(string, tag = "1")]
+    source_port: String,
+    #[prost(string, tag = "2")]
+    source_channel: String,
+    #[prost(message, repeated, tag = "3")]
+    token: Vec<CoinProto>,
+    #[prost(string, tag = "4")]
+    sender: String,
+    #[prost(string, tag = "5")]
+    receiver: String,
+    #[prost(message, optional, tag = "6")]
+    timeout_height: Option<HeightProto>,
+    #[prost(uint64, tag = "7")]
+    timeout_timestamp: u64,
+}
+
+impl Record for CustomMsgTransfer {
+    fn to_bytes(&self) -> Vec<u8> {
+        IbcTransferProto {
+            source_port: self.source_port.clone(),
+            source_channel: self.source_channel.clone(),
+            token: coins_to_proto(&self.token),
+            sender: self.sender.clone(),
+            receiver: self.receiver.clone(),
+            timeout_height: self.timeout_height.as_ref().map(|h| HeightProto {
+                revision_number: h.revision_number,
+                revision_height: h.revision_height,
+            }),
+            timeout_timestamp: self.timeout_timestamp,
+        }
+        .encode_to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let proto: IbcTransferProto = decode_proto(bytes)?;
+        Ok(CustomMsgTransfer {
+            source_port: proto.source_port,
+            source_channel: proto.source_channel,
+            token: coins_from_proto(proto.token),
+            sender: proto.sender,
+            receiver: proto.receiver,
+            timeout_height: proto.timeout_height.map(|h| CustomHeight {
+                revision_number: h.revision_number,
+                revision_height: h.revision_height,
+            }),
+            timeout_timestamp: proto.timeout_timestamp,
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct TxStatusProto {
+    #[prost(uint32, tag = "1")]
+    code: u32,
+    #[prost(string, tag = "2")]
+    log: String,
+    #[prost(int64, tag = "3")]
+    gas_wanted: i64,
+    #[prost(int64, tag = "4")]
+    gas_used: i64,
+    #[prost(message, repeated, tag = "5")]
+    fee: Vec<CoinProto>,
+}
+
+impl Record for TxStatus {
+    fn to_bytes(&self) -> Vec<u8> {
+        TxStatusProto {
+            code: self.code,
+            log: self.log.clone(),
+            gas_wanted: self.gas_wanted,
+            gas_used: self.gas_used,
+            fee: coins_to_proto(&self.fee),
+        }
+        .encode_to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let proto: TxStatusProto = decode_proto(bytes)?;
+        Ok(TxStatus {
+            code: proto.code,
+            log: proto.log,
+            gas_wanted: proto.gas_wanted,
+            gas_used: proto.gas_used,
+            fee: coins_from_proto(proto.fee),
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct SendToCosmosProto {
+    #[prost(string, tag = "1")]
+    token_contract: String,
+    #[prost(string, tag = "2")]
+    amount: String,
+    #[prost(string, tag = "3")]
+    ethereum_sender: String,
+    #[prost(string, tag = "4")]
+    cosmos_receiver: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct BatchSendToEthProto {
+    #[prost(uint64, tag = "1")]
+    batch_nonce: u64,
+    #[prost(string, tag = "2")]
+    token_contract: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct BridgeValidatorProto {
+    #[prost(uint64, tag = "1")]
+    power: u64,
+    #[prost(string, tag = "2")]
+    ethereum_address: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct ValsetUpdatedProto {
+    #[prost(uint64, tag = "1")]
+    valset_nonce: u64,
+    #[prost(message, repeated, tag = "2")]
+    members: Vec<BridgeValidatorProto>,
+    #[prost(string, tag = "3")]
+    reward_amount: String,
+    #[prost(string, tag = "4")]
+    reward_denom: String,
+    #[prost(string, tag = "5")]
+    reward_token: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Erc20DeployedProto {
+    #[prost(string, tag = "1")]
+    cosmos_denom: String,
+    #[prost(string, tag = "2")]
+    token_contract: String,
+    #[prost(string, tag = "3")]
+    name: String,
+    #[prost(string, tag = "4")]
+    symbol: String,
+    #[prost(uint64, tag = "5")]
+    decimals: u64,
+}
+
+/// Identifies a logic call, shared by the logic call executed claim and the logic call confirm
+#[derive(Clone, PartialEq, Message)]
+struct LogicCallProto {
+    #[prost(string, tag = "1")]
+    invalidation_id: String,
+    #[prost(uint64, tag = "2")]
+    invalidation_nonce: u64,
+}
+
+#[derive(Clone, PartialEq, Oneof)]
+enum ClaimDetailsProto {
+    #[prost(message, tag = "7")]
+    SendToCosmos(SendToCosmosProto),
+    #[prost(message, tag = "8")]
+    BatchSendToEth(BatchSendToEthProto),
+    #[prost(message, tag = "9")]
+    ValsetUpdated(ValsetUpdatedProto),
+    #[prost(message, tag = "10")]
+    Erc20Deployed(Erc20DeployedProto),
+    #[prost(message, tag = "11")]
+    LogicCallExecuted(LogicCallProto),
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct ClaimProto {
+    #[prost(string, tag = "1")]
+    orchestrator: String,
+    #[prost(uint64, tag = "2")]
+    event_nonce: u64,
+    #[prost(uint64, tag = "3")]
+    eth_block_height: u64,
+    #[prost(uint64, tag = "4")]
+    block_number: u64,
+    #[prost(int64, tag = "5")]
+    timestamp: i64,
+    #[prost(string, tag = "6")]
+    tx_hash: String,
+    #[prost(oneof = "ClaimDetailsProto", tags = "7, 8, 9, 10, 11")]
+    details: Option<ClaimDetailsProto>,
+}
+
+impl From<&ClaimDetails> for ClaimDetailsProto {
+    fn from(details: &ClaimDetails) -> Self {
+        match details.clone() {
+            ClaimDetails::SendToCosmos {
+                token_contract,
+                amount,
+                ethereum_sender,
+                cosmos_receiver,
+            } => ClaimDetailsProto::SendToCosmos(SendToCosmosProto {
+                token_contract,
+                amount,
+                ethereum_sender,
+                cosmos_receiver,
+            }),
+            ClaimDetails::BatchSendToEth {
+                batch_nonce,
+                token_contract,
+            } => ClaimDetailsProto::BatchSendToEth(BatchSendToEthProto {
+                batch_nonce,
+                token_contract,
+            }),
+            ClaimDetails::ValsetUpdated {
+                valset_nonce,
+                members,
+                reward_amount,
+                reward_denom,
+                reward_token,
+            } => ClaimDetailsProto::ValsetUpdated(ValsetUpdatedProto {
+                valset_nonce,
+                members: members
+                    .into_iter()
+                    .map(|m| BridgeValidatorProto {
+                        power: m.power,
+                        ethereum_address: m.ethereum_address,
+                    })
+                    .collect(),
+                reward_amount,
+                reward_denom,
+                reward_token,
+            }),
+            ClaimDetails::Erc20Deployed {
+                cosmos_denom,
+                token_contract,
+                name,
+                symbol,
+                decimals,
+            } => ClaimDetailsProto::Erc20Deployed(Erc20DeployedProto {
+                cosmos_denom,
+                token_contract,
+                name,
+                symbol,
+                decimals,
+            }),
+            ClaimDetails::LogicCallExecuted {
+                invalidation_id,
+                invalidation_nonce,
+            } => ClaimDetailsProto::LogicCallExecuted(LogicCallProto {
+                invalidation_id,
+                invalidation_nonce,
+            }),
+        }
+    }
+}
+
+impl From<ClaimDetailsProto> for ClaimDetails {
+    fn from(details: ClaimDetailsProto) -> Self {
+        match details {
+            ClaimDetailsProto::SendToCosmos(d) => ClaimDetails::SendToCosmos {
+                token_contract: d.token_contract,
+                amount: d.amount,
+                ethereum_sender: d.ethereum_sender,
+                cosmos_receiver: d.cosmos_receiver,
+            },
+            ClaimDetailsProto::BatchSendToEth(d) => ClaimDetails::BatchSendToEth {
+                batch_nonce: d.batch_nonce,
+                token_contract: d.token_contract,
+            },
+            ClaimDetailsProto::ValsetUpdated(d) => ClaimDetails::ValsetUpdated {
+                valset_nonce: d.valset_nonce,
+                members: d
+                    .members
+                    .into_iter()
+                    .map(|m| CustomBridgeValidator {
+                        power: m.power,
+                        ethereum_address: m.ethereum_address,
+                    })
+                    .collect(),
+                reward_amount: d.reward_amount,
+                reward_denom: d.reward_denom,
+                reward_token: d.reward_token,
+            },
+            ClaimDetailsProto::Erc20Deployed(d) => ClaimDetails::Erc20Deployed {
+                cosmos_denom: d.cosmos_denom,
+                token_contract: d.token_contract,
+                name: d.name,
+                symbol: d.symbol,
+                decimals: d.decimals,
+            },
+            ClaimDetailsProto::LogicCallExecuted(d) => ClaimDetails::LogicCallExecuted {
+                invalidation_id: d.invalidation_id,
+                invalidation_nonce: d.invalidation_nonce,
+            },
+        }
+    }
+}
+
+impl Record for CustomClaim {
+    fn to_bytes(&self) -> Vec<u8> {
+        ClaimProto {
+            orchestrator: self.orchestrator.clone(),
+            event_nonce: self.event_nonce,
+            eth_block_height: self.eth_block_height,
+            block_number: self.block_number,
+            timestamp: self.timestamp,
+            tx_hash: self.tx_hash.clone(),
+            details: Some((&self.details).into()),
+        }
+        .encode_to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let proto: ClaimProto = decode_proto(bytes)?;
+        Ok(CustomClaim {
+            orchestrator: proto.orchestrator,
+            event_nonce: proto.event_nonce,
+            eth_block_height: proto.eth_block_height,
+            block_number: proto.block_number,
+            timestamp: proto.timestamp,
+            tx_hash: proto.tx_hash,
+            details: proto.details.ok_or("claim without details")?.into(),
+        })
+    }
+}
+
+/// The details of an Ethereum event are those of its claim, so they share the claim's oneof and
+/// its tags
+#[derive(Clone, PartialEq, Message)]
+struct EthEventProto {
+    #[prost(uint64, tag = "1")]
+    event_nonce: u64,
+    #[prost(uint64, tag = "2")]
+    block_height: u64,
+    #[prost(string, optional, tag = "3")]
+    tx_hash: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    block_hash: Option<String>,
+    #[prost(oneof = "ClaimDetailsProto", tags = "7, 8, 9, 10, 11")]
+    details: Option<ClaimDetailsProto>,
+}
+
+impl Record for EthEvent {
+    fn to_bytes(&self) -> Vec<u8> {
+        EthEventProto {
+            event_nonce: self.event_nonce,
+            block_height: self.block_height,
+            tx_hash: self.tx_hash.clone(),
+            block_hash: self.block_hash.clone(),
+            details: Some((&self.details).into()),
+        }
+        .encode_to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let proto: EthEventProto = decode_proto(bytes)?;
+        Ok(EthEvent {
+            event_nonce: proto.event_nonce,
+            block_height: proto.block_height,
+            tx_hash: proto.tx_hash,
+            block_hash: proto.block_hash,
+            details: proto.details.ok_or("event without details")?.into(),
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct BatchConfirmProto {
+    #[prost(uint64, tag = "1")]
+    nonce: u64,
+    #[prost(string, tag = "2")]
+    token_contract: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct ValsetConfirmProto {
+    #[prost(uint64, tag = "1")]
+    nonce: u64,
+}
+
+#[derive(Clone, PartialEq, Oneof)]
+enum ConfirmDetailsProto {
+    #[prost(message, tag = "6")]
+    Batch(BatchConfirmProto),
+    #[prost(message, tag = "7")]
+    Valset(ValsetConfirmProto),
+    #[prost(message, tag = "8")]
+    LogicCall(LogicCallProto),
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct ConfirmProto {
+    #[prost(string, tag = "1")]
+    orchestrator: String,
+    #[prost(string, tag = "2")]
+    eth_signer: String,
+    #[prost(uint64, tag = "3")]
+    block_number: u64,
+    #[prost(int64, tag = "4")]
+    timestamp: i64,
+    #[prost(string, tag = "5")]
+    tx_hash: String,
+    #[prost(oneof = "ConfirmDetailsProto", tags = "6, 7, 8")]
+    details: Option<ConfirmDetailsProto>,
+}
+
+impl From<&ConfirmDetails> for ConfirmDetailsProto {
+    fn from(details: &ConfirmDetails) -> Self {
+        match details.clone() {
+            ConfirmDetails::Batch {
+                nonce,
+                token_contract,
+            } => ConfirmDetailsProto::Batch(BatchConfirmProto {
+                nonce,
+                token_contract,
+            }),
+            ConfirmDetails::Valset { nonce } => {
+                ConfirmDetailsProto::Valset(ValsetConfirmProto { nonce })
+            }
+            ConfirmDetails::LogicCall {
+                invalidation_id,
+                invalidation_nonce,
+            } => ConfirmDetailsProto::LogicCall(LogicCallProto {
+                invalidation_id,
+                invalidation_nonce,
+            }),
+        }
+    }
+}
+
+impl From<ConfirmDetailsProto> for ConfirmDetails {
+    fn from(details: ConfirmDetailsProto) -> Self {
+        match details {
+            ConfirmDetailsProto::Batch(d) => ConfirmDetails::Batch {
+                nonce: d.nonce,
+                token_contract: d.token_contract,
+            },
+            ConfirmDetailsProto::Valset(d) => ConfirmDetails::Valset { nonce: d.nonce },
+            ConfirmDetailsProto::LogicCall(d) => ConfirmDetails::LogicCall {
+                invalidation_id: d.invalidation_id,
+                invalidation_nonce: d.invalidation_nonce,
+            },
+        }
+    }
+}
+
+impl Record for CustomConfirm {
+    fn to_bytes(&self) -> Vec<u8> {
+        ConfirmProto {
+            orchestrator: self.orchestrator.clone(),
+            eth_signer: self.eth_signer.clone(),
+            block_number: self.block_number,
+            timestamp: self.timestamp,
+            tx_hash: self.tx_hash.clone(),
+            details: Some((&self.details).into()),
+        }
+        .encode_to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let proto: ConfirmProto = decode_proto(bytes)?;
+        Ok(CustomConfirm {
+            orchestrator: proto.orchestrator,
+            eth_signer: proto.eth_signer,
+            block_number: proto.block_number,
+            timestamp: proto.timestamp,
+            tx_hash: proto.tx_hash,
+            details: proto.details.ok_or("confirm without details")?.into(),
+        })
+    }
+}
+
+/// Protobuf has no 128 bit integers, token amounts are kept as decimal strings like the chain does
+#[derive(Clone, PartialEq, Message)]
+struct SendToEthRollupProto {
+    #[prost(string, tag = "1")]
+    amount: String,
+    #[prost(string, tag = "2")]
+    bridge_fee: String,
+    #[prost(string, tag = "3")]
+    chain_fee: String,
+    #[prost(uint64, tag = "4")]
+    count: u64,
+    #[prost(string, tag = "5")]
+    cancelled_amount: String,
+    #[prost(string, tag = "6")]
+    cancelled_bridge_fee: String,
+    #[prost(uint64, tag = "7")]
+    cancelled_count: u64,
+}
+
+fn parse_amount(amount: &str) -> Result<u128, String> {
+    if amount.is_empty() {
+        return Ok(0);
+    }
+    amount
+        .parse()
+        .map_err(|e| format!("invalid amount {}: {}", amount, e))
+}
+
+impl Record for SendToEthRollup {
+    fn to_bytes(&self) -> Vec<u8> {
+        SendToEthRollupProto {
+            amount: self.amount.to_string(),
+            bridge_fee: self.bridge_fee.to_string(),
+            chain_fee: self.chain_fee.to_string(),
+            count: self.count,
+            cancelled_amount: self.cancelled_amount.to_string(),
+            cancelled_bridge_fee: self.cancelled_bridge_fee.to_string(),
+            cancelled_count: self.cancelled_count,
+        }
+        .encode_to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let proto: SendToEthRollupProto = decode_proto(bytes)?;
+        Ok(SendToEthRollup {
+            amount: parse_amount(&proto.amount)?,
+            bridge_fee: parse_amount(&proto.bridge_fee)?,
+            chain_fee: parse_amount(&proto.chain_fee)?,
+            count: proto.count,
+            cancelled_amount: parse_amount(&proto.cancelled_amount)?,
+            cancelled_bridge_fee: parse_amount(&proto.cancelled_bridge_fee)?,
+            cancelled_count: proto.cancelled_count,
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct BatchedSendProto {
+    #[prost(string, tag = "1")]
+    token_contract: String,
+    #[prost(uint64, tag = "2")]
+    batch_nonce: u64,
+    #[prost(uint64, tag = "3")]
+    batch_timeout: u64,
+    #[prost(int64, tag = "4")]
+    seen_at: i64,
+}
+
+impl Record for BatchedSend {
+    fn to_bytes(&self) -> Vec<u8> {
+        BatchedSendProto {
+            token_contract: self.token_contract.clone(),
+            batch_nonce: self.batch_nonce,
+            batch_timeout: self.batch_timeout,
+            seen_at: self.seen_at,
+        }
+        .encode_to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let proto: BatchedSendProto = decode_proto(bytes)?;
+        Ok(BatchedSend {
+            token_contract: proto.token_contract,
+            batch_nonce: proto.batch_nonce,
+            batch_timeout: proto.batch_timeout,
+            seen_at: proto.seen_at,
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct ExecutedBatchProto {
+    #[prost(uint64, tag = "1")]
+    eth_block_height: u64,
+    #[prost(uint64, tag = "2")]
+    event_nonce: u64,
+    #[prost(int64, tag = "3")]
+    observed_at: i64,
+}
+
+impl Record for ExecutedBatch {
+    fn to_bytes(&self) -> Vec<u8> {
+        ExecutedBatchProto {
+            eth_block_height: self.eth_block_height,
+            event_nonce: self.event_nonce,
+            observed_at: self.observed_at,
+        }
+        .encode_to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let proto: ExecutedBatchProto = decode_proto(bytes)?;
+        Ok(ExecutedBatch {
+            eth_block_height: proto.eth_block_height,
+            event_nonce: proto.event_nonce,
+            observed_at: proto.observed_at,
+        })
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct DepositProto {
+    #[prost(uint64, tag = "1")]
+    event_nonce: u64,
+    #[prost(string, optional, tag = "2")]
+    tx_hash: Option<String>,
+    #[prost(string, tag = "3")]
+    erc20: String,
+    #[prost(string, tag = "4")]
+    sender: String,
+    #[prost(string, tag = "5")]
+    destination: String,
+    #[prost(string, tag = "6")]
+    amount: String,
+    #[prost(uint64, tag = "7")]
+    eth_block_height: u64,
+    #[prost(int64, tag = "8")]
+    seen_at: i64,
+    #[prost(int64, optional, tag = "9")]
+    finalized_at: Option<i64>,
+    #[prost(int64, optional, tag = "10")]
+    observed_at: Option<i64>,
+    #[prost(int64, optional, tag = "11")]
+    forwarded_at: Option<i64>,
+}
+
+impl Record for Deposit {
+    fn to_bytes(&self) -> Vec<u8> {
+        DepositProto {
+            event_nonce: self.event_nonce,
+            tx_hash: self.tx_hash.clone(),
+            erc20: self.erc20.clone(),
+            sender: self.sender.clone(),
+            destination: self.destination.clone(),
+            amount: self.amount.clone(),
+            eth_block_height: self.eth_block_height,
+            seen_at: self.seen_at,
+            finalized_at: self.finalized_at,
+            observed_at: self.observed_at,
+            forwarded_at: self.forwarded_at,
+        }
+        .encode_to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let proto: DepositProto = decode_proto(bytes)?;
+        Ok(Deposit {
+            event_nonce: proto.event_nonce,
+            tx_hash: proto.tx_hash,
+            erc20: proto.erc20,
+            sender: proto.sender,
+            destination: proto.destination,
+            amount: proto.amount,
+            eth_block_height: proto.eth_block_height,
+            seen_at: proto.seen_at,
+            finalized_at: proto.finalized_at,
+            observed_at: proto.observed_at,
+            forwarded_at: proto.forwarded_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocksdb::{Options, DB};
+    use serde::Serialize;
+    use std::time::Instant;
+
+    fn round_trip<T: Record + Serialize>(record: &T) {
+        let decoded = T::from_bytes(&record.to_bytes()).unwrap();
+        assert_eq!(
+            serde_json::to_value(record).unwrap(),
+            serde_json::to_value(&decoded).unwrap()
+        );
+    }
+
+    fn send(i: u64) -> CustomMsgSendToEth {
+        let coin = |amount: u64| CustomCoin {
+            denom: "gravity0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(),
+            amount: amount.to_string(),
+        };
+        CustomMsgSendToEth {
+            sender: "gravity1ahx7f8wyertuus9r20284ej0asrs085ceqtfnm".to_string(),
+            eth_dest: "0xd2e4BdE4B2a9E4EB6e5A2e1e1dC5B5cE3A7c5B01".to_string(),
+            amount: vec![coin(1_000_000 + i)],
+            bridge_fee: vec![coin(10_000)],
+            chain_fee: vec![coin(2_000)],
+            tx_id: Some(i),
+            cancelled_by: None,
+        }
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        round_trip(&send(1));
+        round_trip(&CustomMsgSendToEth {
+            tx_id: None,
+            cancelled_by: Some("ABCD".to_string()),
+            ..send(2)
+        });
+        round_trip(&CustomMsgCancelSendToEth {
+            transaction_id: 7,
+            sender: "gravity1sender".to_string(),
+        });
+        round_trip(&CustomMsgTransfer {
+            source_port: "transfer".to_string(),
+            source_channel: "channel-10".to_string(),
+            token: vec![],
+            sender: "gravity1sender".to_string(),
+            receiver: "osmo1receiver".to_string(),
+            timeout_height: Some(CustomHeight {
+                revision_number: 1,
+                revision_height: 500,
+            }),
+            timeout_timestamp: 0,
+        });
+        round_trip(&TxStatus {
+            code: 5,
+            log: "insufficient funds".to_string(),
+            gas_wanted: 200_000,
+            gas_used: 80_000,
+            fee: vec![],
+        });
+        round_trip(&CustomClaim {
+            orchestrator: "gravity1orch".to_string(),
+            event_nonce: 12,
+            eth_block_height: 15_000_000,
+            block_number: 1_008,
+            timestamp: 1_700_000_000,
+            tx_hash: "ABCD".to_string(),
+            details: ClaimDetails::ValsetUpdated {
+                valset_nonce: 3,
+                members: vec![CustomBridgeValidator {
+                    power: 100,
+                    ethereum_address: "0x01".to_string(),
+                }],
+                reward_amount: "0".to_string(),
+                reward_denom: String::new(),
+                reward_token: String::new(),
+            },
+        });
+        round_trip(&CustomConfirm {
+            orchestrator: "gravity1orch".to_string(),
+            eth_signer: "0x01".to_string(),
+            block_number: 1_009,
+            timestamp: 1_700_000_000,
+            tx_hash: "ABCD".to_string(),
+            details: ConfirmDetails::LogicCall {
+                invalidation_id: "0a0b".to_string(),
+                invalidation_nonce: 4,
+            },
+        });
+        round_trip(&BatchedSend {
+            token_contract: "0x01".to_string(),
+            batch_nonce: 1_200,
+            batch_timeout: 15_880_000,
+            seen_at: 1_700_000_000,
+        });
+        round_trip(&ExecutedBatch {
+            eth_block_height: 15_876_100,
+            event_nonce: 19_315,
+            observed_at: 1_700_000_600,
+        });
+        round_trip(&EthEvent {
+            event_nonce: 19_315,
+            block_height: 15_876_100,
+            tx_hash: None,
+            block_hash: Some("0x5e8a".to_string()),
+            details: ClaimDetails::BatchSendToEth {
+                batch_nonce: 6_312,
+                token_contract: "0x01".to_string(),
+            },
+        });
+        round_trip(&Deposit {
+            event_nonce: 19_320,
+            tx_hash: Some("0xabcd".to_string()),
+            erc20: "0x01".to_string(),
+            sender: "0x02".to_string(),
+            destination: "osmo1receiver".to_string(),
+            amount: "1000000".to_string(),
+            eth_block_height: 15_876_200,
+            seen_at: 1_700_000_000,
+            finalized_at: Some(1_700_001_200),
+            observed_at: None,
+            forwarded_at: None,
+        });
+
+        // a confirm without details is corrupt rather than a default confirm
+        let empty = ConfirmProto::default().encode_to_vec();
+        assert!(CustomConfirm::from_bytes(&empty).is_err());
+    }
+
+    /// Compares how fast sends are scanned and decoded from RocksDB stored as JSON, as before,
+    /// and as protobuf, run with `cargo test --release bench_scan_throughput -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_scan_throughput() {
+        const COUNT: u64 = 200_000;
+        let path = std::env::temp_dir().join("gravity_info_bench_scan_throughput");
+        let mut options = Options::default();
+        options.create_if_missing(true);
+        options.create_missing_column_families(true);
+        let db = DB::open_cf(&options, &path, ["json", "protobuf"]).unwrap();
+        for i in 0..COUNT {
+            let key = format!("{:012}:msgSendToEth:1700000000:{:064}", i, i);
+            let send = send(i);
+            db.put_cf(
+                db.cf_handle("json").unwrap(),
+                key.as_bytes(),
+                serde_json::to_vec(&send).unwrap(),
+            )
+            .unwrap();
+            db.put_cf(
+                db.cf_handle("protobuf").unwrap(),
+                key.as_bytes(),
+                send.to_bytes(),
+            )
+            .unwrap();
+        }
+
+        let scan = |cf: &str, decode: &dyn Fn(&[u8]) -> CustomMsgSendToEth| {
+            let start = Instant::now();
+            let mut bytes = 0;
+            let mut total = 0u64;
+            for item in db.iterator_cf(db.cf_handle(cf).unwrap(), rocksdb::IteratorMode::Start) {
+                let (_, value) = item.unwrap();
+                bytes += value.len();
+                total += decode(&value).tx_id.unwrap();
+            }
+            let elapsed = start.elapsed();
+            assert_eq!(total, COUNT * (COUNT - 1) / 2);
+            println!(
+                "{}: {} bytes per send, {:.0} sends/s",
+                cf,
+                bytes as u64 / COUNT,
+                COUNT as f64 / elapsed.as_secs_f64()
+            );
+            (bytes, elapsed)
+        };
+        let (json_bytes, json_time) = scan("json", &|v| serde_json::from_slice(v).unwrap());
+        let (proto_bytes, proto_time) =
+            scan("protobuf", &|v| CustomMsgSendToEth::from_bytes(v).unwrap());
+        println!(
+            "protobuf is {:.1}x smaller and scans {:.1}x faster",
+            json_bytes as f64 / proto_bytes as f64,
+            json_time.as_secs_f64() / proto_time.as_secs_f64()
+        );
+        assert!(proto_bytes < json_bytes);
+
+        drop(db);
+        DB::destroy(&Options::default(), &path).unwrap();
+    }
+}
@@ -0,0 +1,414 @@
+//! Follows each send to eth from the outgoing tx pool through the batches it is put in to its
+//! execution on Ethereum. Batch contents are only on chain until the batch is executed or times
+//! out, so this thread records the batches of every Gravity info snapshot and the batches executed
+//! in finalized blocks of every Ethereum info snapshot, executions are also recorded from
+//! orchestrator claims as they are indexed. The state of a send is worked out from these records
+//! when it is requested.
+
+use crate::gravity_info::{info_tracker_thread, EthInfo, GravityInfo};
+use crate::transactions::database::{
+    cf, load_executed_batch, load_msg_send_to_eth, load_send_batches,
+    load_withdrawal_tracking_start, save_batched_send, save_executed_batch,
+    save_withdrawal_tracking_start, tx_succeeded, BatchedSend, CustomMsgSendToEth, ExecutedBatch,
+    MSG_KEY_PARTS, TX_HASH_INDEX,
+};
+use log::{error, info};
+use rocksdb::DB;
+use serde::Serialize;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WithdrawalState {
+    /// The tx failed on chain and nothing was sent
+    Failed,
+    /// In the outgoing tx pool waiting to be batched
+    Pending,
+    /// In a batch waiting to be relayed to Ethereum
+    Batched,
+    /// The latest batch it was in timed out, it is back in the pool waiting for a new batch
+    TimedOut,
+    /// Its batch was executed on Ethereum
+    Executed,
+    /// Cancelled while in the pool, the amount and bridge fee were returned to the sender
+    Cancelled,
+    /// Sent before batches were tracked or without a known tx id and not seen in a batch since
+    Unknown,
+}
+
+/// A batch a send was put in
+#[derive(Debug, Clone, Serialize)]
+pub struct WithdrawalBatch {
+    #[serde(flatten)]
+    pub batch: BatchedSend,
+    /// True once Ethereum is past the batch timeout without executing it
+    pub timed_out: bool,
+    pub executed: Option<ExecutedBatch>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Withdrawal {
+    pub tx_hash: String,
+    pub block_number: u64,
+    pub state: WithdrawalState,
+    #[serde(flatten)]
+    pub send: CustomMsgSendToEth,
+    /// Unix time in seconds of the block the send was in
+    pub sent_at: i64,
+    /// Unix time in seconds the latest batch the send is in was first seen
+    pub batched_at: Option<i64>,
+    /// Unix time in seconds the execution of its batch was first reported
+    pub executed_at: Option<i64>,
+    /// Unix time in seconds of the block the cancellation was in
+    pub cancelled_at: Option<i64>,
+    /// Every batch the send was seen in, oldest first
+    pub batches: Vec<WithdrawalBatch>,
+}
+
+/// Records the batches and batch executions in the in memory Gravity and Ethereum info
+pub fn withdrawal_tracker_thread(db: Arc<DB>) {
+    info!("Starting withdrawal tracker");
+
+    info_tracker_thread(db, track_withdrawals);
+}
+
+fn track_withdrawals(db: &DB, gravity: Option<&GravityInfo>, eth: Option<&EthInfo>, now: i64) {
+    if let Some(gravity) = gravity {
+        if load_withdrawal_tracking_start(db).is_none() {
+            save_withdrawal_tracking_start(db, now);
+        }
+        for batch in gravity.pending_batches.iter() {
+            for tx in batch.transactions.iter() {
+                let data = BatchedSend {
+                    token_contract: batch.token_contract.to_string(),
+                    batch_nonce: batch.nonce,
+                    batch_timeout: batch.batch_timeout,
+                    seen_at: now,
+                };
+                save_batched_send(db, tx.id, &data);
+            }
+        }
+    }
+    if let Some(eth) = eth {
+        // an execution in a block that is not finalized could still be rolled back by a reorg
+        for event in eth
+            .batch_events
+            .iter()
+            .filter(|e| e.block_height <= eth.latest_finalized_eth_block)
+        {
+            let eth_block_height = match event.block_height.to_string().parse() {
+                Ok(v) => v,
+                Err(e) => {
+                    error!(
+                        "Skipped batch {} with invalid block height {}: {}",
+                        event.batch_nonce, event.block_height, e
+                    );
+                    continue;
+                }
+            };
+            let data = ExecutedBatch {
+                eth_block_height,
+                event_nonce: event.event_nonce,
+                observed_at: now,
+            };
+            save_executed_batch(db, &event.erc20.to_string(), event.batch_nonce, &data);
+        }
+    }
+}
+
+fn withdrawal_state(
+    succeeded: bool,
+    send: &CustomMsgSendToEth,
+    sent_at: i64,
+    batches: &[WithdrawalBatch],
+    tracking_start: Option<i64>,
+) -> WithdrawalState {
+    if !succeeded {
+        return WithdrawalState::Failed;
+    }
+    if batches.iter().any(|b| b.executed.is_some()) {
+        return WithdrawalState::Executed;
+    }
+    if send.cancelled_by.is_some() {
+        return WithdrawalState::Cancelled;
+    }
+    match batches.last() {
+        Some(batch) if batch.timed_out => WithdrawalState::TimedOut,
+        Some(_) => WithdrawalState::Batched,
+        // a send made before tracking started may have been batched and executed unseen
+        None => match (send.tx_id, tracking_start) {
+            (Some(_), Some(start)) if sent_at >= start => WithdrawalState::Pending,
+            _ => WithdrawalState::Unknown,
+        },
+    }
+}
+
+/// Block time of the tx with `tx_hash`, if it has an indexed message
+fn tx_timestamp(db: &DB, tx_hash: &str) -> Option<i64> {
+    let prefix = format!("{}:", tx_hash.to_uppercase());
+    let cf = cf(db, TX_HASH_INDEX);
+    let (index_key, _) = match db.prefix_iterator_cf(cf, prefix.as_bytes()).next()? {
+        Ok(v) => v,
+        Err(e) => {
+            error!("RocksDB iterator error: {}", e);
+            return None;
+        }
+    };
+    let index_key = String::from_utf8_lossy(&index_key);
+    index_key
+        .strip_prefix(&prefix)?
+        .split(':')
+        .nth(2)?
+        .parse()
+        .ok()
+}
+
+/// The withdrawal of the send stored under `key`, batches are timed out once Ethereum is past
+/// `latest_eth_height`
+pub fn load_withdrawal(db: &DB, key: &str, latest_eth_height: Option<u64>) -> Option<Withdrawal> {
+    let key_parts: Vec<&str> = key.split(':').collect();
+    if key_parts.len() != MSG_KEY_PARTS || key_parts[1] != "msgSendToEth" {
+        return None;
+    }
+    let block_number = key_parts[0].parse().ok()?;
+    let sent_at = key_parts[2].parse().ok()?;
+    let tx_hash = key_parts[4];
+    let send = load_msg_send_to_eth(db, key)?;
+
+    let batches: Vec<WithdrawalBatch> = send
+        .tx_id
+        .map(|tx_id| load_send_batches(db, tx_id))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|batch| {
+            let executed = load_executed_batch(db, &batch.token_contract, batch.batch_nonce);
+            let timed_out = executed.is_none()
+                && latest_eth_height.is_some_and(|height| height > batch.batch_timeout);
+            WithdrawalBatch {
+                batch,
+                timed_out,
+                executed,
+            }
+        })
+        .collect();
+    let state = withdrawal_state(
+        tx_succeeded(db, tx_hash),
+        &send,
+        sent_at,
+        &batches,
+        load_withdrawal_tracking_start(db),
+    );
+    Some(Withdrawal {
+        tx_hash: tx_hash.to_string(),
+        block_number,
+        state,
+        sent_at,
+        batched_at: batches.last().map(|b| b.batch.seen_at),
+        executed_at: batches
+            .iter()
+            .find_map(|b| b.executed.as_ref())
+            .map(|e| e.observed_at),
+        cancelled_at: send
+            .cancelled_by
+            .as_deref()
+            .and_then(|hash| tx_timestamp(db, hash)),
+        batches,
+        send,
+    })
+}
+
+/// The withdrawals of every send in the tx with `tx_hash`
+pub fn load_tx_withdrawals(
+    db: &DB,
+    tx_hash: &str,
+    latest_eth_height: Option<u64>,
+) -> Vec<Withdrawal> {
+    let prefix = format!("{}:", tx_hash.to_uppercase());
+    let cf = cf(db, TX_HASH_INDEX);
+    let mut withdrawals = Vec::new();
+    for item in db.prefix_iterator_cf(cf, prefix.as_bytes()) {
+        let (index_key, _) = match item {
+            Ok(v) => v,
+            Err(e) => {
+                error!("RocksDB iterator error: {}", e);
+                break;
+            }
+        };
+        let index_key = String::from_utf8_lossy(&index_key);
+        let key = match index_key.strip_prefix(&prefix) {
+            Some(k) => k,
+            None => break,
+        };
+        if let Some(withdrawal) = load_withdrawal(db, key, latest_eth_height) {
+            withdrawals.push(withdrawal);
+        }
+    }
+    withdrawals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transactions::database::{
+        save_msg_cancel_send_to_eth, save_msg_send_to_eth, save_tx_status, CustomCoin,
+        CustomMsgCancelSendToEth, TxStatus,
+    };
+    use crate::upstream::fake::{batch_executed_log, TestDb, BRIDGE_ADDRESS, ETH_LATEST_BLOCK};
+    use gravity_utils::types::{
+        BatchTransaction, Erc20Token, TransactionBatch, TransactionBatchExecutedEvent,
+    };
+    use web30::amm::USDC_CONTRACT_ADDRESS;
+
+    fn send(tx_id: u64) -> CustomMsgSendToEth {
+        let coin = |amount: u64| CustomCoin {
+            denom: format!("gravity{}", *USDC_CONTRACT_ADDRESS),
+            amount: amount.to_string(),
+        };
+        CustomMsgSendToEth {
+            sender: "gravity1xq7j6pr0zphuq6elxmrg98zkm57u36pvz2uwcc".to_string(),
+            eth_dest: "0xf0f08f640d5553e79b91296dba6c3f10521e5174".to_string(),
+            amount: vec![coin(89_200_000)],
+            bridge_fee: vec![coin(3_200_000)],
+            chain_fee: vec![coin(17_840)],
+            tx_id: Some(tx_id),
+            cancelled_by: None,
+        }
+    }
+
+    fn batch(nonce: u64, tx_ids: &[u64]) -> TransactionBatch {
+        let token = |amount: u32| Erc20Token {
+            amount: amount.into(),
+            token_contract_address: *USDC_CONTRACT_ADDRESS,
+        };
+        TransactionBatch {
+            nonce,
+            batch_timeout: ETH_LATEST_BLOCK + 100,
+            transactions: tx_ids
+                .iter()
+                .map(|id| BatchTransaction {
+                    id: *id,
+                    sender: "gravity1xq7j6pr0zphuq6elxmrg98zkm57u36pvz2uwcc"
+                        .parse()
+                        .unwrap(),
+                    destination: "0xf0f08f640d5553e79b91296dba6c3f10521e5174"
+                        .parse()
+                        .unwrap(),
+                    erc20_token: token(89_200_000),
+                    erc20_fee: token(3_200_000),
+                })
+                .collect(),
+            total_fee: token(3_200_000),
+            token_contract: *USDC_CONTRACT_ADDRESS,
+        }
+    }
+
+    #[test]
+    fn test_withdrawal_lifecycle() {
+        let db = TestDb::open("withdrawal_lifecycle");
+        let started = 1_700_000_000;
+        let batched_key = format!("{:012}:msgSendToEth:{}:0:AAAA", 1_000, started + 10);
+        let old_key = format!("{:012}:msgSendToEth:{}:0:BBBB", 900, started - 600);
+        let pending_key = format!("{:012}:msgSendToEth:{}:0:CCCC", 1_001, started + 20);
+        let failed_key = format!("{:012}:msgSendToEth:{}:0:DDDD", 1_002, started + 30);
+        save_msg_send_to_eth(&db, &batched_key, &send(7));
+        save_msg_send_to_eth(&db, &old_key, &send(3));
+        save_msg_send_to_eth(&db, &pending_key, &send(8));
+        save_msg_send_to_eth(&db, &failed_key, &send(9));
+        save_tx_status(
+            &db,
+            "DDDD",
+            &TxStatus {
+                code: 5,
+                log: "insufficient funds".to_string(),
+                gas_wanted: 200_000,
+                gas_used: 65_000,
+                fee: vec![],
+            },
+        );
+        let state = |key: &str, height: u64| load_withdrawal(&db, key, Some(height)).unwrap();
+
+        // nothing is pending until the batches are tracked
+        assert_eq!(
+            state(&pending_key, ETH_LATEST_BLOCK).state,
+            WithdrawalState::Unknown
+        );
+
+        let gravity = GravityInfo {
+            pending_batches: vec![batch(5, &[7])],
+            ..Default::default()
+        };
+        track_withdrawals(&db, Some(&gravity), None, started);
+        let withdrawal = state(&batched_key, ETH_LATEST_BLOCK);
+        assert_eq!(withdrawal.state, WithdrawalState::Batched);
+        assert_eq!(withdrawal.sent_at, started + 10);
+        assert_eq!(withdrawal.batched_at, Some(started));
+        assert_eq!(withdrawal.batches[0].batch.batch_nonce, 5);
+        assert_eq!(
+            state(&pending_key, ETH_LATEST_BLOCK).state,
+            WithdrawalState::Pending
+        );
+        assert_eq!(
+            state(&failed_key, ETH_LATEST_BLOCK).state,
+            WithdrawalState::Failed
+        );
+        // sent before tracking started and never seen in a batch
+        assert_eq!(
+            state(&old_key, ETH_LATEST_BLOCK).state,
+            WithdrawalState::Unknown
+        );
+
+        // the batch times out and the send is put in a new one, which keeps its first sighting
+        let withdrawal = state(&batched_key, ETH_LATEST_BLOCK + 101);
+        assert_eq!(withdrawal.state, WithdrawalState::TimedOut);
+        assert!(withdrawal.batches[0].timed_out);
+        let gravity = GravityInfo {
+            pending_batches: vec![batch(6, &[7, 8])],
+            ..Default::default()
+        };
+        track_withdrawals(&db, Some(&gravity), None, started + 60);
+        track_withdrawals(&db, Some(&gravity), None, started + 120);
+        let withdrawal = state(&batched_key, ETH_LATEST_BLOCK + 101);
+        assert_eq!(withdrawal.batches.len(), 2);
+        assert_eq!(withdrawal.state, WithdrawalState::Batched);
+        assert_eq!(withdrawal.batched_at, Some(started + 60));
+
+        let log = batch_executed_log(
+            BRIDGE_ADDRESS.parse().unwrap(),
+            *USDC_CONTRACT_ADDRESS,
+            6,
+            19_315,
+            ETH_LATEST_BLOCK + 50,
+        );
+        let eth = EthInfo {
+            batch_events: TransactionBatchExecutedEvent::from_logs(&[log]).unwrap(),
+            latest_finalized_eth_block: (ETH_LATEST_BLOCK + 50).into(),
+            ..Default::default()
+        };
+        track_withdrawals(&db, None, Some(&eth), started + 180);
+        let withdrawal = state(&batched_key, ETH_LATEST_BLOCK + 200);
+        assert_eq!(withdrawal.state, WithdrawalState::Executed);
+        assert_eq!(withdrawal.executed_at, Some(started + 180));
+        let executed = withdrawal.batches[1].executed.as_ref().unwrap();
+        assert_eq!(executed.eth_block_height, ETH_LATEST_BLOCK + 50);
+        assert_eq!(executed.event_nonce, 19_315);
+        assert!(!withdrawal.batches[1].timed_out);
+
+        // a send cancelled while in the pool
+        let cancel_key = format!("{:012}:msgCancelSendToEth:{}:0:EEEE", 1_003, started + 40);
+        let cancelled_key = format!("{:012}:msgSendToEth:{}:0:FFFF", 1_002, started + 35);
+        save_msg_send_to_eth(&db, &cancelled_key, &send(10));
+        save_msg_cancel_send_to_eth(
+            &db,
+            &cancel_key,
+            &CustomMsgCancelSendToEth {
+                transaction_id: 10,
+                sender: send(10).sender,
+            },
+        );
+        let withdrawals = load_tx_withdrawals(&db, "ffff", Some(ETH_LATEST_BLOCK));
+        assert_eq!(withdrawals.len(), 1);
+        assert_eq!(withdrawals[0].state, WithdrawalState::Cancelled);
+        assert_eq!(withdrawals[0].cancelled_at, Some(started + 40));
+        assert!(load_tx_withdrawals(&db, "EEEE", Some(ETH_LATEST_BLOCK)).is_empty());
+    }
+}
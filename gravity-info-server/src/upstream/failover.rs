@@ -0,0 +1,567 @@
+//! Sources that spread requests over several endpoints for the same chain. Every endpoint is scored on
+//! its latest block height, recent error rate and latency, each request goes to the most synced healthy
+//! endpoint and fails over to the next best one on error. Health is tracked per url in a global table so
+//! that the scores are shared between the collector threads, each of which builds its own clients.
+
+use super::live::{GrpcGravitySource, Web3Source};
+use super::{EthereumSource, GravitySource, RawBlock, RawTxResult};
+use crate::config::{redact_url, EthereumConfig, GravityConfig};
+use crate::metrics::record_upstream_request;
+use async_trait::async_trait;
+use clarity::{Address as EthAddress, Uint256};
+use cosmos_sdk_proto_althea::cosmos::base::v1beta1::DecCoin;
+use cosmos_sdk_proto_althea::cosmos::staking::v1beta1::DelegationResponse;
+use deep_space::client::types::AccountType;
+use deep_space::client::ChainStatus;
+use deep_space::Coin;
+use futures::future::{join_all, LocalBoxFuture};
+use gravity_proto::gravity::v1::{
+    Attestation, BatchFees, Params as GravityParams, PendingIbcAutoForward,
+};
+use gravity_utils::error::GravityError;
+use gravity_utils::types::{BatchConfirmResponse, TransactionBatch};
+use log::warn;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use web30::types::Log;
+
+/// How often the latest block height of each endpoint is checked
+const HEIGHT_REFRESH: Duration = Duration::from_secs(30);
+/// Upper bound on how long a failing endpoint is skipped for
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// Weight of the newest sample in the error rate and latency moving averages
+const DECAY: f64 = 0.1;
+
+#[derive(Debug, Clone, Default)]
+pub struct EndpointHealth {
+    pub url: String,
+    /// Latest block height reported by this endpoint, None until the first successful check
+    pub latest_height: Option<u64>,
+    pub requests: u64,
+    pub errors: u64,
+    /// Moving average of the fraction of requests that failed
+    pub error_rate: f64,
+    /// Moving average of request latency
+    pub latency_ms: f64,
+    height_checked: Option<Instant>,
+    consecutive_errors: u32,
+    backoff_until: Option<Instant>,
+}
+
+lazy_static! {
+    static ref ENDPOINT_HEALTH: Arc<RwLock<HashMap<String, EndpointHealth>>> =
+        Arc::new(RwLock::new(HashMap::new()));
+}
+
+pub fn get_endpoint_health(url: &str) -> Option<EndpointHealth> {
+    ENDPOINT_HEALTH.read().unwrap().get(url).cloned()
+}
+
+fn record_request(chain: &str, url: &str, latency: Duration, success: bool) {
+    // `/metrics` is public, the url may hold an API key
+    record_upstream_request(chain, &redact_url(url), latency, success);
+    let mut health = ENDPOINT_HEALTH.write().unwrap();
+    let h = health
+        .entry(url.to_string())
+        .or_insert_with(|| EndpointHealth {
+            url: url.to_string(),
+            ..Default::default()
+        });
+    let latency_ms = latency.as_secs_f64() * 1000.0;
+    h.latency_ms = if h.requests == 0 {
+        latency_ms
+    } else {
+        h.latency_ms * (1.0 - DECAY) + latency_ms * DECAY
+    };
+    h.requests += 1;
+    if success {
+        h.error_rate *= 1.0 - DECAY;
+        h.consecutive_errors = 0;
+        h.backoff_until = None;
+    } else {
+        h.errors += 1;
+        h.error_rate = h.error_rate * (1.0 - DECAY) + DECAY;
+        h.consecutive_errors += 1;
+        let backoff = Duration::from_secs(1 << h.consecutive_errors.min(6)).min(MAX_BACKOFF);
+        h.backoff_until = Some(Instant::now() + backoff);
+    }
+}
+
+fn record_height(url: &str, height: Option<u64>) {
+    let mut health = ENDPOINT_HEALTH.write().unwrap();
+    let h = health
+        .entry(url.to_string())
+        .or_insert_with(|| EndpointHealth {
+            url: url.to_string(),
+            ..Default::default()
+        });
+    if height.is_some() {
+        h.latest_height = height;
+    }
+    h.height_checked = Some(Instant::now());
+}
+
+fn needs_height_check(url: &str) -> bool {
+    match get_endpoint_health(url).and_then(|h| h.height_checked) {
+        Some(t) => t.elapsed() > HEIGHT_REFRESH,
+        None => true,
+    }
+}
+
+/// Orders `urls` from best to worst. Endpoints in backoff after an error come last, then endpoints
+/// more than `max_block_lag` blocks behind the most synced endpoint, the rest are ordered by how far
+/// behind they are, then error rate, then latency. Ties keep the configured order.
+pub fn rank_endpoints(urls: &[String], max_block_lag: u64) -> Vec<String> {
+    let health = ENDPOINT_HEALTH.read().unwrap();
+    let now = Instant::now();
+    let best_height = urls
+        .iter()
+        .filter_map(|u| health.get(u).and_then(|h| h.latest_height))
+        .max();
+    let mut ranked: Vec<_> = urls
+        .iter()
+        .map(|url| {
+            let key = match health.get(url) {
+                Some(h) => {
+                    let backed_off = matches!(h.backoff_until, Some(t) if t > now);
+                    let lag = match (best_height, h.latest_height) {
+                        (Some(best), Some(height)) => best.saturating_sub(height),
+                        _ => 0,
+                    };
+                    (
+                        backed_off,
+                        lag > max_block_lag,
+                        lag,
+                        (h.error_rate * 1000.0) as u64,
+                        h.latency_ms as u64,
+                    )
+                }
+                None => (false, false, 0, 0, 0),
+            };
+            (key, url.clone())
+        })
+        .collect();
+    ranked.sort_by_key(|(key, _)| *key);
+    ranked.into_iter().map(|(_, url)| url).collect()
+}
+
+/// A set of interchangeable sources for the same chain
+struct Pool<T: ?Sized> {
+    /// Label for metrics, either "ethereum" or "gravity"
+    chain: &'static str,
+    endpoints: Vec<(String, Box<T>)>,
+    max_block_lag: u64,
+}
+
+impl<T: ?Sized> Pool<T> {
+    fn urls(&self) -> Vec<String> {
+        self.endpoints.iter().map(|(url, _)| url.clone()).collect()
+    }
+
+    /// Queries the latest height of every endpoint that has not been checked recently, with only
+    /// one endpoint there is nothing to choose between so this is skipped
+    async fn refresh_heights<'a>(
+        &'a self,
+        probe: impl Fn(&'a T) -> LocalBoxFuture<'a, Result<Option<u64>, GravityError>>,
+    ) {
+        if self.endpoints.len() < 2 {
+            return;
+        }
+        let futs = self
+            .endpoints
+            .iter()
+            .filter(|(url, _)| needs_height_check(url))
+            .map(|(url, source)| {
+                let fut = probe(source.as_ref());
+                async move {
+                    let start = Instant::now();
+                    let res = fut.await;
+                    record_request(self.chain, url, start.elapsed(), res.is_ok());
+                    record_height(url, res.ok().flatten());
+                }
+            });
+        join_all(futs).await;
+    }
+
+    /// Tries `f` against each endpoint from best to worst, returning the first success or the
+    /// last error if every endpoint failed
+    async fn call<'a, R>(
+        &'a self,
+        f: impl Fn(&'a T) -> LocalBoxFuture<'a, Result<R, GravityError>>,
+    ) -> Result<R, GravityError> {
+        let mut last_error = None;
+        for url in rank_endpoints(&self.urls(), self.max_block_lag) {
+            let (_, source) = self.endpoints.iter().find(|(u, _)| *u == url).unwrap();
+            let start = Instant::now();
+            match f(source.as_ref()).await {
+                Ok(v) => {
+                    record_request(self.chain, &url, start.elapsed(), true);
+                    return Ok(v);
+                }
+                Err(e) => {
+                    record_request(self.chain, &url, start.elapsed(), false);
+                    if self.endpoints.len() > 1 {
+                        warn!(
+                            "Request to {} failed with {:?}, failing over",
+                            redact_url(&url),
+                            e
+                        );
+                    }
+                    last_error = Some(e);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            GravityError::InvalidBridgeStateError("No endpoints configured".to_string())
+        }))
+    }
+}
+
+pub struct FailoverEthereum {
+    pool: Pool<dyn EthereumSource>,
+}
+
+impl FailoverEthereum {
+    pub fn new(endpoints: Vec<(String, Box<dyn EthereumSource>)>, max_block_lag: u64) -> Self {
+        FailoverEthereum {
+            pool: Pool {
+                chain: "ethereum",
+                endpoints,
+                max_block_lag,
+            },
+        }
+    }
+
+    pub fn from_config(config: &EthereumConfig, timeout: Duration) -> Self {
+        let endpoints = config
+            .rpc
+            .iter()
+            .map(|url| {
+                let source: Box<dyn EthereumSource> = Box::new(Web3Source::new(url, timeout));
+                (url.clone(), source)
+            })
+            .collect();
+        FailoverEthereum::new(endpoints, config.max_block_lag)
+    }
+
+    async fn refresh(&self) {
+        self.pool
+            .refresh_heights(|s| {
+                Box::pin(async move {
+                    let height = s.block_number().await?;
+                    Ok(height.to_string().parse().ok())
+                })
+            })
+            .await
+    }
+}
+
+#[async_trait(?Send)]
+impl EthereumSource for FailoverEthereum {
+    async fn block_number(&self) -> Result<Uint256, GravityError> {
+        self.refresh().await;
+        self.pool.call(|s| s.block_number()).await
+    }
+
+    async fn finalized_block_number(&self) -> Result<Uint256, GravityError> {
+        self.refresh().await;
+        self.pool.call(|s| s.finalized_block_number()).await
+    }
+
+    async fn get_logs(
+        &self,
+        start: Uint256,
+        end: Uint256,
+        contract: EthAddress,
+        event: &str,
+    ) -> Result<Vec<Log>, GravityError> {
+        self.refresh().await;
+        self.pool
+            .call(|s| s.get_logs(start, end, contract, event))
+            .await
+    }
+
+    async fn erc20_symbol(&self, erc20: EthAddress) -> Result<String, GravityError> {
+        self.refresh().await;
+        self.pool.call(|s| s.erc20_symbol(erc20)).await
+    }
+
+    async fn erc20_decimals(&self, erc20: EthAddress) -> Result<Uint256, GravityError> {
+        self.refresh().await;
+        self.pool.call(|s| s.erc20_decimals(erc20)).await
+    }
+
+    async fn usdc_price(
+        &self,
+        erc20: EthAddress,
+        amount: Uint256,
+    ) -> Result<Uint256, GravityError> {
+        self.refresh().await;
+        self.pool.call(|s| s.usdc_price(erc20, amount)).await
+    }
+}
+
+pub struct FailoverGravity {
+    pool: Pool<dyn GravitySource>,
+}
+
+impl FailoverGravity {
+    pub fn new(endpoints: Vec<(String, Box<dyn GravitySource>)>, max_block_lag: u64) -> Self {
+        FailoverGravity {
+            pool: Pool {
+                chain: "gravity",
+                endpoints,
+                max_block_lag,
+            },
+        }
+    }
+
+    pub fn from_config(config: &GravityConfig, timeout: Duration) -> Result<Self, GravityError> {
+        let mut endpoints = Vec::new();
+        for url in config.grpc.iter() {
+            let source: Box<dyn GravitySource> =
+                Box::new(GrpcGravitySource::new(url, &config.prefix, timeout)?);
+            endpoints.push((url.clone(), source));
+        }
+        Ok(FailoverGravity::new(endpoints, config.max_block_lag))
+    }
+
+    async fn refresh(&self) {
+        self.pool
+            .refresh_heights(|s| {
+                Box::pin(async move {
+                    Ok(match s.chain_status().await? {
+                        ChainStatus::Moving { block_height } => Some(block_height),
+                        _ => None,
+                    })
+                })
+            })
+            .await
+    }
+}
+
+#[async_trait(?Send)]
+impl GravitySource for FailoverGravity {
+    async fn chain_status(&self) -> Result<ChainStatus, GravityError> {
+        self.refresh().await;
+        self.pool.call(|s| s.chain_status()).await
+    }
+
+    async fn get_block(&self, height: u64) -> Result<Option<RawBlock>, GravityError> {
+        self.refresh().await;
+        self.pool.call(|s| s.get_block(height)).await
+    }
+
+    async fn get_block_range(&self, start: u64, end: u64) -> Result<Vec<RawBlock>, GravityError> {
+        self.refresh().await;
+        self.pool.call(|s| s.get_block_range(start, end)).await
+    }
+
+    async fn get_tx_result(&self, tx_hash: String) -> Result<Option<RawTxResult>, GravityError> {
+        self.refresh().await;
+        self.pool.call(|s| s.get_tx_result(tx_hash.clone())).await
+    }
+
+    async fn params(&self) -> Result<GravityParams, GravityError> {
+        self.refresh().await;
+        self.pool.call(|s| s.params()).await
+    }
+
+    async fn pending_batch_fees(&self) -> Result<Vec<BatchFees>, GravityError> {
+        self.refresh().await;
+        self.pool.call(|s| s.pending_batch_fees()).await
+    }
+
+    async fn latest_transaction_batches(&self) -> Result<Vec<TransactionBatch>, GravityError> {
+        self.refresh().await;
+        self.pool.call(|s| s.latest_transaction_batches()).await
+    }
+
+    async fn transaction_batch_signatures(
+        &self,
+        nonce: u64,
+        token_contract: EthAddress,
+    ) -> Result<Vec<BatchConfirmResponse>, GravityError> {
+        self.refresh().await;
+        self.pool
+            .call(|s| s.transaction_batch_signatures(nonce, token_contract))
+            .await
+    }
+
+    async fn attestations(&self) -> Result<Vec<Attestation>, GravityError> {
+        self.refresh().await;
+        self.pool.call(|s| s.attestations()).await
+    }
+
+    async fn pending_ibc_auto_forwards(&self) -> Result<Vec<PendingIbcAutoForward>, GravityError> {
+        self.refresh().await;
+        self.pool.call(|s| s.pending_ibc_auto_forwards()).await
+    }
+
+    async fn last_event_nonce_by_addr(&self, address: String) -> Result<u64, GravityError> {
+        self.refresh().await;
+        self.pool
+            .call(|s| s.last_event_nonce_by_addr(address.clone()))
+            .await
+    }
+
+    async fn denom_to_erc20(&self, denom: String) -> Result<Option<EthAddress>, GravityError> {
+        self.refresh().await;
+        self.pool.call(|s| s.denom_to_erc20(denom.clone())).await
+    }
+
+    async fn total_supply(&self) -> Result<Vec<Coin>, GravityError> {
+        self.refresh().await;
+        self.pool.call(|s| s.total_supply()).await
+    }
+
+    async fn community_pool(&self) -> Result<Vec<Coin>, GravityError> {
+        self.refresh().await;
+        self.pool.call(|s| s.community_pool()).await
+    }
+
+    async fn all_accounts(&self) -> Result<Vec<AccountType>, GravityError> {
+        self.refresh().await;
+        self.pool.call(|s| s.all_accounts()).await
+    }
+
+    async fn balance(&self, address: String, denom: String) -> Result<Option<Coin>, GravityError> {
+        self.refresh().await;
+        self.pool
+            .call(|s| s.balance(address.clone(), denom.clone()))
+            .await
+    }
+
+    async fn delegation_rewards(&self, address: String) -> Result<Vec<DecCoin>, GravityError> {
+        self.refresh().await;
+        self.pool
+            .call(|s| s.delegation_rewards(address.clone()))
+            .await
+    }
+
+    async fn delegations(&self, address: String) -> Result<Vec<DelegationResponse>, GravityError> {
+        self.refresh().await;
+        self.pool.call(|s| s.delegations(address.clone())).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::upstream::fake::FakeEthereum;
+
+    /// Wraps a fake so that it can be switched into a failing state
+    struct Flaky {
+        inner: FakeEthereum,
+        fail: bool,
+    }
+
+    impl Flaky {
+        fn boxed(height: u64, fail: bool) -> Box<dyn EthereumSource> {
+            let mut inner = FakeEthereum::fixture();
+            inner.block_number = height.into();
+            Box::new(Flaky { inner, fail })
+        }
+
+        fn check(&self) -> Result<(), GravityError> {
+            match self.fail {
+                true => Err(GravityError::InvalidBridgeStateError("down".to_string())),
+                false => Ok(()),
+            }
+        }
+    }
+
+    #[async_trait(?Send)]
+    impl EthereumSource for Flaky {
+        async fn block_number(&self) -> Result<Uint256, GravityError> {
+            self.check()?;
+            self.inner.block_number().await
+        }
+        async fn finalized_block_number(&self) -> Result<Uint256, GravityError> {
+            self.check()?;
+            self.inner.finalized_block_number().await
+        }
+        async fn get_logs(
+            &self,
+            start: Uint256,
+            end: Uint256,
+            contract: EthAddress,
+            event: &str,
+        ) -> Result<Vec<Log>, GravityError> {
+            self.check()?;
+            self.inner.get_logs(start, end, contract, event).await
+        }
+        async fn erc20_symbol(&self, erc20: EthAddress) -> Result<String, GravityError> {
+            self.check()?;
+            self.inner.erc20_symbol(erc20).await
+        }
+        async fn erc20_decimals(&self, erc20: EthAddress) -> Result<Uint256, GravityError> {
+            self.check()?;
+            self.inner.erc20_decimals(erc20).await
+        }
+        async fn usdc_price(
+            &self,
+            erc20: EthAddress,
+            amount: Uint256,
+        ) -> Result<Uint256, GravityError> {
+            self.check()?;
+            self.inner.usdc_price(erc20, amount).await
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_fails_over_to_healthy_endpoint() {
+        let eth = FailoverEthereum::new(
+            vec![
+                ("failover-test-a".to_string(), Flaky::boxed(100, true)),
+                ("failover-test-b".to_string(), Flaky::boxed(100, false)),
+            ],
+            3,
+        );
+        assert_eq!(eth.block_number().await.unwrap(), 100u8.into());
+        let a = get_endpoint_health("failover-test-a").unwrap();
+        assert!(a.errors > 0);
+        assert!(a.backoff_until.is_some());
+        // the failing endpoint is now in backoff and ranked last
+        assert_eq!(
+            rank_endpoints(
+                &["failover-test-a".to_string(), "failover-test-b".to_string()],
+                3
+            )[0],
+            "failover-test-b"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_prefers_most_synced_endpoint() {
+        let eth = FailoverEthereum::new(
+            vec![
+                ("synced-test-a".to_string(), Flaky::boxed(90, false)),
+                ("synced-test-b".to_string(), Flaky::boxed(100, false)),
+            ],
+            3,
+        );
+        assert_eq!(eth.block_number().await.unwrap(), 100u8.into());
+        assert_eq!(
+            get_endpoint_health("synced-test-a").unwrap().latest_height,
+            Some(90)
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_all_endpoints_failing_returns_error() {
+        let eth = FailoverEthereum::new(
+            vec![
+                ("down-test-a".to_string(), Flaky::boxed(100, true)),
+                ("down-test-b".to_string(), Flaky::boxed(100, true)),
+            ],
+            3,
+        );
+        assert!(eth.block_number().await.is_err());
+        assert!(FailoverEthereum::new(Vec::new(), 3)
+            .block_number()
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,748 @@
+//! In memory implementations of the upstream traits along with fixture data modeled on mainnet,
+//! these let the collectors and the indexer be tested without network access
+
+use super::{EthereumSource, GravitySource, RawBlock, RawEvent, RawTxResult};
+use crate::gravity_info::InteralAttestation;
+use crate::transactions::database::{open_database, ClaimDetails, CustomClaim};
+use async_trait::async_trait;
+use clarity::abi::derive_signature;
+use clarity::{Address as EthAddress, Uint256};
+use cosmos_sdk_proto_althea::cosmos::base::v1beta1::DecCoin;
+use cosmos_sdk_proto_althea::cosmos::staking::v1beta1::DelegationResponse;
+use cosmos_sdk_proto_althea::cosmos::tx::v1beta1::{AuthInfo, Fee, TxBody, TxRaw};
+use cosmos_sdk_proto_althea::ibc::applications::transfer::v1::MsgTransfer;
+use deep_space::client::types::AccountType;
+use deep_space::client::ChainStatus;
+use deep_space::Coin;
+use gravity_proto::gravity::v1::{
+    Attestation, BatchFees, MsgBatchSendToEthClaim, MsgCancelSendToEth, MsgConfirmBatch,
+    MsgSendToCosmosClaim, MsgSendToEth, MsgValsetConfirm, Params as GravityParams,
+    PendingIbcAutoForward,
+};
+use gravity_utils::error::GravityError;
+use gravity_utils::types::event_signatures::{
+    SENT_TO_COSMOS_EVENT_SIG, TRANSACTION_BATCH_EXECUTED_EVENT_SIG,
+};
+use gravity_utils::types::{BatchConfirmResponse, TransactionBatch};
+use prost::Message;
+use rocksdb::{Options, DB};
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::path::PathBuf;
+use std::sync::Arc;
+use web30::amm::USDC_CONTRACT_ADDRESS;
+use web30::types::Log;
+
+pub const BRIDGE_ADDRESS: &str = "0xa4108aA1Ec4967F8b52220a4f7e94A8201F2D906";
+pub const WETH_ADDRESS: &str = "0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2";
+pub const TRANSFER_EVENT_SIG: &str = "Transfer(address,address,uint256)";
+/// Latest block of the fake Ethereum chain
+pub const ETH_LATEST_BLOCK: u64 = 15_876_600;
+pub const ETH_FINALIZED_BLOCK: u64 = 15_876_536;
+/// Height of the fake Gravity block containing the fixture transactions
+pub const GRAVITY_TX_BLOCK: u64 = 1_005;
+/// Height of the fake Gravity block containing a MsgSendToEth whose tx failed
+pub const GRAVITY_FAILED_TX_BLOCK: u64 = 1_006;
+/// Height of the fake Gravity block cancelling the fixture MsgSendToEth
+pub const GRAVITY_CANCEL_BLOCK: u64 = 1_007;
+/// Outgoing tx id the fake chain assigned the fixture MsgSendToEth
+pub const SEND_TO_ETH_TX_ID: u64 = 1_234;
+/// Height of the fake Gravity block containing an orchestrator's claims for two events
+pub const GRAVITY_CLAIM_BLOCK: u64 = 1_008;
+pub const ORCHESTRATOR_ADDRESS: &str = "gravity1hcdh7yh5q9y3xr0yd6ruhhpjkwrqj3qnqrgyy2";
+/// Event nonce of the fixture MsgSendToCosmosClaim, the MsgBatchSendToEthClaim follows it
+pub const CLAIM_EVENT_NONCE: u64 = 18_500;
+/// Height of the fake Gravity block containing the orchestrator's batch and valset confirms
+pub const GRAVITY_CONFIRM_BLOCK: u64 = 1_009;
+pub const GRAVITY_LATEST_BLOCK: u64 = 1_010;
+
+/// A database in the temp dir named after the test, whatever an earlier run that panicked
+/// left there is destroyed before opening and the database is destroyed again on drop
+pub struct TestDb {
+    db: Option<Arc<DB>>,
+    path: PathBuf,
+}
+
+impl TestDb {
+    pub fn open(name: &str) -> TestDb {
+        let path = std::env::temp_dir().join(format!("gravity_info_test_{}", name));
+        if path.exists() {
+            DB::destroy(&Options::default(), &path).unwrap();
+        }
+        let db = open_database(&path).unwrap();
+        TestDb {
+            db: Some(Arc::new(db)),
+            path,
+        }
+    }
+
+    /// For code that shares the database with other threads
+    pub fn shared(&self) -> Arc<DB> {
+        self.db.clone().unwrap()
+    }
+}
+
+impl Deref for TestDb {
+    type Target = DB;
+
+    fn deref(&self) -> &DB {
+        self.db.as_ref().unwrap()
+    }
+}
+
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        // the database has to be closed before it can be destroyed
+        self.db.take();
+        if let Err(e) = DB::destroy(&Options::default(), &self.path) {
+            eprintln!("Failed to destroy {} {:?}", self.path.display(), e);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FakeErc20 {
+    pub symbol: String,
+    pub decimals: Uint256,
+    /// The USDC price of one whole token, None if there is no pool
+    pub usdc_price: Option<Uint256>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FakeEthereum {
+    pub block_number: Uint256,
+    pub finalized_block_number: Uint256,
+    pub logs: Vec<Log>,
+    pub erc20s: HashMap<EthAddress, FakeErc20>,
+}
+
+#[async_trait(?Send)]
+impl EthereumSource for FakeEthereum {
+    async fn block_number(&self) -> Result<Uint256, GravityError> {
+        Ok(self.block_number)
+    }
+
+    async fn finalized_block_number(&self) -> Result<Uint256, GravityError> {
+        Ok(self.finalized_block_number)
+    }
+
+    async fn get_logs(
+        &self,
+        start: Uint256,
+        end: Uint256,
+        contract: EthAddress,
+        event: &str,
+    ) -> Result<Vec<Log>, GravityError> {
+        let topic = derive_signature(event).unwrap();
+        Ok(self
+            .logs
+            .iter()
+            .filter(|l| {
+                let height = l.block_number.unwrap();
+                l.address == contract
+                    && l.topics[0][..] == topic[..]
+                    && height >= start
+                    && height <= end
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn erc20_symbol(&self, erc20: EthAddress) -> Result<String, GravityError> {
+        Ok(self.erc20(erc20)?.symbol.clone())
+    }
+
+    async fn erc20_decimals(&self, erc20: EthAddress) -> Result<Uint256, GravityError> {
+        Ok(self.erc20(erc20)?.decimals)
+    }
+
+    async fn usdc_price(
+        &self,
+        erc20: EthAddress,
+        amount: Uint256,
+    ) -> Result<Uint256, GravityError> {
+        let token = self.erc20(erc20)?;
+        let decimals: u32 = token.decimals.to_string().parse().unwrap();
+        match token.usdc_price {
+            Some(price) => Ok(price * amount / 10u128.pow(decimals).into()),
+            None => Err(GravityError::InvalidBridgeStateError(format!(
+                "No pool for {}",
+                erc20
+            ))),
+        }
+    }
+}
+
+impl FakeEthereum {
+    fn erc20(&self, erc20: EthAddress) -> Result<&FakeErc20, GravityError> {
+        self.erc20s
+            .get(&erc20)
+            .ok_or_else(|| GravityError::InvalidBridgeStateError(format!("No erc20 {}", erc20)))
+    }
+
+    /// Two deposits (one finalized, one not), an executed USDC batch, USDC flowing in
+    /// and out of the bridge and metadata for USDC and WETH
+    pub fn fixture() -> Self {
+        let bridge: EthAddress = BRIDGE_ADDRESS.parse().unwrap();
+        let weth: EthAddress = WETH_ADDRESS.parse().unwrap();
+        let usdc = *USDC_CONTRACT_ADDRESS;
+        let user: EthAddress = "0xb5e452a90280A978aA8DAe4306F960167c7C528A"
+            .parse()
+            .unwrap();
+
+        let mut erc20s = HashMap::new();
+        erc20s.insert(
+            usdc,
+            FakeErc20 {
+                symbol: "USDC".to_string(),
+                decimals: 6u8.into(),
+                usdc_price: Some(1_000_000u32.into()),
+            },
+        );
+        erc20s.insert(
+            weth,
+            FakeErc20 {
+                symbol: "WETH".to_string(),
+                decimals: 18u8.into(),
+                usdc_price: Some(1_300_000_000u64.into()),
+            },
+        );
+
+        let logs = vec![
+            send_to_cosmos_log(
+                bridge,
+                weth,
+                user,
+                "gravity1xq7j6pr0zphuq6elxmrg98zkm57u36pvz2uwcc",
+                200_000_000_000_000_000,
+                19443,
+                15_876_508,
+            ),
+            send_to_cosmos_log(
+                bridge,
+                weth,
+                user,
+                "gravity1apkwuud8qdkw3nectycl7d46j5jvqs4kq8nhhf",
+                340_000_000_000_000_000,
+                19444,
+                15_876_578,
+            ),
+            batch_executed_log(bridge, usdc, 6312, 19315, 15_876_100),
+            // 1000 USDC in, 250 USDC out
+            transfer_log(usdc, user, bridge, 1_000_000_000, 15_876_550),
+            transfer_log(usdc, bridge, user, 250_000_000, 15_876_560),
+        ];
+
+        FakeEthereum {
+            block_number: ETH_LATEST_BLOCK.into(),
+            finalized_block_number: ETH_FINALIZED_BLOCK.into(),
+            logs,
+            erc20s,
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct FakeGravity {
+    pub blocks: Vec<RawBlock>,
+    /// Keyed by uppercase hex tx hash
+    pub tx_results: HashMap<String, RawTxResult>,
+    /// Hashes of txs whose result the node fails to return
+    pub unavailable_tx_results: Vec<String>,
+    pub params: GravityParams,
+    pub batch_fees: Vec<BatchFees>,
+    pub batches: Vec<TransactionBatch>,
+    pub batch_signatures: Vec<BatchConfirmResponse>,
+    pub attestations: Vec<Attestation>,
+    pub pending_ibc_auto_forwards: Vec<PendingIbcAutoForward>,
+    /// Keyed by orchestrator address, querying any other address fails
+    pub last_event_nonces: HashMap<String, u64>,
+    pub denom_to_erc20: HashMap<String, EthAddress>,
+    pub total_supply: Vec<Coin>,
+    pub community_pool: Vec<Coin>,
+    pub accounts: Vec<AccountType>,
+    pub balances: HashMap<String, Coin>,
+    pub delegation_rewards: HashMap<String, Vec<DecCoin>>,
+    pub delegations: HashMap<String, Vec<DelegationResponse>>,
+}
+
+#[async_trait(?Send)]
+impl GravitySource for FakeGravity {
+    async fn chain_status(&self) -> Result<ChainStatus, GravityError> {
+        Ok(match self.blocks.last() {
+            Some(b) => ChainStatus::Moving {
+                block_height: b.height,
+            },
+            None => ChainStatus::WaitingToStart,
+        })
+    }
+
+    async fn get_block(&self, height: u64) -> Result<Option<RawBlock>, GravityError> {
+        Ok(self.blocks.iter().find(|b| b.height == height).cloned())
+    }
+
+    async fn get_block_range(&self, start: u64, end: u64) -> Result<Vec<RawBlock>, GravityError> {
+        Ok(self
+            .blocks
+            .iter()
+            .filter(|b| b.height >= start && b.height <= end)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_tx_result(&self, tx_hash: String) -> Result<Option<RawTxResult>, GravityError> {
+        if self.unavailable_tx_results.contains(&tx_hash) {
+            return Err(GravityError::InvalidBridgeStateError(format!(
+                "No result for {}",
+                tx_hash
+            )));
+        }
+        Ok(self.tx_results.get(&tx_hash).cloned())
+    }
+
+    async fn params(&self) -> Result<GravityParams, GravityError> {
+        Ok(self.params.clone())
+    }
+
+    async fn pending_batch_fees(&self) -> Result<Vec<BatchFees>, GravityError> {
+        Ok(self.batch_fees.clone())
+    }
+
+    async fn latest_transaction_batches(&self) -> Result<Vec<TransactionBatch>, GravityError> {
+        Ok(self.batches.clone())
+    }
+
+    async fn transaction_batch_signatures(
+        &self,
+        _nonce: u64,
+        _token_contract: EthAddress,
+    ) -> Result<Vec<BatchConfirmResponse>, GravityError> {
+        Ok(self.batch_signatures.clone())
+    }
+
+    async fn attestations(&self) -> Result<Vec<Attestation>, GravityError> {
+        Ok(self.attestations.clone())
+    }
+
+    async fn pending_ibc_auto_forwards(&self) -> Result<Vec<PendingIbcAutoForward>, GravityError> {
+        Ok(self.pending_ibc_auto_forwards.clone())
+    }
+
+    async fn last_event_nonce_by_addr(&self, address: String) -> Result<u64, GravityError> {
+        self.last_event_nonces
+            .get(&address)
+            .cloned()
+            .ok_or_else(|| {
+                GravityError::InvalidBridgeStateError(format!("No orchestrator {}", address))
+            })
+    }
+
+    async fn denom_to_erc20(&self, denom: String) -> Result<Option<EthAddress>, GravityError> {
+        Ok(self.denom_to_erc20.get(&denom).cloned())
+    }
+
+    async fn total_supply(&self) -> Result<Vec<Coin>, GravityError> {
+        Ok(self.total_supply.clone())
+    }
+
+    async fn community_pool(&self) -> Result<Vec<Coin>, GravityError> {
+        Ok(self.community_pool.clone())
+    }
+
+    async fn all_accounts(&self) -> Result<Vec<AccountType>, GravityError> {
+        Ok(self.accounts.clone())
+    }
+
+    async fn balance(&self, address: String, _denom: String) -> Result<Option<Coin>, GravityError> {
+        Ok(self.balances.get(&address).cloned())
+    }
+
+    async fn delegation_rewards(&self, address: String) -> Result<Vec<DecCoin>, GravityError> {
+        Ok(self
+            .delegation_rewards
+            .get(&address)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn delegations(&self, address: String) -> Result<Vec<DelegationResponse>, GravityError> {
+        Ok(self.delegations.get(&address).cloned().unwrap_or_default())
+    }
+}
+
+impl FakeGravity {
+    /// Mainnet params, a pending USDC batch fee, one observed attestation, supply of GRAV and
+    /// USDC and a short chain with a MsgSendToEth and an IBC MsgTransfer at `GRAVITY_TX_BLOCK`,
+    /// the MsgSendToEth is cancelled at `GRAVITY_CANCEL_BLOCK`. Another MsgSendToEth at
+    /// `GRAVITY_FAILED_TX_BLOCK` failed for lack of funds. An orchestrator claims two Ethereum
+    /// events at `GRAVITY_CLAIM_BLOCK` and signs a batch and a valset at `GRAVITY_CONFIRM_BLOCK`.
+    pub fn fixture() -> Self {
+        let usdc = USDC_CONTRACT_ADDRESS.to_string();
+        let params = GravityParams {
+            bridge_ethereum_address: BRIDGE_ADDRESS.to_string(),
+            gravity_id: "gravity-bridge-mainnet".to_string(),
+            bridge_chain_id: 1,
+            bridge_active: true,
+            average_block_time: 6282,
+            average_ethereum_block_time: 12020,
+            target_batch_timeout: 7_200_000,
+            signed_valsets_window: 10000,
+            signed_batches_window: 10000,
+            signed_logic_calls_window: 10000,
+            unbond_slashing_valsets_window: 10000,
+            min_chain_fee_basis_points: 2,
+            ..Default::default()
+        };
+
+        let mut blocks: Vec<RawBlock> = (1_000..=GRAVITY_LATEST_BLOCK)
+            .map(|height| RawBlock {
+                height,
+                time: 1_700_000_000 + height as i64 * 6,
+                txs: Vec::new(),
+            })
+            .collect();
+        let send_to_eth = MsgSendToEth {
+            sender: "gravity1xq7j6pr0zphuq6elxmrg98zkm57u36pvz2uwcc".to_string(),
+            eth_dest: "0xf0f08f640d5553e79b91296dba6c3f10521e5174".to_string(),
+            amount: Some(coin(&format!("gravity{}", usdc), 89_200_000).into()),
+            bridge_fee: Some(coin(&format!("gravity{}", usdc), 3_200_000).into()),
+            chain_fee: Some(coin(&format!("gravity{}", usdc), 17_840).into()),
+        };
+        let ibc_transfer = MsgTransfer {
+            source_port: "transfer".to_string(),
+            source_channel: "channel-24".to_string(),
+            token: Some(coin("ugraviton", 150_000_000).into()),
+            sender: "gravity1apkwuud8qdkw3nectycl7d46j5jvqs4kq8nhhf".to_string(),
+            receiver: "persistence1ac05mw63eury6arcux7u2qtxwxq68qvefxqczm".to_string(),
+            timeout_timestamp: 1_700_010_000_000_000_000,
+            ..Default::default()
+        };
+        let tx_block = blocks
+            .iter_mut()
+            .find(|b| b.height == GRAVITY_TX_BLOCK)
+            .unwrap();
+        let send_tx = encode_tx(vec![any(
+            "/gravity.v1.MsgSendToEth",
+            send_to_eth.encode_to_vec(),
+        )]);
+        let mut tx_results = HashMap::new();
+        tx_results.insert(
+            tx_hash(&send_tx),
+            RawTxResult {
+                gas_wanted: 200_000,
+                gas_used: 120_000,
+                events: vec![RawEvent {
+                    msg_index: 0,
+                    kind: "gravity.v1.EventOutgoingTxId".to_string(),
+                    attributes: vec![
+                        ("message".to_string(), "\"send_to_eth\"".to_string()),
+                        ("tx_id".to_string(), format!("\"{}\"", SEND_TO_ETH_TX_ID)),
+                    ],
+                }],
+                ..Default::default()
+            },
+        );
+        tx_block.txs.push(send_tx);
+        tx_block.txs.push(encode_tx(vec![any(
+            "/ibc.applications.transfer.v1.MsgTransfer",
+            ibc_transfer.encode_to_vec(),
+        )]));
+        let failed_tx = encode_tx_with_fee(
+            vec![any("/gravity.v1.MsgSendToEth", send_to_eth.encode_to_vec())],
+            vec![coin("ugraviton", 5_000)],
+        );
+        tx_results.insert(
+            tx_hash(&failed_tx),
+            RawTxResult {
+                code: 5,
+                raw_log: "failed to execute message; message index: 0: 89200000 is smaller than 92417840: insufficient funds".to_string(),
+                gas_wanted: 200_000,
+                gas_used: 65_000,
+                events: Vec::new(),
+            },
+        );
+        blocks
+            .iter_mut()
+            .find(|b| b.height == GRAVITY_FAILED_TX_BLOCK)
+            .unwrap()
+            .txs
+            .push(failed_tx);
+        let cancel = MsgCancelSendToEth {
+            transaction_id: SEND_TO_ETH_TX_ID,
+            sender: send_to_eth.sender.clone(),
+        };
+        let cancel_tx = encode_tx(vec![any(
+            "/gravity.v1.MsgCancelSendToEth",
+            cancel.encode_to_vec(),
+        )]);
+        tx_results.insert(tx_hash(&cancel_tx), RawTxResult::default());
+        blocks
+            .iter_mut()
+            .find(|b| b.height == GRAVITY_CANCEL_BLOCK)
+            .unwrap()
+            .txs
+            .push(cancel_tx);
+        let send_to_cosmos_claim = MsgSendToCosmosClaim {
+            event_nonce: CLAIM_EVENT_NONCE,
+            eth_block_height: ETH_FINALIZED_BLOCK - 100,
+            token_contract: usdc.clone(),
+            amount: "1000000000".to_string(),
+            ethereum_sender: "0xf0f08f640d5553e79b91296dba6c3f10521e5174".to_string(),
+            cosmos_receiver: send_to_eth.sender.clone(),
+            orchestrator: ORCHESTRATOR_ADDRESS.to_string(),
+        };
+        let batch_claim = MsgBatchSendToEthClaim {
+            event_nonce: CLAIM_EVENT_NONCE + 1,
+            eth_block_height: ETH_FINALIZED_BLOCK - 50,
+            batch_nonce: 1_200,
+            token_contract: usdc.clone(),
+            orchestrator: ORCHESTRATOR_ADDRESS.to_string(),
+        };
+        let claim_tx = encode_tx(vec![
+            any(
+                "/gravity.v1.MsgSendToCosmosClaim",
+                send_to_cosmos_claim.encode_to_vec(),
+            ),
+            any(
+                "/gravity.v1.MsgBatchSendToEthClaim",
+                batch_claim.encode_to_vec(),
+            ),
+        ]);
+        tx_results.insert(tx_hash(&claim_tx), RawTxResult::default());
+        blocks
+            .iter_mut()
+            .find(|b| b.height == GRAVITY_CLAIM_BLOCK)
+            .unwrap()
+            .txs
+            .push(claim_tx);
+        let batch_confirm = MsgConfirmBatch {
+            nonce: 1_200,
+            token_contract: usdc.clone(),
+            eth_signer: "0x5c3a8f2bd4b2e6b5c2e0b6ad6e7f3a7b4c1d9e0f".to_string(),
+            orchestrator: ORCHESTRATOR_ADDRESS.to_string(),
+            signature: "0xsignature".to_string(),
+        };
+        let valset_confirm = MsgValsetConfirm {
+            nonce: 50,
+            orchestrator: ORCHESTRATOR_ADDRESS.to_string(),
+            eth_address: batch_confirm.eth_signer.clone(),
+            signature: "0xsignature".to_string(),
+        };
+        let confirm_tx = encode_tx(vec![
+            any("/gravity.v1.MsgConfirmBatch", batch_confirm.encode_to_vec()),
+            any(
+                "/gravity.v1.MsgValsetConfirm",
+                valset_confirm.encode_to_vec(),
+            ),
+        ]);
+        tx_results.insert(tx_hash(&confirm_tx), RawTxResult::default());
+        blocks
+            .iter_mut()
+            .find(|b| b.height == GRAVITY_CONFIRM_BLOCK)
+            .unwrap()
+            .txs
+            .push(confirm_tx);
+
+        FakeGravity {
+            blocks,
+            tx_results,
+            params,
+            batch_fees: vec![BatchFees {
+                token: usdc.clone(),
+                total_fees: "10000000000".to_string(),
+                tx_count: 1,
+                ..Default::default()
+            }],
+            attestations: vec![Attestation {
+                observed: true,
+                votes: vec!["gravityvaloper1".to_string(); 3],
+                height: 4_303_597,
+                claim: Some(any(
+                    "/gravity.v1.MsgSendToCosmosClaim",
+                    send_to_cosmos_claim.encode_to_vec(),
+                )),
+            }],
+            total_supply: vec![
+                coin("ugraviton", 2_489_386_289_699_730),
+                coin(&format!("gravity{}", usdc), 5_000_000_000),
+            ],
+            community_pool: vec![coin("ugraviton", 938_460_578_037_767)],
+            ..Default::default()
+        }
+    }
+}
+
+pub fn coin(denom: &str, amount: u128) -> Coin {
+    Coin {
+        denom: denom.to_string(),
+        amount: amount.into(),
+    }
+}
+
+pub fn any(type_url: &str, value: Vec<u8>) -> prost_types::Any {
+    prost_types::Any {
+        type_url: type_url.to_string(),
+        value,
+    }
+}
+
+/// Encodes messages into `TxRaw` bytes as they appear in a block
+pub fn encode_tx(messages: Vec<prost_types::Any>) -> Vec<u8> {
+    encode_tx_with_fee(messages, Vec::new())
+}
+
+pub fn encode_tx_with_fee(messages: Vec<prost_types::Any>, fee: Vec<Coin>) -> Vec<u8> {
+    let body = TxBody {
+        messages,
+        ..Default::default()
+    };
+    let auth_info = AuthInfo {
+        fee: Some(Fee {
+            amount: fee.into_iter().map(|c| c.into()).collect(),
+            gas_limit: 200_000,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    TxRaw {
+        body_bytes: body.encode_to_vec(),
+        auth_info_bytes: auth_info.encode_to_vec(),
+        ..Default::default()
+    }
+    .encode_to_vec()
+}
+
+/// The hash a Gravity node reports for a tx, as computed by the indexer
+pub fn tx_hash(tx: &[u8]) -> String {
+    sha256::digest(tx).to_uppercase()
+}
+
+/// An attestation of `event_nonce` that the validators have observed
+pub fn observed(event_nonce: u64) -> InteralAttestation {
+    InteralAttestation {
+        height: 1_000,
+        observed: true,
+        votes: 10,
+        event_nonce: Some(event_nonce),
+    }
+}
+
+/// A claim by `orchestrator` of an event in Ethereum block `eth_block_height`
+pub fn claim(
+    event_nonce: u64,
+    orchestrator: &str,
+    timestamp: i64,
+    eth_block_height: u64,
+    details: ClaimDetails,
+) -> CustomClaim {
+    CustomClaim {
+        orchestrator: orchestrator.to_string(),
+        event_nonce,
+        eth_block_height,
+        block_number: 1_000 + event_nonce,
+        timestamp,
+        tx_hash: "ABCD".to_string(),
+        details,
+    }
+}
+
+/// Left pads `bytes` into a 32 byte abi word
+fn word(bytes: &[u8]) -> Vec<u8> {
+    let mut out = vec![0u8; 32 - bytes.len()];
+    out.extend_from_slice(bytes);
+    out
+}
+
+fn address_word(address: EthAddress) -> Vec<u8> {
+    word(&hex::decode(address.to_string().trim_start_matches("0x")).unwrap())
+}
+
+fn uint_word(value: u128) -> Vec<u8> {
+    word(&value.to_be_bytes())
+}
+
+fn hex_data(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Builds a log the way an Ethereum node would return it over json rpc
+pub fn make_log(
+    contract: EthAddress,
+    event: &str,
+    indexed: Vec<Vec<u8>>,
+    data: Vec<u8>,
+    block: u64,
+) -> Log {
+    let mut topics = vec![hex_data(&derive_signature(event).unwrap())];
+    topics.extend(indexed.iter().map(|t| hex_data(t)));
+    serde_json::from_value(serde_json::json!({
+        "address": contract.to_string(),
+        "topics": topics,
+        "data": hex_data(&data),
+        "blockNumber": format!("0x{:x}", block),
+        "blockHash": hex_data(&uint_word(block as u128)),
+        "transactionHash": hex_data(&uint_word(block as u128 + 1)),
+        "transactionIndex": "0x0",
+        "logIndex": "0x0",
+        "removed": false,
+    }))
+    .unwrap()
+}
+
+pub fn send_to_cosmos_log(
+    bridge: EthAddress,
+    erc20: EthAddress,
+    sender: EthAddress,
+    destination: &str,
+    amount: u128,
+    event_nonce: u64,
+    block: u64,
+) -> Log {
+    // destination is a dynamic string so the first word is its offset
+    let mut data = uint_word(3 * 32);
+    data.extend(uint_word(amount));
+    data.extend(uint_word(event_nonce.into()));
+    data.extend(uint_word(destination.len() as u128));
+    let mut destination = destination.as_bytes().to_vec();
+    destination.resize(destination.len().div_ceil(32) * 32, 0);
+    data.extend(destination);
+    make_log(
+        bridge,
+        SENT_TO_COSMOS_EVENT_SIG,
+        vec![address_word(erc20), address_word(sender)],
+        data,
+        block,
+    )
+}
+
+pub fn batch_executed_log(
+    bridge: EthAddress,
+    erc20: EthAddress,
+    batch_nonce: u64,
+    event_nonce: u64,
+    block: u64,
+) -> Log {
+    make_log(
+        bridge,
+        TRANSACTION_BATCH_EXECUTED_EVENT_SIG,
+        vec![uint_word(batch_nonce.into()), address_word(erc20)],
+        uint_word(event_nonce.into()),
+        block,
+    )
+}
+
+pub fn transfer_log(
+    erc20: EthAddress,
+    from: EthAddress,
+    to: EthAddress,
+    amount: u128,
+    block: u64,
+) -> Log {
+    make_log(
+        erc20,
+        TRANSFER_EVENT_SIG,
+        vec![address_word(from), address_word(to)],
+        uint_word(amount),
+        block,
+    )
+}
@@ -0,0 +1,344 @@
+//! Implementations of the upstream traits backed by real Ethereum and Gravity full nodes
+
+use super::{EthereumSource, GravitySource, RawBlock, RawEvent, RawTxResult};
+use async_trait::async_trait;
+use clarity::{Address as EthAddress, Uint256};
+use cosmos_gravity::query::{
+    get_attestations, get_gravity_params, get_latest_transaction_batches, get_pending_batch_fees,
+    get_transaction_batch_signatures,
+};
+use cosmos_sdk_proto_althea::cosmos::bank::v1beta1::query_client::QueryClient as BankQueryClient;
+use cosmos_sdk_proto_althea::cosmos::bank::v1beta1::QueryBalanceRequest;
+use cosmos_sdk_proto_althea::cosmos::base::v1beta1::DecCoin;
+use cosmos_sdk_proto_althea::cosmos::distribution::v1beta1::query_client::QueryClient as DistQueryClient;
+use cosmos_sdk_proto_althea::cosmos::distribution::v1beta1::QueryDelegationTotalRewardsRequest;
+use cosmos_sdk_proto_althea::cosmos::staking::v1beta1::query_client::QueryClient as StakingQueryClient;
+use cosmos_sdk_proto_althea::cosmos::staking::v1beta1::{
+    DelegationResponse, QueryDelegatorDelegationsRequest,
+};
+use cosmos_sdk_proto_althea::cosmos::tx::v1beta1::service_client::ServiceClient as TxServiceClient;
+use cosmos_sdk_proto_althea::cosmos::tx::v1beta1::GetTxRequest;
+use cosmos_sdk_proto_althea::tendermint::types::Block;
+use deep_space::client::types::AccountType;
+use deep_space::client::{ChainStatus, PAGE};
+use deep_space::error::CosmosGrpcError;
+use deep_space::{Coin, Contact};
+use futures::future::join;
+use gravity_proto::gravity::v1::query_client::QueryClient as GravityQueryClient;
+use gravity_proto::gravity::v1::{
+    Attestation, BatchFees, Params as GravityParams, PendingIbcAutoForward,
+    QueryDenomToErc20Request, QueryLastEventNonceByAddrRequest, QueryPendingIbcAutoForwards,
+};
+use gravity_utils::error::GravityError;
+use gravity_utils::types::{BatchConfirmResponse, TransactionBatch};
+use std::time::Duration;
+use tonic::transport::channel::Channel;
+use tonic::transport::Endpoint;
+use web30::amm::USDC_CONTRACT_ADDRESS;
+use web30::client::Web3;
+use web30::types::Log;
+
+/// Address used as the sender for read only contract calls
+const QUERY_SENDER: &str = "0x388C818CA8B9251b393131C08a736A67ccB19297";
+
+pub struct Web3Source {
+    web3: Web3,
+    query_sender: EthAddress,
+}
+
+impl Web3Source {
+    pub fn new(url: &str, timeout: Duration) -> Self {
+        Web3Source {
+            web3: Web3::new(url, timeout),
+            query_sender: QUERY_SENDER.parse().unwrap(),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl EthereumSource for Web3Source {
+    async fn block_number(&self) -> Result<Uint256, GravityError> {
+        Ok(self.web3.eth_block_number().await?)
+    }
+
+    async fn finalized_block_number(&self) -> Result<Uint256, GravityError> {
+        Ok(self.web3.eth_get_finalized_block().await?.number)
+    }
+
+    async fn get_logs(
+        &self,
+        start: Uint256,
+        end: Uint256,
+        contract: EthAddress,
+        event: &str,
+    ) -> Result<Vec<Log>, GravityError> {
+        Ok(self
+            .web3
+            .check_for_events(start, Some(end), vec![contract], vec![event])
+            .await?)
+    }
+
+    async fn erc20_symbol(&self, erc20: EthAddress) -> Result<String, GravityError> {
+        Ok(self.web3.get_erc20_symbol(erc20, self.query_sender).await?)
+    }
+
+    async fn erc20_decimals(&self, erc20: EthAddress) -> Result<Uint256, GravityError> {
+        Ok(self
+            .web3
+            .get_erc20_decimals(erc20, self.query_sender)
+            .await?)
+    }
+
+    async fn usdc_price(
+        &self,
+        erc20: EthAddress,
+        amount: Uint256,
+    ) -> Result<Uint256, GravityError> {
+        let pricev3 = self.web3.get_uniswap_v3_price_with_retries(
+            self.query_sender,
+            erc20,
+            *USDC_CONTRACT_ADDRESS,
+            amount,
+            None,
+            None,
+        );
+        let pricev2 = self.web3.get_uniswap_v2_price(
+            self.query_sender,
+            erc20,
+            *USDC_CONTRACT_ADDRESS,
+            amount,
+            None,
+        );
+        // prefer the v3 price when both pools exist
+        match join(pricev3, pricev2).await {
+            (Ok(r), _) => Ok(r),
+            (_, Ok(r)) => Ok(r),
+            (Err(e), Err(_)) => Err(e.into()),
+        }
+    }
+}
+
+/// A Gravity full node accessed over GRPC, the query clients share a single connection
+/// and are cloned for each request since they require mutable access
+pub struct GrpcGravitySource {
+    contact: Contact,
+    gravity: GravityQueryClient<Channel>,
+    bank: BankQueryClient<Channel>,
+    distribution: DistQueryClient<Channel>,
+    staking: StakingQueryClient<Channel>,
+    tx: TxServiceClient<Channel>,
+}
+
+impl GrpcGravitySource {
+    /// Does not connect until the first request, so an endpoint that is down at startup can
+    /// still be used once it comes back
+    pub fn new(url: &str, prefix: &str, timeout: Duration) -> Result<Self, GravityError> {
+        let contact = Contact::new(url, timeout, prefix)?;
+        let channel = Endpoint::new(url.to_string())
+            .map_err(CosmosGrpcError::from)?
+            .timeout(timeout)
+            .connect_lazy();
+        Ok(GrpcGravitySource {
+            contact,
+            gravity: GravityQueryClient::new(channel.clone()),
+            bank: BankQueryClient::new(channel.clone()),
+            distribution: DistQueryClient::new(channel.clone()),
+            staking: StakingQueryClient::new(channel.clone()),
+            tx: TxServiceClient::new(channel),
+        })
+    }
+}
+
+/// Fails on a block without a header or time instead of panicking the indexer
+fn raw_block(height: u64, block: Block) -> Result<RawBlock, GravityError> {
+    let (block_height, time) = match block.header.and_then(|h| Some((h.height, h.time?))) {
+        Some(v) => v,
+        None => {
+            return Err(GravityError::InvalidBridgeStateError(format!(
+                "Block {} has no header or time",
+                height
+            )))
+        }
+    };
+    Ok(RawBlock {
+        height: block_height as u64,
+        time: time.seconds,
+        txs: block.data.map(|d| d.txs).unwrap_or_default(),
+    })
+}
+
+#[async_trait(?Send)]
+impl GravitySource for GrpcGravitySource {
+    async fn chain_status(&self) -> Result<ChainStatus, GravityError> {
+        Ok(self.contact.get_chain_status().await?)
+    }
+
+    async fn get_block(&self, height: u64) -> Result<Option<RawBlock>, GravityError> {
+        let block = self.contact.get_block(height).await?;
+        block.map(|b| raw_block(height, b)).transpose()
+    }
+
+    async fn get_block_range(&self, start: u64, end: u64) -> Result<Vec<RawBlock>, GravityError> {
+        let blocks = self.contact.get_block_range(start, end).await?;
+        if blocks.len() as u64 != (end + 1).saturating_sub(start) {
+            return Err(GravityError::InvalidBridgeStateError(format!(
+                "Got {} blocks for range {} to {}",
+                blocks.len(),
+                start,
+                end
+            )));
+        }
+        (start..=end)
+            .zip(blocks)
+            .map(|(height, block)| {
+                let block = block.ok_or_else(|| {
+                    GravityError::InvalidBridgeStateError(format!("Block {} is missing", height))
+                })?;
+                raw_block(height, block)
+            })
+            .collect()
+    }
+
+    async fn get_tx_result(&self, tx_hash: String) -> Result<Option<RawTxResult>, GravityError> {
+        let res = match self.tx.clone().get_tx(GetTxRequest { hash: tx_hash }).await {
+            Ok(v) => v.into_inner(),
+            Err(e) if e.code() == tonic::Code::NotFound => return Ok(None),
+            Err(e) => return Err(CosmosGrpcError::from(e).into()),
+        };
+        Ok(res.tx_response.map(|r| RawTxResult {
+            code: r.code,
+            raw_log: r.raw_log,
+            gas_wanted: r.gas_wanted,
+            gas_used: r.gas_used,
+            // the per message logs carry string attributes on every sdk version, unlike `events`
+            events: r
+                .logs
+                .into_iter()
+                .flat_map(|log| {
+                    let msg_index = log.msg_index;
+                    log.events.into_iter().map(move |e| RawEvent {
+                        msg_index,
+                        kind: e.r#type,
+                        attributes: e.attributes.into_iter().map(|a| (a.key, a.value)).collect(),
+                    })
+                })
+                .collect(),
+        }))
+    }
+
+    async fn params(&self) -> Result<GravityParams, GravityError> {
+        get_gravity_params(&mut self.gravity.clone()).await
+    }
+
+    async fn pending_batch_fees(&self) -> Result<Vec<BatchFees>, GravityError> {
+        Ok(get_pending_batch_fees(&mut self.gravity.clone())
+            .await?
+            .batch_fees)
+    }
+
+    async fn latest_transaction_batches(&self) -> Result<Vec<TransactionBatch>, GravityError> {
+        get_latest_transaction_batches(&mut self.gravity.clone()).await
+    }
+
+    async fn transaction_batch_signatures(
+        &self,
+        nonce: u64,
+        token_contract: EthAddress,
+    ) -> Result<Vec<BatchConfirmResponse>, GravityError> {
+        get_transaction_batch_signatures(&mut self.gravity.clone(), nonce, token_contract).await
+    }
+
+    async fn attestations(&self) -> Result<Vec<Attestation>, GravityError> {
+        get_attestations(&mut self.gravity.clone(), None).await
+    }
+
+    async fn pending_ibc_auto_forwards(&self) -> Result<Vec<PendingIbcAutoForward>, GravityError> {
+        // a limit of zero returns every pending forward
+        let res = self
+            .gravity
+            .clone()
+            .get_pending_ibc_auto_forwards(QueryPendingIbcAutoForwards { limit: 0 })
+            .await
+            .map_err(CosmosGrpcError::from)?;
+        Ok(res.into_inner().pending_ibc_auto_forwards)
+    }
+
+    async fn last_event_nonce_by_addr(&self, address: String) -> Result<u64, GravityError> {
+        let res = self
+            .gravity
+            .clone()
+            .last_event_nonce_by_addr(QueryLastEventNonceByAddrRequest { address })
+            .await
+            .map_err(CosmosGrpcError::from)?;
+        Ok(res.into_inner().event_nonce)
+    }
+
+    async fn denom_to_erc20(&self, denom: String) -> Result<Option<EthAddress>, GravityError> {
+        // the node returns an error for denoms that are not bridged, any other error is a failed
+        // request that the next endpoint may answer
+        match self
+            .gravity
+            .clone()
+            .denom_to_erc20(QueryDenomToErc20Request { denom })
+            .await
+        {
+            Ok(v) => Ok(v.into_inner().erc20.parse().ok()),
+            Err(e)
+                if matches!(
+                    e.code(),
+                    tonic::Code::NotFound | tonic::Code::InvalidArgument
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(CosmosGrpcError::from(e).into()),
+        }
+    }
+
+    async fn total_supply(&self) -> Result<Vec<Coin>, GravityError> {
+        Ok(self.contact.query_total_supply().await?)
+    }
+
+    async fn community_pool(&self) -> Result<Vec<Coin>, GravityError> {
+        Ok(self.contact.query_community_pool().await?)
+    }
+
+    async fn all_accounts(&self) -> Result<Vec<AccountType>, GravityError> {
+        Ok(self.contact.get_all_accounts().await?)
+    }
+
+    async fn balance(&self, address: String, denom: String) -> Result<Option<Coin>, GravityError> {
+        let res = self
+            .bank
+            .clone()
+            .balance(QueryBalanceRequest { address, denom })
+            .await
+            .map_err(CosmosGrpcError::from)?;
+        Ok(res.into_inner().balance.map(|c| c.into()))
+    }
+
+    async fn delegation_rewards(&self, address: String) -> Result<Vec<DecCoin>, GravityError> {
+        let res = self
+            .distribution
+            .clone()
+            .delegation_total_rewards(QueryDelegationTotalRewardsRequest {
+                delegator_address: address,
+            })
+            .await
+            .map_err(CosmosGrpcError::from)?;
+        Ok(res.into_inner().total)
+    }
+
+    async fn delegations(&self, address: String) -> Result<Vec<DelegationResponse>, GravityError> {
+        let res = self
+            .staking
+            .clone()
+            .delegator_delegations(QueryDelegatorDelegationsRequest {
+                delegator_addr: address,
+                pagination: PAGE,
+            })
+            .await
+            .map_err(CosmosGrpcError::from)?;
+        Ok(res.into_inner().delegation_responses)
+    }
+}
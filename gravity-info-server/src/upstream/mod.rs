@@ -0,0 +1,129 @@
+//! Abstractions over the Ethereum and Gravity full nodes this server collects data from. Every collector
+//! takes a `&dyn EthereumSource` and/or `&dyn GravitySource` instead of building its own `Web3`, `Contact`
+//! or `GravityQueryClient`, this lets the same code run against live nodes in production and against the
+//! in memory fakes in `fake` during tests.
+
+use async_trait::async_trait;
+use clarity::{Address as EthAddress, Uint256};
+use cosmos_sdk_proto_althea::cosmos::base::v1beta1::DecCoin;
+use cosmos_sdk_proto_althea::cosmos::staking::v1beta1::DelegationResponse;
+use deep_space::client::types::AccountType;
+use deep_space::client::ChainStatus;
+use deep_space::Coin;
+use gravity_proto::gravity::v1::{
+    Attestation, BatchFees, Params as GravityParams, PendingIbcAutoForward,
+};
+use gravity_utils::error::GravityError;
+use gravity_utils::types::{BatchConfirmResponse, TransactionBatch};
+use web30::types::Log;
+
+pub mod failover;
+pub mod live;
+
+#[cfg(test)]
+pub mod fake;
+
+/// A Gravity block reduced to the parts the transaction indexer reads
+#[derive(Debug, Clone, Default)]
+pub struct RawBlock {
+    pub height: u64,
+    /// Block time in unix seconds
+    pub time: i64,
+    /// Raw protobuf encoded `TxRaw` bytes for every tx in the block
+    pub txs: Vec<Vec<u8>>,
+}
+
+/// An event emitted while executing one message of a tx
+#[derive(Debug, Clone, Default)]
+pub struct RawEvent {
+    /// Index of the message in the tx that emitted this event
+    pub msg_index: u32,
+    pub kind: String,
+    pub attributes: Vec<(String, String)>,
+}
+
+/// The execution result of a Gravity tx reduced to the parts the transaction indexer reads
+#[derive(Debug, Clone, Default)]
+pub struct RawTxResult {
+    /// Zero if the tx succeeded
+    pub code: u32,
+    pub raw_log: String,
+    pub gas_wanted: i64,
+    pub gas_used: i64,
+    pub events: Vec<RawEvent>,
+}
+
+impl RawTxResult {
+    /// The value of the first attribute named `key` emitted by message `msg_index`, typed events
+    /// json encode their values so surrounding quotes are removed
+    pub fn attribute(&self, msg_index: u32, key: &str) -> Option<String> {
+        self.events
+            .iter()
+            .filter(|e| e.msg_index == msg_index)
+            .flat_map(|e| e.attributes.iter())
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.trim_matches('"').to_string())
+    }
+}
+
+/// The latest block height of `gravity`, None if the request failed or the chain is not moving
+pub async fn gravity_height(gravity: &dyn GravitySource) -> Option<u64> {
+    match gravity.chain_status().await {
+        Ok(ChainStatus::Moving { block_height }) => Some(block_height),
+        _ => None,
+    }
+}
+
+/// Everything this server reads from Ethereum
+#[async_trait(?Send)]
+pub trait EthereumSource {
+    async fn block_number(&self) -> Result<Uint256, GravityError>;
+    async fn finalized_block_number(&self) -> Result<Uint256, GravityError>;
+    /// Returns all logs for `event` emitted by `contract` between `start` and `end` inclusive
+    async fn get_logs(
+        &self,
+        start: Uint256,
+        end: Uint256,
+        contract: EthAddress,
+        event: &str,
+    ) -> Result<Vec<Log>, GravityError>;
+    async fn erc20_symbol(&self, erc20: EthAddress) -> Result<String, GravityError>;
+    async fn erc20_decimals(&self, erc20: EthAddress) -> Result<Uint256, GravityError>;
+    /// The amount of USDC `amount` of `erc20` would buy on Uniswap
+    async fn usdc_price(&self, erc20: EthAddress, amount: Uint256)
+        -> Result<Uint256, GravityError>;
+}
+
+/// Everything this server reads from Gravity Bridge
+#[async_trait(?Send)]
+pub trait GravitySource {
+    async fn chain_status(&self) -> Result<ChainStatus, GravityError>;
+    /// Returns None if the node does not have this block, for example if it has been pruned
+    async fn get_block(&self, height: u64) -> Result<Option<RawBlock>, GravityError>;
+    /// Returns the blocks from `start` to `end`, `end` is inclusive. A live node fails if any of
+    /// them is missing, callers still check that every height came back
+    async fn get_block_range(&self, start: u64, end: u64) -> Result<Vec<RawBlock>, GravityError>;
+    /// Returns None if the node has no result for this tx hash, for example if it has been pruned
+    async fn get_tx_result(&self, tx_hash: String) -> Result<Option<RawTxResult>, GravityError>;
+    async fn params(&self) -> Result<GravityParams, GravityError>;
+    async fn pending_batch_fees(&self) -> Result<Vec<BatchFees>, GravityError>;
+    async fn latest_transaction_batches(&self) -> Result<Vec<TransactionBatch>, GravityError>;
+    async fn transaction_batch_signatures(
+        &self,
+        nonce: u64,
+        token_contract: EthAddress,
+    ) -> Result<Vec<BatchConfirmResponse>, GravityError>;
+    async fn attestations(&self) -> Result<Vec<Attestation>, GravityError>;
+    /// Observed deposits to other chains waiting to be sent over IBC, in the order they will be sent
+    async fn pending_ibc_auto_forwards(&self) -> Result<Vec<PendingIbcAutoForward>, GravityError>;
+    /// The nonce of the last Ethereum event claimed by the orchestrator with `address`
+    async fn last_event_nonce_by_addr(&self, address: String) -> Result<u64, GravityError>;
+    /// Returns None if this denom has no ERC20 representation
+    async fn denom_to_erc20(&self, denom: String) -> Result<Option<EthAddress>, GravityError>;
+    async fn total_supply(&self) -> Result<Vec<Coin>, GravityError>;
+    async fn community_pool(&self) -> Result<Vec<Coin>, GravityError>;
+    async fn all_accounts(&self) -> Result<Vec<AccountType>, GravityError>;
+    async fn balance(&self, address: String, denom: String) -> Result<Option<Coin>, GravityError>;
+    async fn delegation_rewards(&self, address: String) -> Result<Vec<DecCoin>, GravityError>;
+    async fn delegations(&self, address: String) -> Result<Vec<DelegationResponse>, GravityError>;
+}
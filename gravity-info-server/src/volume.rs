@@ -14,10 +14,14 @@ use std::{
     thread,
     time::Duration,
 };
-use web30::client::Web3;
 use web30::types::Log;
 
-use crate::gravity_info::{get_erc20_metadata, get_gravity_info, Erc20Metadata, ETH_NODE_RPC};
+use crate::config::get_config;
+use crate::gravity_info::{get_erc20_metadata, get_gravity_info, Erc20Metadata};
+use crate::health::{record_update, Store};
+use crate::metrics::{record_collector_run, VOLUME_COLLECTOR};
+use crate::upstream::failover::FailoverEthereum;
+use crate::upstream::EthereumSource;
 use clarity::Address as EthAddress;
 
 // update once a day
@@ -57,15 +61,17 @@ pub fn bridge_volume_thread() {
     thread::spawn(move || loop {
         let runner = System::new();
         runner.block_on(async move {
-            let web3 = Web3::new(ETH_NODE_RPC, REQUEST_TIMEOUT);
+            let start = Instant::now();
+            let web3 = FailoverEthereum::from_config(&get_config().ethereum, REQUEST_TIMEOUT);
             let metadata = get_erc20_metadata();
             let params = get_gravity_info();
             if let (Some(metadata), Some(params)) = (metadata, params) {
                 let gravity_contract_address = params.params.bridge_ethereum_address;
-                let latest_block = match web3.eth_block_number().await {
+                let latest_block = match web3.block_number().await {
                     Ok(v) => v,
                     Err(e) => {
                         warn!("Failed to get ETH block number with {:?}", e);
+                        record_collector_run(VOLUME_COLLECTOR, start, false);
                         return;
                     }
                 };
@@ -94,11 +100,16 @@ pub fn bridge_volume_thread() {
                     &web3,
                 );
                 info!("Starting volume query");
-                let start = Instant::now();
                 let (daily_volume, weekly_volume, monthly_volume) =
                     join3(daily_volume, weekly_volume, monthly_volume).await;
                 match (daily_volume, weekly_volume, monthly_volume) {
                     (Ok(daily), Ok(weekly), Ok(monthly)) => {
+                        record_update(
+                            Store::Volume,
+                            None,
+                            latest_block.to_string().parse().ok(),
+                            Some(LOOP_TIME),
+                        );
                         set_volume_info(BridgeVolumeNumbers {
                             daily_volume: daily.volume,
                             daily_inflow: daily.inflow,
@@ -114,10 +125,20 @@ pub fn bridge_volume_thread() {
                             "Successfuly updated volume info in {}s!",
                             start.elapsed().as_secs()
                         );
+                        record_collector_run(VOLUME_COLLECTOR, start, true);
+                    }
+                    (Err(e), _, _) => {
+                        warn!("Could not get daily volume {:?}", e);
+                        record_collector_run(VOLUME_COLLECTOR, start, false);
+                    }
+                    (_, Err(e), _) => {
+                        warn!("Could not get weekly volume {:?}", e);
+                        record_collector_run(VOLUME_COLLECTOR, start, false);
+                    }
+                    (_, _, Err(e)) => {
+                        warn!("Could not get monthly volume {:?}", e);
+                        record_collector_run(VOLUME_COLLECTOR, start, false);
                     }
-                    (Err(e), _, _) => warn!("Could not get daily volume {:?}", e),
-                    (_, Err(e), _) => warn!("Could not get weekly volume {:?}", e),
-                    (_, _, Err(e)) => warn!("Could not get monthly volume {:?}", e),
                 }
             }
         });
@@ -143,7 +164,7 @@ async fn get_bridge_volume_for_range(
     ending_block: Uint256,
     metadata: &[Erc20Metadata],
     gravity_contract_address: EthAddress,
-    web3: &Web3,
+    web3: &dyn EthereumSource,
 ) -> Result<BridgeVolume, GravityError> {
     // total volume in usdc
     let mut volume = 0u8.into();
@@ -181,7 +202,7 @@ async fn get_gravity_volume_for_token(
     ending_block: Uint256,
     erc20: &Erc20Metadata,
     gravity_contract_address: EthAddress,
-    web3: &Web3,
+    web3: &dyn EthereumSource,
 ) -> Result<BridgeVolume, GravityError> {
     if let Some(exchange_rate) = erc20.exchange_rate {
         let mut volume: f64 = 0u8.into();
@@ -197,11 +218,11 @@ async fn get_gravity_volume_for_token(
         let mut current_block = starting_block;
         while current_block + blocks_to_search < ending_block {
             let logs = web3
-                .check_for_events(
+                .get_logs(
                     current_block,
-                    Some(current_block + blocks_to_search),
-                    vec![erc20.address],
-                    vec!["Transfer(address,address,uint256)"],
+                    current_block + blocks_to_search,
+                    erc20.address,
+                    "Transfer(address,address,uint256)",
                 )
                 .await?;
 
@@ -213,11 +234,11 @@ async fn get_gravity_volume_for_token(
             current_block += blocks_to_search;
         }
         let logs = web3
-            .check_for_events(
+            .get_logs(
                 current_block,
-                Some(ending_block),
-                vec![erc20.address],
-                vec!["Transfer(address,address,uint256)"],
+                ending_block,
+                erc20.address,
+                "Transfer(address,address,uint256)",
             )
             .await?;
         let (v, i, o) = sum_logs(logs, gravity_contract_address, decimals, exchange_rate)?;
@@ -269,3 +290,32 @@ fn sum_logs(
     }
     Ok((volume, inflow, outflow))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::upstream::fake::{FakeEthereum, BRIDGE_ADDRESS, ETH_LATEST_BLOCK};
+    use web30::amm::USDC_CONTRACT_ADDRESS;
+
+    #[actix_web::test]
+    async fn test_bridge_volume_offline() {
+        let metadata = vec![Erc20Metadata {
+            address: *USDC_CONTRACT_ADDRESS,
+            decimals: 6u8.into(),
+            symbol: "USDC".to_string(),
+            exchange_rate: Some(1_000_000u32.into()),
+        }];
+        let volume = get_bridge_volume_for_range(
+            (ETH_LATEST_BLOCK - 99).into(),
+            ETH_LATEST_BLOCK.into(),
+            &metadata,
+            BRIDGE_ADDRESS.parse().unwrap(),
+            &FakeEthereum::fixture(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(volume.inflow, 1000.0);
+        assert_eq!(volume.outflow, 250.0);
+        assert_eq!(volume.volume, 1250.0);
+    }
+}